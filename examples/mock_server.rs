@@ -0,0 +1,25 @@
+//! Serves a [`MockServer`] on localhost until the process is killed, for the browser tests of the
+//! `wasm` feature.
+//!
+//! ```sh
+//! cargo run --example mock_server -- 127.0.0.1:8536
+//! ```
+
+#[cfg(not(target_arch = "wasm32"))]
+fn main() -> std::io::Result<()> {
+  use lemmy_client::testing::MockServer;
+
+  let addr = std::env::args()
+    .nth(1)
+    .unwrap_or_else(|| "127.0.0.1:8536".to_owned());
+  let server = MockServer::new().start_on(addr)?;
+  println!("Serving a mock Lemmy instance on http://{}", server.addr());
+
+  loop {
+    std::thread::park();
+  }
+}
+
+// Sockets aren't available in WASM.
+#[cfg(target_arch = "wasm32")]
+fn main() {}
@@ -0,0 +1,100 @@
+use std::fmt;
+
+/// A major version of Lemmy's HTTP API, served under `/api/v3` or `/api/v4`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ApiVersion {
+  /// The API of Lemmy 0.19 and earlier.
+  V3,
+  /// The API of Lemmy 1.0 and later.
+  V4,
+}
+
+impl ApiVersion {
+  /// The version of the API served by an instance running the given version of Lemmy, e.g.
+  /// `0.19.5`. Versions that can't be parsed are assumed to be recent.
+  /// ```
+  /// # use lemmy_client::ApiVersion;
+  /// assert_eq!(ApiVersion::from_lemmy_version("0.19.5"), ApiVersion::V3);
+  /// assert_eq!(ApiVersion::from_lemmy_version("1.0.0-beta.2"), ApiVersion::V4);
+  /// ```
+  pub fn from_lemmy_version(version: &str) -> Self {
+    let mut parts = version
+      .trim_start_matches('v')
+      .split(['.', '-', '+'])
+      .map(|part| part.parse::<u32>().ok());
+
+    match (parts.next().flatten(), parts.next().flatten()) {
+      (Some(0), Some(minor)) if minor < 20 => Self::V3,
+      _ => Self::V4,
+    }
+  }
+
+  /// The path the API is served under, relative to the instance's base URL.
+  pub(crate) fn base_path(self) -> &'static str {
+    match self {
+      Self::V3 => "api/v3/",
+      Self::V4 => "api/v4/",
+    }
+  }
+}
+
+impl fmt::Display for ApiVersion {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(match self {
+      Self::V3 => "v3",
+      Self::V4 => "v4",
+    })
+  }
+}
+
+/// Which version of the API a [`LemmyClient`][client] talks to the instance with.
+///
+/// API v3 support is limited to seven authentication endpoints: [`login`][login],
+/// [`logout`][logout], [`reset_password`][reset_password],
+/// [`change_password_after_reset`][change_password_after_reset],
+/// [`change_password`][change_password], [`verify_email`][verify_email] and
+/// [`validate_auth`][validate_auth]. Every other endpoint, including the likes of `list_posts`,
+/// `create_comment` and `search`, fails with
+/// [`ClientError::UnsupportedOnServerVersion`][unsupported] on instances speaking v3, as their
+/// requests or responses differ between the two versions.
+/// ```
+/// # use lemmy_client::{ApiVersionMode, ClientOptions, LemmyClient};
+/// # use lemmy_api_common::account::auth::Login;
+/// async fn login_anywhere(domain: String, login: Login) {
+///   let client = LemmyClient::new(ClientOptions {
+///     api_version: ApiVersionMode::Detect,
+///     ..ClientOptions::new(domain)
+///   });
+///
+///   // Asks the instance for its version first, then logs in on `/api/v3/user/login` or
+///   // `/api/v4/account/auth/login`.
+///   client.login(login).await.unwrap();
+/// }
+/// ```
+///
+/// [client]: crate::LemmyClient
+/// [unsupported]: crate::ClientError::UnsupportedOnServerVersion
+/// [login]: crate::LemmyClient::login
+/// [logout]: crate::LemmyClient::logout
+/// [reset_password]: crate::LemmyClient::reset_password
+/// [change_password_after_reset]: crate::LemmyClient::change_password_after_reset
+/// [change_password]: crate::LemmyClient::change_password
+/// [verify_email]: crate::LemmyClient::verify_email
+/// [validate_auth]: crate::LemmyClient::validate_auth
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiVersionMode {
+  /// Always use the given version.
+  Fixed(ApiVersion),
+  /// Ask the instance for its version before the first request, using whichever API it answers
+  /// on. See [`LemmyClient::detect_api_version`][detect].
+  ///
+  /// [detect]: crate::LemmyClient::detect_api_version
+  Detect,
+}
+
+impl Default for ApiVersionMode {
+  /// Always use [`ApiVersion::V4`].
+  fn default() -> Self {
+    Self::Fixed(ApiVersion::V4)
+  }
+}
@@ -0,0 +1,161 @@
+use std::{error::Error, fmt, str::FromStr};
+use url::Url;
+
+/// Why a URL or domain can't be used as the base of a Lemmy instance's API.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum InvalidBaseUrl {
+  /// The URL could not be parsed.
+  Parse(url::ParseError),
+  /// The URL's scheme is neither `http` nor `https`.
+  UnsupportedScheme(String),
+  /// The URL contains a username or password.
+  Credentials,
+  /// The URL has a query or fragment, which would end up in every request.
+  QueryOrFragment,
+  /// A domain contains more than a host and port, e.g. a scheme or a path.
+  InvalidDomain(String),
+}
+
+impl fmt::Display for InvalidBaseUrl {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Parse(e) => write!(f, "invalid URL: {e}"),
+      Self::UnsupportedScheme(scheme) => {
+        write!(
+          f,
+          "unsupported scheme `{scheme}`, expected `http` or `https`"
+        )
+      }
+      Self::Credentials => f.write_str("URL must not contain credentials"),
+      Self::QueryOrFragment => f.write_str("URL must not have a query or fragment"),
+      Self::InvalidDomain(domain) => write!(
+        f,
+        "`{domain}` is not a domain, use `ClientOptions::from_url` for full URLs"
+      ),
+    }
+  }
+}
+
+impl Error for InvalidBaseUrl {
+  fn source(&self) -> Option<&(dyn Error + 'static)> {
+    match self {
+      Self::Parse(e) => Some(e),
+      _ => None,
+    }
+  }
+}
+
+/// The URL a Lemmy instance is served at, which API routes are resolved against.
+///
+/// It may include a port and a path prefix for instances behind a reverse proxy sub-path. The
+/// URL is validated when a `BaseUrl` is created, so any `BaseUrl` produces well-formed routes.
+/// ```
+/// # use lemmy_client::BaseUrl;
+/// let base_url: BaseUrl = "https://example.org/lemmy".parse().unwrap();
+/// assert_eq!(base_url.as_str(), "https://example.org/lemmy/");
+///
+/// assert!("ftp://example.org".parse::<BaseUrl>().is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BaseUrl(Url);
+
+impl BaseUrl {
+  /// Creates the base URL of an instance from its domain, which may include a port.
+  ///
+  /// Fails with [`InvalidBaseUrl::InvalidDomain`] if `domain` contains anything else, such as a
+  /// scheme or a path.
+  pub fn from_domain(domain: &str, secure: bool) -> Result<Self, InvalidBaseUrl> {
+    let is_invalid = domain.is_empty()
+      || domain
+        .chars()
+        .any(|c| matches!(c, '/' | '\\' | '?' | '#' | '@') || c.is_whitespace());
+    if is_invalid {
+      return Err(InvalidBaseUrl::InvalidDomain(domain.to_owned()));
+    }
+
+    let scheme = if secure { "https" } else { "http" };
+    Url::parse(&format!("{scheme}://{domain}/"))
+      .map_err(InvalidBaseUrl::Parse)?
+      .try_into()
+  }
+
+  /// Returns the URL, which always ends with a `/`.
+  pub fn as_url(&self) -> &Url {
+    &self.0
+  }
+
+  /// Returns the URL as a string, which always ends with a `/`.
+  pub fn as_str(&self) -> &str {
+    self.0.as_str()
+  }
+
+  /// Returns the host and, if it isn't the scheme's default, the port, e.g. `localhost:8536`.
+  pub fn domain(&self) -> String {
+    let host = self.0.host_str().unwrap_or_default();
+
+    match self.0.port() {
+      Some(port) => format!("{host}:{port}"),
+      None => host.to_owned(),
+    }
+  }
+
+  /// Returns whether the URL uses HTTPS.
+  pub fn is_secure(&self) -> bool {
+    self.0.scheme() == "https"
+  }
+}
+
+impl TryFrom<Url> for BaseUrl {
+  type Error = InvalidBaseUrl;
+
+  fn try_from(mut url: Url) -> Result<Self, Self::Error> {
+    if !matches!(url.scheme(), "http" | "https") {
+      return Err(InvalidBaseUrl::UnsupportedScheme(url.scheme().to_owned()));
+    }
+    if !url.username().is_empty() || url.password().is_some() {
+      return Err(InvalidBaseUrl::Credentials);
+    }
+    if url.query().is_some() || url.fragment().is_some() {
+      return Err(InvalidBaseUrl::QueryOrFragment);
+    }
+
+    // Routes are appended to the path, so `/lemmy` has to become `/lemmy/`.
+    if !url.path().ends_with('/') {
+      let path = format!("{}/", url.path());
+      url.set_path(&path);
+    }
+
+    Ok(Self(url))
+  }
+}
+
+impl FromStr for BaseUrl {
+  type Err = InvalidBaseUrl;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    Url::parse(s).map_err(InvalidBaseUrl::Parse)?.try_into()
+  }
+}
+
+impl TryFrom<&str> for BaseUrl {
+  type Error = InvalidBaseUrl;
+
+  fn try_from(value: &str) -> Result<Self, Self::Error> {
+    value.parse()
+  }
+}
+
+impl TryFrom<String> for BaseUrl {
+  type Error = InvalidBaseUrl;
+
+  fn try_from(value: String) -> Result<Self, Self::Error> {
+    value.parse()
+  }
+}
+
+impl fmt::Display for BaseUrl {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(self.as_str())
+  }
+}
@@ -0,0 +1,182 @@
+//! A synchronous client for programs that don't run an async runtime.
+//!
+//! [`LemmyClient`] has a blocking version of every endpoint method of the async
+//! [`LemmyClient`][crate::LemmyClient], with the same name and arguments. It wraps an async client
+//! and drives its requests to completion on a private single-threaded tokio runtime, so both
+//! clients send exactly the same requests.
+//!
+//! ```no_run
+//! use lemmy_client::{ClientOptions, blocking::LemmyClient};
+//!
+//! let client = LemmyClient::new(ClientOptions::new("lemmy.ml"));
+//! let site = client.get_site().unwrap();
+//!
+//! println!("{}", site.site_view.site.name);
+//! ```
+//!
+//! <div class="warning">
+//!
+//! Calling a blocking method from within an async runtime panics, just like with
+//! `reqwest::blocking`. Use the async client there instead.
+//!
+//! </div>
+
+use crate::{ApiVersion, Capabilities, ClientOptions, LemmyResult, SessionData, Transport};
+use futures_util::{Stream, StreamExt};
+use lemmy_api_common::account::auth::{Login, LoginResponse};
+use std::{borrow::Cow, future::Future};
+use tokio::runtime::{Builder, Runtime};
+
+/// Blocking API wrapper for Lemmy. See the [module docs](self) for details.
+pub struct LemmyClient {
+  pub(crate) client: crate::LemmyClient,
+  pub(crate) runtime: Runtime,
+}
+
+impl LemmyClient {
+  /// Creates a new blocking client that sends requests with [`reqwest`].
+  /// # Examples
+  /// ```
+  /// # use lemmy_client::{ClientOptions, blocking::LemmyClient};
+  /// let client = LemmyClient::new(ClientOptions::new("lemmy.ml"));
+  /// ```
+  ///
+  /// If the client can't be created from the [`HttpOptions`][crate::HttpOptions], every request
+  /// fails with [`ClientError::InvalidOptions`][crate::ClientError::InvalidOptions]. Use
+  /// [`try_new`][LemmyClient::try_new] to find out right away.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the runtime can't be started, see [`try_new`][LemmyClient::try_new].
+  #[cfg(feature = "native")]
+  pub fn new<Domain>(options: ClientOptions<Domain>) -> Self
+  where
+    Domain: Into<Cow<'static, str>>,
+  {
+    crate::LemmyClient::new(options).into()
+  }
+
+  /// Creates a new blocking client like [`new`][LemmyClient::new], failing with
+  /// [`ClientError::InvalidOptions`][crate::ClientError::InvalidOptions] right away if the options
+  /// are invalid, and with [`ClientError::Transport`][crate::ClientError::Transport] if the
+  /// runtime can't be started.
+  #[cfg(feature = "native")]
+  pub fn try_new<Domain>(options: ClientOptions<Domain>) -> LemmyResult<Self>
+  where
+    Domain: Into<Cow<'static, str>>,
+  {
+    let client = crate::LemmyClient::try_new(options)?;
+    let runtime = runtime().map_err(|e| crate::ClientError::Transport(Box::new(e)))?;
+
+    Ok(Self { client, runtime })
+  }
+
+  /// Creates a new blocking client that sends requests with a pre-configured
+  /// [`reqwest::Client`].
+  #[cfg(feature = "native")]
+  pub fn with_reqwest_client<Domain>(
+    options: ClientOptions<Domain>,
+    client: reqwest::Client,
+  ) -> Self
+  where
+    Domain: Into<Cow<'static, str>>,
+  {
+    crate::LemmyClient::with_reqwest_client(options, client).into()
+  }
+
+  /// Creates a new blocking client that sends requests through a custom [`Transport`].
+  pub fn with_transport<Domain>(
+    options: ClientOptions<Domain>,
+    transport: impl Transport + 'static,
+  ) -> Self
+  where
+    Domain: Into<Cow<'static, str>>,
+  {
+    crate::LemmyClient::with_transport(options, transport).into()
+  }
+
+  /// Returns the async client the requests are sent with.
+  pub fn client(&self) -> &crate::LemmyClient {
+    &self.client
+  }
+
+  /// Returns the async client the requests are sent with, e.g. to
+  /// [set the JWT][crate::LemmyClient::set_jwt] or change the headers.
+  pub fn client_mut(&mut self) -> &mut crate::LemmyClient {
+    &mut self.client
+  }
+
+  /// Returns the async client the requests are sent with, dropping the runtime.
+  pub fn into_async(self) -> crate::LemmyClient {
+    self.client
+  }
+
+  /// Runs a future to completion on the client's runtime, e.g. one built from
+  /// [`client`][Self::client] for an operation without a blocking version.
+  ///
+  /// # Panics
+  ///
+  /// Panics if called from within an async runtime.
+  pub fn block_on<F: Future>(&self, future: F) -> F::Output {
+    self.runtime.block_on(future)
+  }
+
+  /// Turns a stream into an iterator that blocks on each item.
+  pub(crate) fn block_on_stream<'a, S>(&'a self, stream: S) -> impl Iterator<Item = S::Item> + 'a
+  where
+    S: Stream + 'a,
+  {
+    let mut stream = Box::pin(stream);
+    std::iter::from_fn(move || self.block_on(stream.next()))
+  }
+
+  /// Blocking version of
+  /// [`LemmyClient::detect_api_version`](crate::LemmyClient::detect_api_version).
+  pub fn detect_api_version(&self) -> LemmyResult<ApiVersion> {
+    self.block_on(self.client.detect_api_version())
+  }
+
+  /// Blocking version of [`LemmyClient::capabilities`](crate::LemmyClient::capabilities).
+  pub fn capabilities(&self) -> LemmyResult<Capabilities> {
+    self.block_on(self.client.capabilities())
+  }
+
+  /// Blocking version of
+  /// [`LemmyClient::refresh_capabilities`](crate::LemmyClient::refresh_capabilities).
+  pub fn refresh_capabilities(&self) -> LemmyResult<Capabilities> {
+    self.block_on(self.client.refresh_capabilities())
+  }
+
+  /// Blocking version of [`LemmyClient::start_session`](crate::LemmyClient::start_session).
+  pub fn start_session(&self, login: Login) -> LemmyResult<LoginResponse> {
+    self.block_on(self.client.start_session(login))
+  }
+
+  /// Blocking version of [`LemmyClient::restore_session`](crate::LemmyClient::restore_session).
+  pub fn restore_session(&self) -> LemmyResult<Option<SessionData>> {
+    self.block_on(self.client.restore_session())
+  }
+
+  /// Blocking version of [`LemmyClient::end_session`](crate::LemmyClient::end_session).
+  pub fn end_session(&self) -> LemmyResult<()> {
+    self.block_on(self.client.end_session())
+  }
+}
+
+impl From<crate::LemmyClient> for LemmyClient {
+  /// Wraps an async client, starting a runtime for it.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the runtime can't be started.
+  fn from(client: crate::LemmyClient) -> Self {
+    let runtime = runtime().expect("failed to start the blocking client's runtime");
+
+    Self { client, runtime }
+  }
+}
+
+/// Starts the runtime a blocking client runs its requests on.
+fn runtime() -> std::io::Result<Runtime> {
+  Builder::new_current_thread().enable_all().build()
+}
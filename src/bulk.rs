@@ -0,0 +1,283 @@
+use crate::{ClientError, Endpoint, LemmyClient};
+use futures_util::{StreamExt, stream};
+use http::{Method, Uri};
+use lemmy_api_common::{
+  SuccessResponse,
+  comment::{
+    CommentResponse,
+    actions::{
+      CreateCommentLike,
+      DeleteComment,
+      LockComment,
+      SaveComment,
+      moderation::{DistinguishComment, PurgeComment, RemoveComment},
+    },
+  },
+  community::{
+    CommunityResponse,
+    actions::{
+      BlockCommunity,
+      FollowCommunity,
+      moderation::{
+        AddModToCommunity,
+        AddModToCommunityResponse,
+        BanFromCommunity,
+        PurgeCommunity,
+        RemoveCommunity,
+      },
+    },
+  },
+  notification::MarkNotificationAsRead,
+  person::{
+    PersonResponse,
+    actions::{
+      BlockPerson,
+      moderation::{BanPerson, PurgePerson, RegistrationApplicationResponse},
+    },
+  },
+  post::{
+    PostResponse,
+    actions::{
+      CreatePostLike,
+      DeletePost,
+      HidePost,
+      MarkPostAsRead,
+      SavePost,
+      moderation::{FeaturePost, LockPost, PurgePost, RemovePost},
+    },
+  },
+  report::{
+    CommentReportResponse,
+    PostReportResponse,
+    PrivateMessageReportResponse,
+    ResolveCommentReport,
+    ResolvePostReport,
+    ResolvePrivateMessageReport,
+  },
+  site::administration::ApproveRegistrationApplication,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// The form of an endpoint that [`Bulk`] can call many times, e.g. [`RemovePost`] for
+/// [`remove_post`][LemmyClient::remove_post].
+pub trait BulkOperation: Serialize + Clone + fmt::Debug {
+  /// The endpoint the form is sent to.
+  const ENDPOINT: Endpoint;
+  /// What the endpoint responds with.
+  type Response: for<'de> Deserialize<'de> + Clone + fmt::Debug + Send + Sync + 'static;
+}
+
+/// Implements [`BulkOperation`] for a list of forms, with the endpoint they are sent to and its
+/// response.
+macro_rules! bulk_operations {
+  ($($form:ty => $endpoint:ident -> $response:ty;)*) => {
+    $(
+      impl BulkOperation for $form {
+        const ENDPOINT: Endpoint = Endpoint::$endpoint;
+        type Response = $response;
+      }
+    )*
+  };
+}
+
+bulk_operations! {
+  RemovePost => RemovePost -> PostResponse;
+  DeletePost => DeletePost -> PostResponse;
+  LockPost => LockPost -> PostResponse;
+  FeaturePost => FeaturePost -> PostResponse;
+  MarkPostAsRead => MarkPostAsRead -> PostResponse;
+  HidePost => HidePost -> SuccessResponse;
+  SavePost => SavePost -> PostResponse;
+  CreatePostLike => LikePost -> PostResponse;
+  ResolvePostReport => ResolvePostReport -> PostReportResponse;
+  PurgePost => PurgePost -> SuccessResponse;
+
+  RemoveComment => RemoveComment -> CommentResponse;
+  DeleteComment => DeleteComment -> CommentResponse;
+  LockComment => LockComment -> CommentResponse;
+  DistinguishComment => DistinguishComment -> CommentResponse;
+  SaveComment => SaveComment -> CommentResponse;
+  CreateCommentLike => LikeComment -> CommentResponse;
+  ResolveCommentReport => ResolveCommentReport -> CommentReportResponse;
+  PurgeComment => PurgeComment -> SuccessResponse;
+
+  BanFromCommunity => BanFromCommunity -> PersonResponse;
+  AddModToCommunity => AddModToCommunity -> AddModToCommunityResponse;
+  RemoveCommunity => RemoveCommunity -> CommunityResponse;
+  FollowCommunity => FollowCommunity -> CommunityResponse;
+  BlockCommunity => BlockCommunity -> CommunityResponse;
+  PurgeCommunity => PurgeCommunity -> SuccessResponse;
+
+  BanPerson => BanFromSite -> PersonResponse;
+  BlockPerson => BlockPerson -> PersonResponse;
+  PurgePerson => PurgePerson -> SuccessResponse;
+
+  ApproveRegistrationApplication => ApproveRegistrationApplication -> RegistrationApplicationResponse;
+  ResolvePrivateMessageReport => ResolvePrivateMessageReport -> PrivateMessageReportResponse;
+  MarkNotificationAsRead => MarkNotificationAsRead -> SuccessResponse;
+}
+
+/// What became of one operation of a [`Bulk`] run.
+#[derive(Debug)]
+pub enum BulkOutcome<R> {
+  /// The call succeeded with this response.
+  Succeeded(R),
+  /// The call failed, or in a dry run, its request could not be built.
+  Failed(ClientError),
+  /// In a dry run, the request that would have been sent.
+  Planned {
+    /// The HTTP method of the request.
+    method: Method,
+    /// The URL of the request, query included.
+    uri: Uri,
+  },
+}
+
+/// One operation of a [`Bulk`] run and what became of it.
+#[derive(Debug)]
+pub struct BulkItem<O: BulkOperation> {
+  /// The position of the operation among those passed to [`Bulk::run`].
+  pub index: usize,
+  /// The operation.
+  pub operation: O,
+  /// What became of it.
+  pub outcome: BulkOutcome<O::Response>,
+}
+
+/// What became of every operation of a [`Bulk`] run, in the order they were passed in.
+#[derive(Debug)]
+pub struct BulkReport<O: BulkOperation> {
+  /// Each operation with its outcome.
+  pub items: Vec<BulkItem<O>>,
+}
+
+impl<O: BulkOperation> BulkReport<O> {
+  /// The operations that succeeded.
+  pub fn succeeded(&self) -> impl Iterator<Item = &BulkItem<O>> {
+    self
+      .items
+      .iter()
+      .filter(|item| matches!(item.outcome, BulkOutcome::Succeeded(_)))
+  }
+
+  /// The operations that failed, e.g. to try them again.
+  pub fn failed(&self) -> impl Iterator<Item = &BulkItem<O>> {
+    self
+      .items
+      .iter()
+      .filter(|item| matches!(item.outcome, BulkOutcome::Failed(_)))
+  }
+
+  /// Returns true if no operation failed.
+  pub fn is_success(&self) -> bool {
+    self.failed().next().is_none()
+  }
+}
+
+/// Calls an endpoint once for each of many operations, a few at a time. Created with
+/// [`LemmyClient::bulk`].
+///
+/// Calls are made like any other, so they wait for the client's rate limiter and are retried
+/// according to its [`RetryPolicy`][crate::RetryPolicy]. A failed call doesn't stop the others.
+/// ```
+/// # use lemmy_client::LemmyClient;
+/// # use lemmy_client::lemmy_api_common::post::{PostId, actions::moderation::RemovePost};
+/// # async fn sweep(client: &LemmyClient, spam: Vec<PostId>) {
+/// let removals = spam.into_iter().map(|post_id| RemovePost {
+///   post_id,
+///   removed: true,
+///   reason: "Spam".to_owned(),
+///   remove_children: None,
+/// });
+/// let report = client.bulk().concurrency(8).run(removals).await;
+///
+/// for item in report.failed() {
+///   println!("could not remove {:?}: {:?}", item.operation.post_id, item.outcome);
+/// }
+/// # }
+/// ```
+pub struct Bulk<'a> {
+  client: &'a LemmyClient,
+  concurrency: usize,
+  dry_run: bool,
+}
+
+impl Bulk<'_> {
+  /// Sets how many calls are in flight at once, 4 by default.
+  pub fn concurrency(mut self, concurrency: usize) -> Self {
+    self.concurrency = concurrency.max(1);
+
+    self
+  }
+
+  /// If true, nothing is sent. Each operation's request is only built, which checks that the
+  /// form serializes and the endpoint is available on the instance, and reported as
+  /// [`BulkOutcome::Planned`]. With the `tracing` feature, each request is also logged.
+  pub fn dry_run(mut self, dry_run: bool) -> Self {
+    self.dry_run = dry_run;
+
+    self
+  }
+
+  /// Runs every operation and reports what became of each.
+  pub async fn run<O: BulkOperation>(
+    &self,
+    operations: impl IntoIterator<Item = O>,
+  ) -> BulkReport<O> {
+    let mut items = stream::iter(operations.into_iter().enumerate())
+      .map(|(index, operation)| async move {
+        let outcome = self.call(&operation).await;
+
+        BulkItem {
+          index,
+          operation,
+          outcome,
+        }
+      })
+      .buffer_unordered(self.concurrency)
+      .collect::<Vec<_>>()
+      .await;
+    items.sort_by_key(|item| item.index);
+
+    BulkReport { items }
+  }
+
+  async fn call<O: BulkOperation>(&self, operation: &O) -> BulkOutcome<O::Response> {
+    if !self.dry_run {
+      return match self
+        .client
+        .make_request(O::ENDPOINT, operation.clone())
+        .await
+      {
+        Ok(response) => BulkOutcome::Succeeded(response),
+        Err(error) => BulkOutcome::Failed(error),
+      };
+    }
+
+    match self.client.build_request(O::ENDPOINT, operation).await {
+      Ok(request) => {
+        #[cfg(feature = "tracing")]
+        crate::instrument::record_dry_run(O::ENDPOINT, &request);
+
+        BulkOutcome::Planned {
+          method: request.method().clone(),
+          uri: request.uri().clone(),
+        }
+      }
+      Err(error) => BulkOutcome::Failed(error),
+    }
+  }
+}
+
+impl LemmyClient {
+  /// Creates a [`Bulk`] executor that calls an endpoint for each of many operations, such as the
+  /// removals of a moderation sweep.
+  pub fn bulk(&self) -> Bulk<'_> {
+    Bulk {
+      client: self,
+      concurrency: 4,
+      dry_run: false,
+    }
+  }
+}
@@ -0,0 +1,576 @@
+use crate::{Endpoint, LemmyResult, Middleware, RequestContext, transport::MaybeSendSync};
+use bytes::Bytes;
+use http::{
+  HeaderValue,
+  Method,
+  Request,
+  Response,
+  StatusCode,
+  header::{AUTHORIZATION, COOKIE, ETAG, LAST_MODIFIED},
+};
+use std::{
+  any::Any,
+  collections::HashMap,
+  ops::ControlFlow,
+  sync::{Arc, Mutex, OnceLock},
+  time::Duration,
+};
+use web_time::SystemTime;
+
+/// A successful response kept by a [`CacheStore`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedResponse {
+  /// The status of the response.
+  pub status: StatusCode,
+  /// The JSON body of the response.
+  pub body: Bytes,
+  /// When the response goes stale.
+  pub expires_at: SystemTime,
+  /// The `ETag` header of the response.
+  pub etag: Option<HeaderValue>,
+  /// The `Last-Modified` header of the response.
+  pub last_modified: Option<HeaderValue>,
+  /// What the body was decoded into, shared by the clones of the response.
+  decoded: Decoded,
+}
+
+impl CachedResponse {
+  /// Creates a response without `ETag` and `Last-Modified` headers, e.g. to return one that a
+  /// [`CacheStore`] kept elsewhere.
+  pub fn new(status: StatusCode, body: Bytes, expires_at: SystemTime) -> Self {
+    Self {
+      status,
+      body,
+      expires_at,
+      etag: None,
+      last_modified: None,
+      decoded: Decoded::default(),
+    }
+  }
+
+  /// Keep `response` until `expires_at`, sharing what it is decoded into with the copies returned
+  /// later.
+  pub(crate) fn from_response(response: &mut Response<Bytes>, expires_at: SystemTime) -> Self {
+    let cached = Self {
+      etag: response.headers().get(ETAG).cloned(),
+      last_modified: response.headers().get(LAST_MODIFIED).cloned(),
+      ..Self::new(response.status(), response.body().clone(), expires_at)
+    };
+    response.extensions_mut().insert(cached.decoded.clone());
+
+    cached
+  }
+
+  /// Whether the response is still fresh.
+  pub fn is_fresh(&self) -> bool {
+    SystemTime::now() < self.expires_at
+  }
+
+  /// Turn the response back into the one it was created from.
+  pub(crate) fn into_response(self) -> Response<Bytes> {
+    let mut response = Response::new(self.body);
+    *response.status_mut() = self.status;
+
+    let headers = response.headers_mut();
+    if let Some(etag) = self.etag {
+      headers.insert(ETAG, etag);
+    }
+    if let Some(last_modified) = self.last_modified {
+      headers.insert(LAST_MODIFIED, last_modified);
+    }
+    response.extensions_mut().insert(self.decoded);
+
+    response
+  }
+}
+
+/// What the body of a [`CachedResponse`] was decoded into, set by the first call that decodes it
+/// so that calls it is returned to again get a copy instead of decoding the same body again.
+#[derive(Clone, Default)]
+pub(crate) struct Decoded(Arc<OnceLock<Arc<dyn Any + Send + Sync>>>);
+
+impl Decoded {
+  /// Returns a copy of the value, if the body was decoded into an `R` before.
+  pub(crate) fn get<R: Clone + 'static>(&self) -> Option<R> {
+    self.0.get()?.downcast_ref::<R>().cloned()
+  }
+
+  pub(crate) fn set<R: Send + Sync + 'static>(&self, value: R) {
+    let _ = self.0.set(Arc::new(value));
+  }
+}
+
+impl std::fmt::Debug for Decoded {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.write_str(if self.0.get().is_some() {
+      "Decoded"
+    } else {
+      "NotDecoded"
+    })
+  }
+}
+
+/// Only a copy of the body, so it doesn't tell responses apart.
+impl PartialEq for Decoded {
+  fn eq(&self, _other: &Self) -> bool {
+    true
+  }
+}
+
+impl Eq for Decoded {}
+
+/// Where a [`ResponseCache`] keeps its responses.
+///
+/// Entries are grouped by the endpoint they were returned by, so that all of an endpoint's entries
+/// can be dropped at once. Stores don't need to check whether entries are fresh, but may drop
+/// entries whenever they want to. Since the cache only saves work, stores handle their errors by
+/// pretending the entry isn't there.
+pub trait CacheStore: MaybeSendSync {
+  /// Returns the response stored for `key`.
+  fn get(&self, endpoint: Endpoint, key: &str) -> Option<CachedResponse>;
+  /// Stores `response` for `key`, replacing the previous one.
+  fn insert(&self, endpoint: Endpoint, key: &str, response: CachedResponse);
+  /// Removes every response of `endpoint`.
+  fn remove_endpoint(&self, endpoint: Endpoint);
+}
+
+impl<S: CacheStore + ?Sized> CacheStore for Arc<S> {
+  fn get(&self, endpoint: Endpoint, key: &str) -> Option<CachedResponse> {
+    (**self).get(endpoint, key)
+  }
+
+  fn insert(&self, endpoint: Endpoint, key: &str, response: CachedResponse) {
+    (**self).insert(endpoint, key, response)
+  }
+
+  fn remove_endpoint(&self, endpoint: Endpoint) {
+    (**self).remove_endpoint(endpoint)
+  }
+}
+
+/// A [`CacheStore`] that keeps up to a number of responses in memory, dropping the least recently
+/// used one to make room for new ones.
+#[derive(Debug)]
+pub struct MemoryCache {
+  capacity: usize,
+  entries: Mutex<MemoryEntries>,
+}
+
+#[derive(Debug, Default)]
+struct MemoryEntries {
+  /// Each response with the tick it was last used at.
+  responses: HashMap<(Endpoint, String), (CachedResponse, u64)>,
+  tick: u64,
+}
+
+impl MemoryCache {
+  /// Creates a store that keeps up to `capacity` responses.
+  pub fn new(capacity: usize) -> Self {
+    Self {
+      capacity,
+      entries: Mutex::default(),
+    }
+  }
+}
+
+impl CacheStore for MemoryCache {
+  fn get(&self, endpoint: Endpoint, key: &str) -> Option<CachedResponse> {
+    let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+    entries.tick += 1;
+    let tick = entries.tick;
+    let (response, last_used) = entries.responses.get_mut(&(endpoint, key.to_owned()))?;
+    *last_used = tick;
+
+    Some(response.clone())
+  }
+
+  fn insert(&self, endpoint: Endpoint, key: &str, response: CachedResponse) {
+    let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+    let key = (endpoint, key.to_owned());
+
+    if !entries.responses.contains_key(&key) && entries.responses.len() >= self.capacity {
+      let least_recently_used = entries
+        .responses
+        .iter()
+        .min_by_key(|(_, (_, last_used))| *last_used)
+        .map(|(key, _)| key.clone());
+
+      match least_recently_used {
+        Some(least_recently_used) => entries.responses.remove(&least_recently_used),
+        // A store without capacity keeps nothing.
+        None => return,
+      };
+    }
+
+    entries.tick += 1;
+    let tick = entries.tick;
+    entries.responses.insert(key, (response, tick));
+  }
+
+  fn remove_endpoint(&self, endpoint: Endpoint) {
+    self
+      .entries
+      .lock()
+      .unwrap_or_else(|e| e.into_inner())
+      .responses
+      .retain(|(entry_endpoint, _), _| *entry_endpoint != endpoint);
+  }
+}
+
+/// A [`CacheStore`] that keeps responses in files in a directory, so that they survive restarts.
+/// Each endpoint gets a subdirectory. On Unix, the files are only readable by their owner.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone)]
+pub struct FileCache {
+  dir: std::path::PathBuf,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl FileCache {
+  /// Creates a store that keeps responses in the directory at `dir`, which is created when the
+  /// first response is stored.
+  pub fn new(dir: impl Into<std::path::PathBuf>) -> Self {
+    Self { dir: dir.into() }
+  }
+
+  fn path(&self, endpoint: Endpoint, key: &str) -> std::path::PathBuf {
+    self
+      .dir
+      .join(endpoint.name())
+      .join(format!("{:016x}", fingerprint(key.as_bytes())))
+  }
+}
+
+/// Files start with the key, since different keys can share a file name, followed by a line with
+/// the expiry in seconds since the Unix epoch and the status, a line each for the `ETag` and
+/// `Last-Modified` headers, empty if there are none, and the body.
+#[cfg(not(target_arch = "wasm32"))]
+impl CacheStore for FileCache {
+  fn get(&self, endpoint: Endpoint, key: &str) -> Option<CachedResponse> {
+    let file = std::fs::read(self.path(endpoint, key)).ok()?;
+    let mut parts = file.splitn(5, |byte| *byte == b'\n');
+
+    if parts.next()? != key.as_bytes() {
+      return None;
+    }
+
+    let (expires_at, status) = std::str::from_utf8(parts.next()?).ok()?.split_once(' ')?;
+    let expires_at = SystemTime::UNIX_EPOCH + Duration::from_secs(expires_at.parse().ok()?);
+    let status = StatusCode::from_bytes(status.as_bytes()).ok()?;
+    let mut header = || {
+      let value = parts.next()?;
+      Some(
+        (!value.is_empty())
+          .then(|| HeaderValue::from_bytes(value).ok())
+          .flatten(),
+      )
+    };
+    let etag = header()?;
+    let last_modified = header()?;
+    let body = Bytes::copy_from_slice(parts.next()?);
+
+    Some(CachedResponse {
+      etag,
+      last_modified,
+      ..CachedResponse::new(status, body, expires_at)
+    })
+  }
+
+  fn insert(&self, endpoint: Endpoint, key: &str, response: CachedResponse) {
+    use std::io::Write;
+
+    let path = self.path(endpoint, key);
+    let expires_at = response
+      .expires_at
+      .duration_since(SystemTime::UNIX_EPOCH)
+      .unwrap_or_default()
+      .as_secs();
+
+    // Written next to the file and renamed over it, so that readers never see a partly written
+    // response. The random suffix keeps concurrent writers of the same key apart.
+    let temp = path.with_extension(format!("{:016x}.tmp", fastrand::u64(..)));
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    let written = path
+      .parent()
+      .map_or(Ok(()), std::fs::create_dir_all)
+      .and_then(|()| options.open(&temp))
+      .and_then(|mut file| {
+        writeln!(file, "{key}\n{expires_at} {}", response.status.as_u16())?;
+        for header in [&response.etag, &response.last_modified] {
+          file.write_all(header.as_ref().map_or(&[][..], HeaderValue::as_bytes))?;
+          file.write_all(b"\n")?;
+        }
+        file.write_all(&response.body)
+      })
+      .and_then(|()| std::fs::rename(&temp, &path));
+    if written.is_err() {
+      let _ = std::fs::remove_file(&temp);
+    }
+  }
+
+  fn remove_endpoint(&self, endpoint: Endpoint) {
+    let _ = std::fs::remove_dir_all(self.dir.join(endpoint.name()));
+  }
+}
+
+/// A [`Middleware`] that answers calls to read-only endpoints with responses it saw before, for as
+/// long as they are fresh. Register it with [`LemmyClient::add_middleware`][add], before
+/// middleware that change the URL or `Authorization` header of requests, so that it sees requests
+/// as the client built them.
+///
+/// Responses are stored per URL, query included, and per `Authorization` header, so accounts
+/// never see each other's responses. Only successful responses are cached.
+///
+/// By default, these endpoints are cached:
+/// - [`get_site`][crate::LemmyClient::get_site] for 5 minutes.
+/// - [`get_community`][crate::LemmyClient::get_community] for 1 minute.
+/// - [`list_custom_emojis`][crate::LemmyClient::list_custom_emojis],
+///   [`list_taglines`][crate::LemmyClient::list_taglines] and
+///   [`get_federated_instances`][crate::LemmyClient::get_federated_instances] for 10 minutes.
+///
+/// When a call that changes what one of them returns succeeds, e.g.
+/// [`edit_site`][crate::LemmyClient::edit_site] or
+/// [`follow_community`][crate::LemmyClient::follow_community], the responses of the endpoints it
+/// affects are dropped. Changes made by other clients show up once the responses go stale.
+/// ```
+/// # use lemmy_client::{ClientOptions, Endpoint, LemmyClient, MemoryCache, ResponseCache};
+/// # use std::{sync::Arc, time::Duration};
+/// let cache = Arc::new(
+///   ResponseCache::new(MemoryCache::new(1000))
+///     .ttl(Endpoint::GetSite, Duration::from_secs(60))
+///     .ttl(Endpoint::ListCommunities, Duration::from_secs(30))
+///     .invalidate_on(Endpoint::CreateCommunity, Endpoint::ListCommunities),
+/// );
+///
+/// let mut client = LemmyClient::new(ClientOptions::new("lemmy.ml"));
+/// client.add_middleware(cache.clone());
+///
+/// // Forget everything about the site, e.g. after an admin changed it elsewhere.
+/// cache.invalidate(Endpoint::GetSite);
+/// ```
+///
+/// [add]: crate::LemmyClient::add_middleware
+pub struct ResponseCache {
+  store: Arc<dyn CacheStore>,
+  ttls: HashMap<Endpoint, Duration>,
+  invalidations: HashMap<Endpoint, Vec<Endpoint>>,
+}
+
+impl std::fmt::Debug for ResponseCache {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("ResponseCache")
+      .field("ttls", &self.ttls)
+      .field("invalidations", &self.invalidations)
+      .finish_non_exhaustive()
+  }
+}
+
+/// Marks responses served by a [`ResponseCache`], so that they aren't stored again.
+#[derive(Debug, Clone, Copy)]
+struct CacheHit;
+
+impl ResponseCache {
+  /// Creates a cache that keeps responses in `store`, with the default endpoints and lifetimes.
+  pub fn new(store: impl CacheStore + 'static) -> Self {
+    use Endpoint::*;
+
+    let minutes = |minutes: u64| Duration::from_secs(minutes * 60);
+    let ttls = HashMap::from([
+      (GetSite, minutes(5)),
+      (GetCommunity, minutes(1)),
+      (ListCustomEmojis, minutes(10)),
+      (ListTaglines, minutes(10)),
+      (GetFederatedInstances, minutes(10)),
+    ]);
+
+    let mut invalidations = HashMap::<_, Vec<_>>::new();
+    let mut rule = |mutations: &[Endpoint], affected: &[Endpoint]| {
+      for mutation in mutations {
+        invalidations
+          .entry(*mutation)
+          .or_default()
+          .extend_from_slice(affected);
+      }
+    };
+    rule(
+      &[
+        CreateSite,
+        EditSite,
+        UploadSiteIcon,
+        DeleteSiteIcon,
+        UploadSiteBanner,
+        DeleteSiteBanner,
+        AddAdmin,
+      ],
+      &[GetSite],
+    );
+    rule(
+      &[CreateTagline, EditTagline, DeleteTagline],
+      &[ListTaglines, GetSite],
+    );
+    rule(
+      &[CreateCustomEmoji, EditCustomEmoji, DeleteCustomEmoji],
+      &[ListCustomEmojis],
+    );
+    rule(
+      &[AdminBlockInstance, AdminAllowInstance],
+      &[GetFederatedInstances],
+    );
+    rule(
+      &[
+        UpdateCommunity,
+        DeleteCommunity,
+        FollowCommunity,
+        BlockCommunity,
+        RemoveCommunity,
+        PurgeCommunity,
+        TransferCommunity,
+        AddModToCommunity,
+        UploadCommunityIcon,
+        DeleteCommunityIcon,
+        UploadCommunityBanner,
+        DeleteCommunityBanner,
+        CreateCommunityTag,
+        EditCommunityTag,
+        DeleteCommunityTag,
+        EditCommunityNotifications,
+      ],
+      &[GetCommunity],
+    );
+
+    Self {
+      store: Arc::new(store),
+      ttls,
+      invalidations,
+    }
+  }
+
+  /// Caches responses of `endpoint` for `ttl`. Only endpoints that use HTTP GET are cached, and a
+  /// `ttl` of zero stops caching the endpoint.
+  pub fn ttl(mut self, endpoint: Endpoint, ttl: Duration) -> Self {
+    if ttl.is_zero() {
+      self.ttls.remove(&endpoint);
+    } else {
+      self.ttls.insert(endpoint, ttl);
+    }
+
+    self
+  }
+
+  /// Drops the responses of `affected` whenever a call to `mutation` succeeds.
+  pub fn invalidate_on(mut self, mutation: Endpoint, affected: Endpoint) -> Self {
+    self
+      .invalidations
+      .entry(mutation)
+      .or_default()
+      .push(affected);
+
+    self
+  }
+
+  /// Drops the responses of `endpoint`.
+  pub fn invalidate(&self, endpoint: Endpoint) {
+    self.store.remove_endpoint(endpoint);
+  }
+
+  /// How long responses of the endpoint are fresh for, if they are cached at all.
+  fn ttl_of(&self, endpoint: Endpoint) -> Option<Duration> {
+    (endpoint.method() == Method::GET)
+      .then(|| self.ttls.get(&endpoint).copied())
+      .flatten()
+  }
+}
+
+impl Middleware for ResponseCache {
+  fn before_request(
+    &self,
+    context: &RequestContext,
+    request: &mut Request<Vec<u8>>,
+  ) -> ControlFlow<LemmyResult<Response<Bytes>>> {
+    if self.ttl_of(context.endpoint).is_none() {
+      return ControlFlow::Continue(());
+    }
+
+    match self.store.get(context.endpoint, &cache_key(request)) {
+      Some(cached) if cached.is_fresh() => {
+        let mut response = cached.into_response();
+        response.extensions_mut().insert(CacheHit);
+
+        ControlFlow::Break(Ok(response))
+      }
+      _ => ControlFlow::Continue(()),
+    }
+  }
+
+  fn after_response(
+    &self,
+    context: &RequestContext,
+    request: &Request<Vec<u8>>,
+    result: LemmyResult<Response<Bytes>>,
+  ) -> LemmyResult<Response<Bytes>> {
+    let Ok(mut response) = result else {
+      return result;
+    };
+
+    if !response.status().is_success() || response.extensions().get::<CacheHit>().is_some() {
+      return Ok(response);
+    }
+
+    if let Some(ttl) = self.ttl_of(context.endpoint) {
+      self.store.insert(
+        context.endpoint,
+        &cache_key(request),
+        CachedResponse::from_response(&mut response, SystemTime::now() + ttl),
+      );
+    }
+
+    for affected in self
+      .invalidations
+      .get(&context.endpoint)
+      .into_iter()
+      .flatten()
+    {
+      self.store.remove_endpoint(*affected);
+    }
+
+    Ok(response)
+  }
+}
+
+/// Identifies a response by the URL it was requested from and the account it was requested by,
+/// as given by its `Authorization` and `Cookie` headers. Only a fingerprint of those is kept, so
+/// that stores never see JWTs.
+///
+/// Cookies that the transport adds on its own, like a browser does for a fetch transport that
+/// includes credentials, aren't part of the key.
+pub(crate) fn cache_key(request: &Request<Vec<u8>>) -> String {
+  let credentials: Vec<_> = [AUTHORIZATION, COOKIE]
+    .iter()
+    .flat_map(|name| {
+      request
+        .headers()
+        .get_all(name)
+        .iter()
+        .map(move |value| [name.as_str().as_bytes(), b":", value.as_bytes(), b"\n"].concat())
+    })
+    .collect();
+  let identity = if credentials.is_empty() {
+    "anonymous".to_owned()
+  } else {
+    format!("{:016x}", fingerprint(&credentials.concat()))
+  };
+
+  format!("{} {identity}", request.uri())
+}
+
+/// 64-bit FNV-1a, which unlike the standard library's hasher is stable across releases, so that
+/// keys stay the same for stores that outlive the program.
+fn fingerprint(bytes: &[u8]) -> u64 {
+  bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+    (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+  })
+}
@@ -0,0 +1,122 @@
+use std::{
+  collections::HashMap,
+  future::Future,
+  pin::Pin,
+  sync::{
+    Arc,
+    Mutex,
+    atomic::{AtomicBool, Ordering},
+  },
+  task::{Context, Poll, Waker},
+};
+
+/// Cancels the calls made through the handles created with it by
+/// [`LemmyClient::with_cancellation`][with], e.g. when the user navigates away from a page that
+/// is still loading.
+///
+/// Cancelled calls fail with [`ClientError::Cancelled`][cancelled] right away, and their requests
+/// are dropped, which aborts them on WASM too. Once cancelled, a token stays cancelled: calls made
+/// with it afterwards fail without being sent. Clones of a token cancel together.
+/// ```
+/// # use lemmy_client::{CancellationToken, ClientError, LemmyClient};
+/// # use lemmy_client::lemmy_api_common::post::GetPosts;
+/// # async fn load(client: &LemmyClient) {
+/// let token = CancellationToken::new();
+/// let posts = client.with_cancellation(&token);
+///
+/// // E.g. from the "Stop" button.
+/// token.cancel();
+///
+/// let result = posts.list_posts(GetPosts::default()).await;
+/// assert!(matches!(result, Err(ClientError::Cancelled)));
+/// # }
+/// ```
+///
+/// [with]: crate::LemmyClient::with_cancellation
+/// [cancelled]: crate::ClientError::Cancelled
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<Inner>);
+
+#[derive(Debug, Default)]
+struct Inner {
+  cancelled: AtomicBool,
+  waiters: Mutex<Waiters>,
+}
+
+/// The tasks waiting for a token to be cancelled, by the id of the future they wait with.
+#[derive(Debug, Default)]
+struct Waiters {
+  next_id: u64,
+  wakers: HashMap<u64, Waker>,
+}
+
+impl CancellationToken {
+  /// Creates a token that isn't cancelled yet.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Cancels the calls made with this token.
+  pub fn cancel(&self) {
+    self.0.cancelled.store(true, Ordering::SeqCst);
+
+    let wakers = std::mem::take(&mut self.waiters().wakers);
+    wakers.into_values().for_each(Waker::wake);
+  }
+
+  /// Returns whether the token was cancelled.
+  pub fn is_cancelled(&self) -> bool {
+    self.0.cancelled.load(Ordering::SeqCst)
+  }
+
+  /// Completes once the token is cancelled.
+  pub fn cancelled(&self) -> impl Future<Output = ()> + '_ {
+    Cancelled {
+      token: self,
+      id: None,
+    }
+  }
+
+  fn waiters(&self) -> std::sync::MutexGuard<'_, Waiters> {
+    self.0.waiters.lock().unwrap_or_else(|e| e.into_inner())
+  }
+}
+
+struct Cancelled<'a> {
+  token: &'a CancellationToken,
+  /// Set once the future registered its waker.
+  id: Option<u64>,
+}
+
+impl Future for Cancelled<'_> {
+  type Output = ();
+
+  fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+    if self.token.is_cancelled() {
+      return Poll::Ready(());
+    }
+
+    let mut waiters = self.token.waiters();
+    let id = *self.id.get_or_insert_with(|| {
+      waiters.next_id += 1;
+      waiters.next_id
+    });
+    waiters.wakers.insert(id, cx.waker().clone());
+    drop(waiters);
+
+    // Checked again in case the token was cancelled before the waker was registered.
+    if self.token.is_cancelled() {
+      Poll::Ready(())
+    } else {
+      Poll::Pending
+    }
+  }
+}
+
+impl Drop for Cancelled<'_> {
+  fn drop(&mut self) {
+    if let Some(id) = self.id {
+      self.token.waiters().wakers.remove(&id);
+    }
+  }
+}
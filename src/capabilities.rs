@@ -0,0 +1,238 @@
+use crate::{ApiVersion, Endpoint};
+use lemmy_api_common::site::{GetSiteResponse, RegistrationMode};
+use serde::{Deserialize, de::IgnoredAny};
+use std::fmt;
+
+/// Something that only some instances support, either because it was added in a later version of
+/// Lemmy or because admins can turn it off.
+///
+/// Features added in a later version are checked against the version the instance reports, see
+/// [`Feature::min_version`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Feature {
+  /// Multi-communities, which bundle several communities into one feed.
+  MultiCommunity,
+  /// Warning users about their posts and comments.
+  PostWarnings,
+  /// Moderators editing other users' posts.
+  ModEditPost,
+  /// Approving follows of private communities.
+  PendingFollows,
+  /// Private notes about other users.
+  PersonNotes,
+  /// Logging in with an external OAuth provider.
+  Oauth,
+  /// Captchas when registering.
+  Captcha,
+  /// Registering new accounts.
+  Registration,
+}
+
+impl Feature {
+  /// The feature an endpoint belongs to, or `None` if every instance supports it.
+  /// ```
+  /// # use lemmy_client::{Endpoint, Feature};
+  /// assert_eq!(
+  ///   Feature::of(Endpoint::CreateMultiCommunity),
+  ///   Some(Feature::MultiCommunity)
+  /// );
+  /// assert_eq!(Feature::of(Endpoint::GetSite), None);
+  /// ```
+  pub fn of(endpoint: Endpoint) -> Option<Self> {
+    match endpoint {
+      Endpoint::CreateMultiCommunity
+      | Endpoint::EditMultiCommunity
+      | Endpoint::GetMultiCommunity
+      | Endpoint::AddMultiCommunityEntry
+      | Endpoint::RemoveMultiCommunityEntry
+      | Endpoint::ListMultiCommunities
+      | Endpoint::FollowMultiCommunity => Some(Self::MultiCommunity),
+      Endpoint::CreatePostWarning | Endpoint::CreateCommentWarning => Some(Self::PostWarnings),
+      Endpoint::ModEditPost => Some(Self::ModEditPost),
+      Endpoint::ListCommunityPendingFollows | Endpoint::ApproveCommunityPendingFollow => {
+        Some(Self::PendingFollows)
+      }
+      Endpoint::CreatePersonNote => Some(Self::PersonNotes),
+      Endpoint::AuthenticateWithOauth => Some(Self::Oauth),
+      Endpoint::GetCaptcha => Some(Self::Captcha),
+      Endpoint::RegisterAccount => Some(Self::Registration),
+      _ => None,
+    }
+  }
+
+  /// The first version of Lemmy with the feature, as `(major, minor, patch)`, or `None` if
+  /// instances of every version can have it.
+  /// ```
+  /// # use lemmy_client::Feature;
+  /// assert_eq!(Feature::MultiCommunity.min_version(), Some((1, 0, 0)));
+  /// assert_eq!(Feature::Oauth.min_version(), None);
+  /// ```
+  pub fn min_version(self) -> Option<(u32, u32, u32)> {
+    match self {
+      Self::MultiCommunity
+      | Self::PostWarnings
+      | Self::ModEditPost
+      | Self::PendingFollows
+      | Self::PersonNotes => Some((1, 0, 0)),
+      Self::Oauth | Self::Captcha | Self::Registration => None,
+    }
+  }
+}
+
+impl fmt::Display for Feature {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(match self {
+      Self::MultiCommunity => "multi-communities",
+      Self::PostWarnings => "post warnings",
+      Self::ModEditPost => "moderator post edits",
+      Self::PendingFollows => "pending follows",
+      Self::PersonNotes => "person notes",
+      Self::Oauth => "OAuth login",
+      Self::Captcha => "captchas",
+      Self::Registration => "registration",
+    })
+  }
+}
+
+/// What an instance supports, derived from the version of Lemmy it runs and its site settings.
+///
+/// Fetched with [`LemmyClient::capabilities`][capabilities] and kept up to date by
+/// [`get_site`][get_site].
+/// ```
+/// # use lemmy_client::{Feature, LemmyClient};
+/// async fn list_multi_communities(client: &LemmyClient) {
+///   if client
+///     .capabilities()
+///     .await
+///     .unwrap()
+///     .supports(Feature::MultiCommunity)
+///   {
+///     // ...
+///   }
+/// }
+/// ```
+///
+/// [capabilities]: crate::LemmyClient::capabilities
+/// [get_site]: crate::LemmyClient::get_site
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Capabilities {
+  /// The version of Lemmy the instance runs, e.g. `0.19.5`.
+  pub version: String,
+  /// The version of the API the instance speaks.
+  pub api_version: ApiVersion,
+  /// Whether users can log in with an external OAuth provider.
+  pub oauth_enabled: bool,
+  /// Whether registering requires solving a captcha.
+  pub captcha_enabled: bool,
+  /// Whether and how new users can register.
+  pub registration_mode: RegistrationMode,
+  /// Whether content is only visible to logged in users.
+  pub private_instance: bool,
+}
+
+impl Capabilities {
+  /// Returns whether the instance supports a feature.
+  pub fn supports(&self, feature: Feature) -> bool {
+    let recent_enough = feature
+      .min_version()
+      .is_none_or(|min| parse_version(&self.version).is_none_or(|version| version >= min));
+
+    recent_enough
+      && match feature {
+        Feature::Oauth => self.oauth_enabled,
+        Feature::Captcha => self.captcha_enabled,
+        Feature::Registration => self.registration_mode != RegistrationMode::Closed,
+        _ => true,
+      }
+  }
+
+  /// Returns whether the instance supports calling an endpoint.
+  pub fn supports_endpoint(&self, endpoint: Endpoint) -> bool {
+    Feature::of(endpoint).is_none_or(|feature| self.supports(feature))
+  }
+}
+
+/// Parses a version of Lemmy such as `0.19.5` or `1.0.0-beta.2` into `(major, minor, patch)`,
+/// ignoring any pre-release or build suffix. Like [`ApiVersion::from_lemmy_version`], callers
+/// treat versions that can't be parsed as recent.
+fn parse_version(version: &str) -> Option<(u32, u32, u32)> {
+  let mut parts = version
+    .trim_start_matches('v')
+    .split(['.', '-', '+'])
+    .map(|part| part.parse::<u32>().ok());
+
+  Some((
+    parts.next()??,
+    parts.next()??,
+    parts.next().flatten().unwrap_or(0),
+  ))
+}
+
+impl From<&GetSiteResponse> for Capabilities {
+  fn from(site: &GetSiteResponse) -> Self {
+    let local_site = &site.site_view.local_site;
+
+    Self {
+      api_version: ApiVersion::from_lemmy_version(&site.version),
+      version: site.version.clone(),
+      oauth_enabled: !site.oauth_providers.is_empty(),
+      captcha_enabled: site.captcha_enabled,
+      registration_mode: local_site.registration_mode,
+      private_instance: local_site.private_instance,
+    }
+  }
+}
+
+/// The parts of a site response that [`Capabilities`] are derived from, in a shape shared by API
+/// v3 and v4.
+#[derive(Clone, Deserialize)]
+pub(crate) struct SiteInfo {
+  version: String,
+  site_view: SiteViewInfo,
+  #[serde(default)]
+  oauth_providers: Vec<IgnoredAny>,
+  /// Moved here from the local site in API v4.
+  captcha_enabled: Option<bool>,
+}
+
+#[derive(Clone, Deserialize)]
+struct SiteViewInfo {
+  local_site: LocalSiteInfo,
+}
+
+#[derive(Clone, Deserialize)]
+struct LocalSiteInfo {
+  /// `RequireApplication` in API v3, `require_application` in v4.
+  registration_mode: String,
+  private_instance: bool,
+  #[serde(default)]
+  captcha_enabled: bool,
+}
+
+impl From<SiteInfo> for Capabilities {
+  fn from(site: SiteInfo) -> Self {
+    let local_site = site.site_view.local_site;
+    let registration_mode = match local_site
+      .registration_mode
+      .replace('_', "")
+      .to_lowercase()
+      .as_str()
+    {
+      "closed" => RegistrationMode::Closed,
+      "requireapplication" => RegistrationMode::RequireApplication,
+      "requireinvitation" => RegistrationMode::RequireInvitation,
+      _ => RegistrationMode::Open,
+    };
+
+    Self {
+      api_version: ApiVersion::from_lemmy_version(&site.version),
+      version: site.version,
+      oauth_enabled: !site.oauth_providers.is_empty(),
+      captcha_enabled: site.captcha_enabled.unwrap_or(local_site.captcha_enabled),
+      registration_mode,
+      private_instance: local_site.private_instance,
+    }
+  }
+}
@@ -1,47 +1,240 @@
-use std::borrow::Cow;
+use crate::{ApiVersionMode, BaseUrl, InvalidBaseUrl, RateLimitMode, RetryPolicy};
+use std::{borrow::Cow, time::Duration};
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 /// Options for creating a [`LemmyClient`][client].
 ///
+/// [`ClientOptions::new`] fills in defaults for everything but the domain. Use struct update
+/// syntax to override individual options:
+/// ```
+/// # use lemmy_client::{ClientOptions, RetryPolicy};
+/// let options = ClientOptions {
+///   retry_policy: RetryPolicy::never(),
+///   ..ClientOptions::new("lemmy.ml")
+/// };
+/// ```
+///
 /// [client]: crate::LemmyClient
 pub struct ClientOptions<Domain: Into<Cow<'static, str>>> {
-  /// Domain of the instance the client will send requests to.
+  /// Domain of the instance the client will send requests to, optionally with a port.
   /// ```
   /// # use lemmy_client::ClientOptions;
-  /// // ❌ You should not include the scheme for the domain.
-  /// let options = ClientOptions {
-  ///     domain: "https://lemmy.ml",
-  ///     secure: true
-  /// };
+  /// // ❌ You should not include the scheme for the domain. Use `from_url` for full URLs.
+  /// let options = ClientOptions::new("https://lemmy.ml");
   ///
   /// // ✅ All you need is the domain (including subdomain, if applicable).
-  /// let options = ClientOptions {
-  ///     domain: "lemmy.ml",
-  ///     secure: true
-  /// };
+  /// let options = ClientOptions::new("lemmy.ml");
   /// ```
+  ///
+  /// A domain that contains anything else makes [`LemmyClient::try_new`][try_new] fail with an
+  /// [`InvalidBaseUrl`] error.
+  ///
+  /// [try_new]: crate::LemmyClient::try_new
   pub domain: Domain,
   /// If true, use HTTPS. If false, use HTTP
   pub secure: bool,
+  /// The full URL of the instance, for instances served under a path prefix. Takes precedence
+  /// over [`domain`][Self::domain] and [`secure`][Self::secure] when set.
+  pub base_url: Option<BaseUrl>,
+  /// When and how failed requests are retried.
+  pub retry_policy: RetryPolicy,
+  /// Whether requests are throttled to stay within the instance's rate limits.
+  pub rate_limit: RateLimitMode,
+  /// Which version of the API to talk to the instance with.
+  pub api_version: ApiVersionMode,
+  /// If true, calls to endpoints of a [`Feature`][feature] the instance doesn't support fail with
+  /// [`ClientError::UnsupportedFeature`][unsupported] without being sent. The instance's
+  /// [`Capabilities`][capabilities] are fetched before the first such call.
+  ///
+  /// [feature]: crate::Feature
+  /// [unsupported]: crate::ClientError::UnsupportedFeature
+  /// [capabilities]: crate::Capabilities
+  pub check_capabilities: bool,
+  /// If true, calls to endpoints that use HTTP GET wait for an identical call that is already in
+  /// flight, i.e. with the same route, query and `Authorization` header, instead of sending their
+  /// own request. Each caller decodes its own copy of the shared response. The request keeps going
+  /// while any of its callers waits for it, and is dropped once none does.
+  pub coalesce_requests: bool,
+  /// How the HTTP client created by [`LemmyClient::new`][new] connects to the instance.
+  ///
+  /// [new]: crate::LemmyClient::new
+  pub http: HttpOptions,
+}
+
+impl<Domain> ClientOptions<Domain>
+where
+  Domain: Into<Cow<'static, str>>,
+{
+  /// Creates options for an instance at `domain`, using HTTPS, the default [`RetryPolicy`],
+  /// no client-side rate limiting, API v4, no capability checks and the default [`HttpOptions`].
+  pub fn new(domain: Domain) -> Self {
+    Self {
+      domain,
+      secure: true,
+      base_url: None,
+      retry_policy: RetryPolicy::default(),
+      rate_limit: RateLimitMode::default(),
+      api_version: ApiVersionMode::default(),
+      check_capabilities: false,
+      coalesce_requests: false,
+      http: HttpOptions::default(),
+    }
+  }
+}
+
+impl ClientOptions<String> {
+  /// Creates options for an instance served at `base_url`, which may include a port and a path
+  /// prefix, using the same defaults as [`new`][ClientOptions::new].
+  ///
+  /// Fails if `base_url` isn't a valid HTTP or HTTPS URL.
+  /// ```
+  /// # use lemmy_client::ClientOptions;
+  /// let options = ClientOptions::from_url("https://example.org/lemmy/").unwrap();
+  /// assert_eq!(options.domain, "example.org");
+  ///
+  /// let options = ClientOptions::from_url("http://localhost:8536").unwrap();
+  /// assert_eq!(options.domain, "localhost:8536");
+  /// ```
+  pub fn from_url<U>(base_url: U) -> Result<Self, InvalidBaseUrl>
+  where
+    U: TryInto<BaseUrl, Error = InvalidBaseUrl>,
+  {
+    let base_url = base_url.try_into()?;
+
+    Ok(Self {
+      secure: base_url.is_secure(),
+      base_url: Some(base_url.clone()),
+      ..Self::new(base_url.domain())
+    })
+  }
+}
+
+/// Connection settings of the [`reqwest`] client created by [`LemmyClient::new`][new].
+///
+/// They don't apply to clients created with a custom [`Transport`][transport] or a pre-configured
+/// client, nor in the browser, where connections are managed by the browser itself.
+/// ```
+/// # use lemmy_client::{ClientOptions, HttpOptions};
+/// # use std::time::Duration;
+/// // A local development instance with a self-signed certificate.
+/// let options = ClientOptions {
+///   http: HttpOptions {
+///     timeout: Some(Duration::from_secs(10)),
+///     accept_invalid_certs: true,
+///     ..HttpOptions::default()
+///   },
+///   ..ClientOptions::new("localhost:8536")
+/// };
+/// ```
+///
+/// [new]: crate::LemmyClient::new
+/// [transport]: crate::Transport
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpOptions {
+  /// How long connecting to the instance may take.
+  pub connect_timeout: Option<Duration>,
+  /// How long to wait for each read of the response.
+  pub read_timeout: Option<Duration>,
+  /// How long a whole request may take, from connecting until the response has been read.
+  pub timeout: Option<Duration>,
+  /// URL of a proxy to send all requests through, e.g. `http://proxy:3128` or
+  /// `socks5://localhost:9050`. Credentials can be included in the URL.
+  pub proxy: Option<String>,
+  /// PEM-encoded certificates to trust in addition to the system's roots, e.g. the certificate
+  /// authority of a private network. Each entry may contain several certificates.
+  pub root_certificates: Vec<Vec<u8>>,
+  /// Accept any TLS certificate, including self-signed and expired ones.
+  ///
+  /// <div class="warning">
+  ///
+  /// This makes HTTPS connections vulnerable to interception. Only use it for local development
+  /// instances.
+  ///
+  /// </div>
+  pub accept_invalid_certs: bool,
+  /// How long an idle connection is kept open for reuse. `None` keeps it open indefinitely.
+  pub pool_idle_timeout: Option<Duration>,
+  /// Use HTTP/2 without negotiating it first. Only enable this for instances known to support
+  /// it, e.g. behind a reverse proxy speaking HTTP/2 over plain HTTP. Over HTTPS, HTTP/2 is used
+  /// whenever the instance offers it.
+  pub http2_prior_knowledge: bool,
+  /// Ask for gzip compressed responses and decompress them.
+  pub gzip: bool,
+  /// Ask for brotli compressed responses and decompress them.
+  pub brotli: bool,
+}
+
+impl Default for HttpOptions {
+  /// No timeouts, proxy or extra certificates, idle connections closed after 90 seconds, and
+  /// gzip and brotli decompression.
+  fn default() -> Self {
+    Self {
+      connect_timeout: None,
+      read_timeout: None,
+      timeout: None,
+      proxy: None,
+      root_certificates: Vec::new(),
+      accept_invalid_certs: false,
+      pool_idle_timeout: Some(Duration::from_secs(90)),
+      http2_prior_knowledge: false,
+      gzip: true,
+      brotli: true,
+    }
+  }
 }
 
 /// Internal options used by the Lemmy client implementation.
 /// This type being non-generic helps cut down on binary size
 /// from monomorphization.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ClientOptionsInternal {
   pub domain: Cow<'static, str>,
   pub secure: bool,
+  /// Kept as a result so that creating a client with a custom transport can't fail. Requests
+  /// made with an invalid base URL fail instead.
+  pub base_url: Result<BaseUrl, InvalidBaseUrl>,
+  pub retry_policy: RetryPolicy,
+  pub rate_limit: RateLimitMode,
+  pub api_version: ApiVersionMode,
+  pub check_capabilities: bool,
+  pub coalesce_requests: bool,
 }
 
 impl<Domain> From<ClientOptions<Domain>> for ClientOptionsInternal
 where
   Domain: Into<Cow<'static, str>>,
 {
-  fn from(ClientOptions { domain, secure }: ClientOptions<Domain>) -> Self {
+  fn from(
+    ClientOptions {
+      domain,
+      secure,
+      base_url,
+      retry_policy,
+      rate_limit,
+      api_version,
+      check_capabilities,
+      coalesce_requests,
+      http: _,
+    }: ClientOptions<Domain>,
+  ) -> Self {
+    let domain = domain.into();
+    let (domain, secure, base_url) = match base_url {
+      Some(base_url) => (base_url.domain().into(), base_url.is_secure(), Ok(base_url)),
+      None => {
+        let base_url = BaseUrl::from_domain(&domain, secure);
+        (domain, secure, base_url)
+      }
+    };
+
     Self {
       secure,
-      domain: domain.into(),
+      domain,
+      base_url,
+      retry_policy,
+      rate_limit,
+      api_version,
+      check_capabilities,
+      coalesce_requests,
     }
   }
 }
@@ -0,0 +1,155 @@
+use crate::{
+  CacheStore,
+  CachedResponse,
+  ClientError,
+  LemmyResult,
+  Middleware,
+  RequestContext,
+  cache::cache_key,
+};
+use bytes::Bytes;
+use http::{
+  Method,
+  Request,
+  Response,
+  StatusCode,
+  header::{IF_MODIFIED_SINCE, IF_NONE_MATCH},
+};
+use std::{ops::ControlFlow, sync::Arc};
+use web_time::SystemTime;
+
+/// What [`ConditionalRequests`] does when the instance responds that nothing changed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NotModifiedMode {
+  /// Return the response that was received last, as if the instance had sent it again, without
+  /// decoding it again if it was decoded before. Fails with [`ClientError::NotModified`] if the
+  /// store no longer has it.
+  #[default]
+  ReturnCached,
+  /// Fail with [`ClientError::NotModified`], so that callers can skip the work of handling the
+  /// same response twice.
+  Error,
+}
+
+/// A [`Middleware`] that makes calls to endpoints that use HTTP GET conditional, so that
+/// instances can skip sending a response that didn't change since the last one, e.g. when polling
+/// [`unread_counts`][crate::LemmyClient::unread_counts].
+///
+/// The last response of each URL, query included, and `Authorization` header is kept in a
+/// [`CacheStore`] if it has an `ETag` or `Last-Modified` header, which are sent back with the next
+/// request as `If-None-Match` and `If-Modified-Since`. Instances that don't send these headers are
+/// called as usual. Register it with [`LemmyClient::add_middleware`][add], before middleware that
+/// change the URL or `Authorization` header of requests, and don't share its store with a
+/// [`ResponseCache`][crate::ResponseCache].
+/// ```
+/// # use lemmy_client::{
+/// #   ClientError,
+/// #   ClientOptions,
+/// #   ConditionalRequests,
+/// #   LemmyClient,
+/// #   MemoryCache,
+/// #   NotModifiedMode,
+/// # };
+/// # async fn poll() -> Result<(), ClientError> {
+/// let mut client = LemmyClient::new(ClientOptions::new("lemmy.ml"));
+/// client.add_middleware(
+///   ConditionalRequests::new(MemoryCache::new(100)).not_modified(NotModifiedMode::Error),
+/// );
+///
+/// match client.unread_counts().await {
+///   Ok(counts) => println!("{counts:?}"),
+///   Err(ClientError::NotModified) => println!("nothing new"),
+///   Err(e) => return Err(e),
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [add]: crate::LemmyClient::add_middleware
+pub struct ConditionalRequests {
+  store: Arc<dyn CacheStore>,
+  mode: NotModifiedMode,
+}
+
+impl std::fmt::Debug for ConditionalRequests {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("ConditionalRequests")
+      .field("mode", &self.mode)
+      .finish_non_exhaustive()
+  }
+}
+
+impl ConditionalRequests {
+  /// Creates a middleware that keeps the last responses in `store` and returns them again when
+  /// they weren't modified.
+  pub fn new(store: impl CacheStore + 'static) -> Self {
+    Self {
+      store: Arc::new(store),
+      mode: NotModifiedMode::default(),
+    }
+  }
+
+  /// Sets what happens when the instance responds that nothing changed.
+  pub fn not_modified(mut self, mode: NotModifiedMode) -> Self {
+    self.mode = mode;
+
+    self
+  }
+}
+
+impl Middleware for ConditionalRequests {
+  fn before_request(
+    &self,
+    context: &RequestContext,
+    request: &mut Request<Vec<u8>>,
+  ) -> ControlFlow<LemmyResult<Response<Bytes>>> {
+    if context.endpoint.method() != Method::GET {
+      return ControlFlow::Continue(());
+    }
+
+    if let Some(last) = self.store.get(context.endpoint, &cache_key(request)) {
+      let headers = request.headers_mut();
+      if let Some(etag) = last.etag {
+        headers.insert(IF_NONE_MATCH, etag);
+      }
+      if let Some(last_modified) = last.last_modified {
+        headers.insert(IF_MODIFIED_SINCE, last_modified);
+      }
+    }
+
+    ControlFlow::Continue(())
+  }
+
+  fn after_response(
+    &self,
+    context: &RequestContext,
+    request: &Request<Vec<u8>>,
+    result: LemmyResult<Response<Bytes>>,
+  ) -> LemmyResult<Response<Bytes>> {
+    let endpoint = context.endpoint;
+    let mut response = match result {
+      Ok(response) if endpoint.method() == Method::GET => response,
+      result => return result,
+    };
+
+    if response.status() == StatusCode::NOT_MODIFIED {
+      // Without the response received last, e.g. because the store dropped it, there is nothing
+      // to return.
+      return match self.mode {
+        NotModifiedMode::Error => Err(ClientError::NotModified),
+        NotModifiedMode::ReturnCached => self
+          .store
+          .get(endpoint, &cache_key(request))
+          .map(CachedResponse::into_response)
+          .ok_or(ClientError::NotModified),
+      };
+    }
+
+    let cached = CachedResponse::from_response(&mut response, SystemTime::now());
+    if response.status().is_success() && (cached.etag.is_some() || cached.last_modified.is_some()) {
+      self.store.insert(endpoint, &cache_key(request), cached);
+    }
+
+    Ok(response)
+  }
+}
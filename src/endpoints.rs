@@ -1,3 +1,107 @@
+/// Generates the endpoint methods of the async [`LemmyClient`][crate::LemmyClient] from their
+/// definitions, along with a blocking version of each on
+/// [`blocking::LemmyClient`][crate::blocking::LemmyClient] when the `blocking` feature is enabled.
+///
+/// Each blocking method blocks on the async method of the same name, so both clients share the
+/// same routes and serialization. Methods returning a stream of pages get a blocking version that
+/// returns an iterator instead.
+macro_rules! client_methods {
+  (@munch [$($async:tt)*] [$($blocking:tt)*]) => {
+    impl LemmyClient {
+      $($async)*
+    }
+
+    #[cfg(all(feature = "blocking", not(target_arch = "wasm32")))]
+    impl crate::blocking::LemmyClient {
+      $($blocking)*
+    }
+  };
+  (
+    @munch [$($async:tt)*] [$($blocking:tt)*]
+    $(#[$attr:meta])*
+    pub async fn $name:ident(&mut $self:ident) -> LemmyResult<$ret:ty> $body:block
+    $($rest:tt)*
+  ) => {
+    client_methods! {
+      @munch
+      [
+        $($async)*
+        $(#[$attr])*
+        pub async fn $name(&mut $self) -> LemmyResult<$ret> $body
+      ]
+      [
+        $($blocking)*
+        #[doc = concat!(
+          "Blocking version of [`LemmyClient::", stringify!($name), "`](crate::LemmyClient::",
+          stringify!($name), ")."
+        )]
+        pub fn $name(&mut self) -> crate::LemmyResult<$ret> {
+          self.runtime.block_on(self.client.$name())
+        }
+      ]
+      $($rest)*
+    }
+  };
+  (
+    @munch [$($async:tt)*] [$($blocking:tt)*]
+    $(#[$attr:meta])*
+    pub async fn $name:ident(&$self:ident $(, $arg:ident: $ty:ty)* $(,)?) -> LemmyResult<$ret:ty>
+    $body:block
+    $($rest:tt)*
+  ) => {
+    client_methods! {
+      @munch
+      [
+        $($async)*
+        $(#[$attr])*
+        pub async fn $name(&$self $(, $arg: $ty)*) -> LemmyResult<$ret> $body
+      ]
+      [
+        $($blocking)*
+        #[doc = concat!(
+          "Blocking version of [`LemmyClient::", stringify!($name), "`](crate::LemmyClient::",
+          stringify!($name), ")."
+        )]
+        pub fn $name(&self $(, $arg: $ty)*) -> crate::LemmyResult<$ret> {
+          self.runtime.block_on(self.client.$name($($arg),*))
+        }
+      ]
+      $($rest)*
+    }
+  };
+  (
+    @munch [$($async:tt)*] [$($blocking:tt)*]
+    $(#[$attr:meta])*
+    pub fn $name:ident(&$self:ident $(, $arg:ident: $ty:ty)* $(,)?)
+      -> impl Stream<Item = LemmyResult<$item:ty>> + '_
+    $body:block
+    $($rest:tt)*
+  ) => {
+    client_methods! {
+      @munch
+      [
+        $($async)*
+        $(#[$attr])*
+        pub fn $name(&$self $(, $arg: $ty)*) -> impl Stream<Item = LemmyResult<$item>> + '_ $body
+      ]
+      [
+        $($blocking)*
+        #[doc = concat!(
+          "Blocking version of [`LemmyClient::", stringify!($name), "`](crate::LemmyClient::",
+          stringify!($name), "). Each page is fetched when the iterator reaches it."
+        )]
+        pub fn $name(&self $(, $arg: $ty)*) -> impl Iterator<Item = crate::LemmyResult<$item>> + '_ {
+          self.block_on_stream(self.client.$name($($arg),*))
+        }
+      ]
+      $($rest)*
+    }
+  };
+  ($($methods:tt)*) => {
+    client_methods! { @munch [] [] $($methods)* }
+  };
+}
+
 pub mod account;
 pub mod admin;
 pub mod comment;
@@ -11,3 +115,271 @@ pub mod post;
 pub mod private_message;
 pub mod report;
 pub mod site;
+
+use crate::ApiVersion;
+use http::Method;
+
+/// Expands to `Some` of its argument, or `None` if there is none.
+macro_rules! optional {
+  () => {
+    None
+  };
+  ($value:expr) => {
+    Some($value)
+  };
+}
+
+/// The HTTP method of an endpoint on API v3: the one given for v3, or else the one of v4.
+macro_rules! v3_method {
+  ($method:ident) => {
+    Method::$method
+  };
+  ($method:ident $v3_method:ident) => {
+    Method::$v3_method
+  };
+}
+
+/// Generates [`Endpoint`] from a table of variant, client method, HTTP method and route, followed
+/// by the route on API v3, and its HTTP method if it differs, for endpoints that exist there with
+/// the same request and response.
+macro_rules! endpoints {
+  (
+    $(
+      $variant:ident => $name:ident, $method:ident $path:literal
+      $(| v3 $($v3_method:ident)? $v3_path:literal)?;
+    )*
+  ) => {
+    /// Identifies one of the Lemmy API endpoints wrapped by [`LemmyClient`][crate::LemmyClient].
+    ///
+    /// Each variant is named after the client method that calls it and knows its HTTP method and
+    /// route, relative to the API base path.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    #[non_exhaustive]
+    pub enum Endpoint {
+      $(
+        #[doc = concat!(
+          "[`LemmyClient::", stringify!($name), "`](crate::LemmyClient::", stringify!($name),
+          "): HTTP ", stringify!($method), " /", $path
+        )]
+        $variant,
+      )*
+    }
+
+    impl Endpoint {
+      /// Every endpoint, in the order they are declared.
+      pub const ALL: &[Endpoint] = &[$(Endpoint::$variant),*];
+
+      /// The name of the [`LemmyClient`][crate::LemmyClient] method that calls this endpoint,
+      /// e.g. `list_posts`.
+      pub fn name(self) -> &'static str {
+        match self {
+          $(Endpoint::$variant => stringify!($name),)*
+        }
+      }
+
+      /// The HTTP method used by this endpoint.
+      pub fn method(self) -> Method {
+        match self {
+          $(Endpoint::$variant => Method::$method,)*
+        }
+      }
+
+      /// The route of this endpoint, relative to the API base path, e.g. `post/list`.
+      pub fn path(self) -> &'static str {
+        match self {
+          $(Endpoint::$variant => $path,)*
+        }
+      }
+
+      /// The route of this endpoint on instances speaking `version` of the API, relative to the
+      /// API base path. Returns `None` if the endpoint doesn't exist there, or has a different
+      /// request or response.
+      pub fn path_for(self, version: ApiVersion) -> Option<&'static str> {
+        match version {
+          ApiVersion::V4 => Some(self.path()),
+          ApiVersion::V3 => match self {
+            $(Endpoint::$variant => optional!($($v3_path)?),)*
+          },
+        }
+      }
+
+      /// The HTTP method of this endpoint on instances speaking `version` of the API, e.g. PUT
+      /// for [`ChangePassword`][Endpoint::ChangePassword] on API v3.
+      pub fn method_for(self, version: ApiVersion) -> Method {
+        match version {
+          ApiVersion::V4 => self.method(),
+          ApiVersion::V3 => match self {
+            $(Endpoint::$variant => v3_method!($method $($($v3_method)?)?),)*
+          },
+        }
+      }
+    }
+  };
+}
+
+endpoints! {
+  RegisterAccount => register_account, POST "account/auth/register";
+  Login => login, POST "account/auth/login" | v3 "user/login";
+  Logout => logout, POST "account/auth/logout" | v3 "user/logout";
+  ResetPassword => reset_password, POST "account/auth/password_reset" | v3 "user/password_reset";
+  ChangePasswordAfterReset => change_password_after_reset, POST "account/auth/password_change" | v3 "user/password_change";
+  ChangePassword => change_password, POST "account/auth/change_password" | v3 PUT "user/change_password";
+  GenerateTotpSecret => generate_totp_secret, POST "account/auth/totp/generate";
+  EditTotp => edit_totp, POST "account/auth/totp/update";
+  VerifyEmail => verify_email, POST "account/auth/verify_email" | v3 "user/verify_email";
+  ResendVerificationEmail => resend_verification_email, POST "account/auth/resend_verification_email";
+  GetCaptcha => get_captcha, GET "account/auth/get_captcha";
+  GetCurrentUser => get_current_user, GET "account";
+  UnreadCounts => unread_counts, GET "account/unread_counts";
+  DeleteImage => delete_image, DELETE "account/media";
+  ListMedia => list_media, GET "account/media/list";
+  ListNotifications => list_notifications, GET "account/notification/list";
+  MarkAllNotificationsAsRead => mark_all_notifications_as_read, POST "account/notification/mark_as_read/all";
+  MarkNotificationAsRead => mark_notification_as_read, POST "account/notification/mark_as_read";
+  DeleteAccount => delete_account, POST "account/delete";
+  ListLogins => list_logins, GET "account/login/list";
+  ValidateAuth => validate_auth, GET "account/validate_auth" | v3 "user/validate_auth";
+  DonationDialogShown => donation_dialog_shown, POST "account/donation_dialog_shown";
+  UploadUserAvatar => upload_user_avatar, POST "account/avatar";
+  DeleteUserAvatar => delete_user_avatar, DELETE "account/avatar";
+  UploadUserBanner => upload_user_banner, POST "account/banner";
+  DeleteUserBanner => delete_user_banner, DELETE "account/banner";
+  BlockPerson => block_person, POST "account/block/person";
+  BlockCommunity => block_community, POST "account/block/community";
+  UserBlockInstanceCommunities => user_block_instance_communities, POST "account/block/instance/communities";
+  UserBlockInstancePersons => user_block_instance_persons, POST "account/block/instance/persons";
+  ListSaved => list_saved, GET "account/saved";
+  ListRead => list_read, GET "account/read";
+  ListHidden => list_hidden, GET "account/hidden";
+  ListLiked => list_liked, GET "account/liked";
+  SaveUserSettings => save_user_settings, PUT "account/settings/save";
+  ExportSettings => export_settings, GET "account/settings/export";
+  ImportSettings => import_settings, POST "account/settings/import";
+  ExportUserData => export_user_data, GET "account/data/export";
+
+  AddAdmin => add_admin, POST "admin/add";
+  GetRegistrationApplication => get_registration_application, GET "admin/registration_application";
+  ListRegistrationApplications => list_registration_applications, GET "admin/registration_application/list";
+  ApproveRegistrationApplication => approve_registration_application, PUT "admin/registration_application/approve";
+  PurgePerson => purge_person, POST "admin/purge/person";
+  PurgeCommunity => purge_community, POST "admin/purge/community";
+  PurgePost => purge_post, POST "admin/purge/post";
+  PurgeComment => purge_comment, POST "admin/purge/comment";
+  CreateTagline => create_tagline, POST "admin/tagline";
+  EditTagline => edit_tagline, PUT "admin/tagline";
+  DeleteTagline => delete_tagline, POST "admin/tagline/delete";
+  ListTaglines => list_taglines, GET "admin/tagline/list";
+  BanFromSite => ban_from_site, POST "admin/ban";
+  ListUsers => list_users, GET "admin/users";
+  AdminBlockInstance => admin_block_instance, POST "admin/instance/block";
+  AdminAllowInstance => admin_allow_instance, POST "admin/instance/allow";
+
+  GetComment => get_comment, GET "comment";
+  CreateComment => create_comment, POST "comment";
+  EditComment => edit_comment, PUT "comment";
+  DeleteComment => delete_comment, POST "comment/delete";
+  RemoveComment => remove_comment, POST "comment/remove";
+  DistinguishComment => distinguish_comment, POST "comment/distinguish";
+  LikeComment => like_comment, POST "comment/like";
+  ListCommentLikes => list_comment_likes, GET "comment/like/list";
+  SaveComment => save_comment, PUT "comment/save";
+  LockComment => lock_comment, POST "comment/lock";
+  ListComments => list_comments, GET "comment/list";
+  ListCommentsSlim => list_comments_slim, GET "comment/list/slim";
+  CreateCommentWarning => create_comment_warning, POST "comment/warn";
+  ReportComment => report_comment, POST "comment/report";
+  ResolveCommentReport => resolve_comment_report, PUT "comment/report/resolve";
+
+  GetCommunity => get_community, GET "community";
+  CreateCommunity => create_community, POST "community";
+  UpdateCommunity => update_community, PUT "community";
+  DeleteCommunity => delete_community, DELETE "community";
+  GetRandomCommunity => get_random_community, GET "community/random";
+  ListCommunities => list_communities, GET "community/list";
+  FollowCommunity => follow_community, POST "community/follow";
+  ReportCommunity => report_community, POST "community/report";
+  ResolveCommunityReport => resolve_community_report, PUT "community/report/resolve";
+  RemoveCommunity => remove_community, POST "community/remove";
+  TransferCommunity => transfer_community, POST "community/transfer";
+  BanFromCommunity => ban_from_community, POST "community/ban_user";
+  AddModToCommunity => add_mod_to_community, POST "community/mod";
+  UploadCommunityIcon => upload_community_icon, POST "community/icon";
+  DeleteCommunityIcon => delete_community_icon, DELETE "community/icon";
+  UploadCommunityBanner => upload_community_banner, POST "community/banner";
+  DeleteCommunityBanner => delete_community_banner, DELETE "community/banner";
+  CreateCommunityTag => create_community_tag, POST "community/tag";
+  EditCommunityTag => edit_community_tag, PUT "community/tag";
+  DeleteCommunityTag => delete_community_tag, DELETE "community/tag";
+  EditCommunityNotifications => edit_community_notifications, POST "community/notifications";
+  ListCommunityPendingFollows => list_community_pending_follows, GET "community/pending_follows/list";
+  ApproveCommunityPendingFollow => approve_community_pending_follow, POST "community/pending_follows/approve";
+
+  CreateCustomEmoji => create_custom_emoji, POST "custom_emoji";
+  EditCustomEmoji => edit_custom_emoji, PUT "custom_emoji";
+  DeleteCustomEmoji => delete_custom_emoji, POST "custom_emoji/delete";
+  ListCustomEmojis => list_custom_emojis, GET "custom_emoji/list";
+
+  UploadImage => upload_image, POST "image";
+  DeleteImageAdmin => delete_image_admin, DELETE "image";
+  PictrsHealth => pictrs_health, GET "image/health";
+  ListAllMedia => list_all_media, GET "image/list";
+
+  CreateMultiCommunity => create_multi_community, POST "multi_community";
+  EditMultiCommunity => edit_multi_community, PUT "multi_community";
+  GetMultiCommunity => get_multi_community, GET "multi_community";
+  AddMultiCommunityEntry => add_multi_community_entry, POST "multi_community/entry";
+  RemoveMultiCommunityEntry => remove_multi_community_entry, DELETE "multi_community/entry";
+  ListMultiCommunities => list_multi_communities, GET "multi_community/list";
+  FollowMultiCommunity => follow_multi_community, POST "multi_community/follow";
+
+  CreateOauthProvider => create_oauth_provider, POST "oauth_provider";
+  EditOauthProvider => edit_oauth_provider, PUT "oauth_provider";
+  DeleteOauthProvider => delete_oauth_provider, POST "oauth_provider/delete";
+  AuthenticateWithOauth => authenticate_with_oauth, POST "oauth/authenticate";
+
+  GetPersonDetails => get_person_details, GET "person";
+  ListPersons => list_persons, GET "person/list";
+  ListPersonContent => list_person_content, GET "person/content";
+  CreatePersonNote => create_person_note, POST "person/note";
+
+  GetPost => get_post, GET "post";
+  CreatePost => create_post, POST "post";
+  EditPost => edit_post, PUT "post";
+  GetLinkedSiteMetadata => get_linked_site_metadata, GET "post/site_metadata";
+  DeletePost => delete_post, POST "post/delete";
+  RemovePost => remove_post, POST "post/remove";
+  MarkPostAsRead => mark_post_as_read, POST "post/mark_as_read";
+  MarkManyPostsAsRead => mark_many_posts_as_read, POST "post/mark_as_read/many";
+  HidePost => hide_post, POST "post/hide";
+  LockPost => lock_post, POST "post/lock";
+  FeaturePost => feature_post, POST "post/feature";
+  ListPosts => list_posts, GET "post/list";
+  LikePost => like_post, POST "post/like";
+  ListPostLikes => list_post_likes, GET "post/like/list";
+  SavePost => save_post, PUT "post/save";
+  ReportPost => report_post, POST "post/report";
+  ResolvePostReport => resolve_post_report, PUT "post/report/resolve";
+  EditPostNotifications => edit_post_notifications, PUT "post/notifications";
+  ModEditPost => mod_edit_post, PUT "post/mod_edit";
+  CreatePostWarning => create_post_warning, POST "post/warn";
+
+  CreatePrivateMessage => create_private_message, POST "private_message";
+  EditPrivateMessage => edit_private_message, PUT "private_message";
+  DeletePrivateMessage => delete_private_message, POST "private_message/delete";
+  ReportPrivateMessage => report_private_message, POST "private_message/report";
+  ResolvePrivateMessageReport => resolve_private_message_report, PUT "private_message/report/resolve";
+
+  ListReports => list_reports, GET "report/list";
+
+  GetSite => get_site, GET "site";
+  CreateSite => create_site, POST "site";
+  EditSite => edit_site, PUT "site";
+  UploadSiteIcon => upload_site_icon, POST "site/icon";
+  DeleteSiteIcon => delete_site_icon, DELETE "site/icon";
+  UploadSiteBanner => upload_site_banner, POST "site/banner";
+  DeleteSiteBanner => delete_site_banner, DELETE "site/banner";
+  GetModlog => get_modlog, GET "modlog";
+  Search => search, GET "search";
+  ResolveObject => resolve_object, GET "resolve_object";
+  GetFederatedInstances => get_federated_instances, GET "federated_instances";
+}
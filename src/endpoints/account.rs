@@ -1,5 +1,6 @@
-use crate::{LemmyClient, LemmyResult};
-use http::{Method, header::AUTHORIZATION};
+use crate::{Endpoint, LemmyClient, LemmyResult, PaginationOptions, UploadBody, paginate};
+use futures_util::Stream;
+use http::header::AUTHORIZATION;
 use lemmy_api_common::{
   PagedResponse,
   SuccessResponse,
@@ -38,43 +39,38 @@ use lemmy_api_common::{
   post::PostView,
   site::UnreadCountsResponse,
 };
-use reqwest::Body;
 
-impl LemmyClient {
+client_methods! {
   /// Registers a new account on an instance.
   ///
   /// HTTP POST /account/auth/register
   pub async fn register_account(&self, data: Register) -> LemmyResult<LoginResponse> {
-    self
-      .make_request(Method::POST, "account/auth/register", data)
-      .await
+    self.make_request(Endpoint::RegisterAccount, data).await
   }
 
   /// Logs into the instance, giving you a JWT to use to make authorized requests.
   ///
   /// HTTP POST /account/auth/login
   pub async fn login(&self, data: Login) -> LemmyResult<LoginResponse> {
-    self
-      .make_request(Method::POST, "account/auth/login", data)
-      .await
+    self.make_request(Endpoint::Login, data).await
   }
 
   /// Deletes the active session associated with the JWT.
   /// If the response is successful, the JWT from the headers the client
-  /// sends with each request is also removed.
+  /// sends with each request is also removed, and the client's
+  /// [`Session`][crate::Session] is ended.
   ///
   /// HTTP POST /account/auth/logout
   pub async fn logout(&mut self) -> LemmyResult<SuccessResponse> {
-    let response = self
-      .make_request(Method::POST, "account/auth/logout", ())
-      .await;
+    let response = self.make_request(Endpoint::Logout, ()).await?;
 
-    if response.is_ok() {
-      let headers = self.headers_mut();
-      headers.remove(AUTHORIZATION);
-    }
+    let headers = self.headers_mut();
+    headers.remove(AUTHORIZATION);
+    // The instance already ended the session, so failing to remove it from the store shouldn't
+    // fail the logout.
+    let _ = self.session.clear();
 
-    response
+    Ok(response)
   }
 
   /// Sends an email to your account (if you have one) with a one time link to change your password.
@@ -82,9 +78,7 @@ impl LemmyClient {
   ///
   /// HTTP POST /account/auth/password_reset
   pub async fn reset_password(&self, data: ResetPassword) -> LemmyResult<SuccessResponse> {
-    self
-      .make_request(Method::POST, "account/auth/password_reset", data)
-      .await
+    self.make_request(Endpoint::ResetPassword, data).await
   }
 
   /// Follows through with one time link password reset request.
@@ -95,7 +89,7 @@ impl LemmyClient {
     data: ChangePasswordAfterReset,
   ) -> LemmyResult<SuccessResponse> {
     self
-      .make_request(Method::POST, "account/auth/password_change", data)
+      .make_request(Endpoint::ChangePasswordAfterReset, data)
       .await
   }
 
@@ -103,9 +97,7 @@ impl LemmyClient {
   ///
   /// HTTP PUT /account/auth/change_password
   pub async fn change_password(&self, data: ChangePassword) -> LemmyResult<LoginResponse> {
-    self
-      .make_request(Method::POST, "account/auth/change_password", data)
-      .await
+    self.make_request(Endpoint::ChangePassword, data).await
   }
 
   /// Generates a secret to enable time-based one time passwords for two-factor authentication.
@@ -114,9 +106,7 @@ impl LemmyClient {
   ///
   /// HTTP POST /account/auth/totp/generate
   pub async fn generate_totp_secret(&self) -> LemmyResult<GenerateTotpSecretResponse> {
-    self
-      .make_request(Method::POST, "account/auth/totp/generate", ())
-      .await
+    self.make_request(Endpoint::GenerateTotpSecret, ()).await
   }
 
   /// Enables/disables two-factor authentication.
@@ -128,9 +118,7 @@ impl LemmyClient {
   ///
   /// HTTP POST /account/auth/totp/update
   pub async fn edit_totp(&self, data: EditTotp) -> LemmyResult<EditTotpResponse> {
-    self
-      .make_request(Method::POST, "account/auth/totp/update", data)
-      .await
+    self.make_request(Endpoint::EditTotp, data).await
   }
 
   /// Verifies your email. Used when the instance you are registering an account on requires email
@@ -138,9 +126,7 @@ impl LemmyClient {
   ///
   /// HTTP POST /account/auth/verify_email
   pub async fn verify_email(&self, data: VerifyEmail) -> LemmyResult<SuccessResponse> {
-    self
-      .make_request(Method::POST, "account/auth/verify_email", data)
-      .await
+    self.make_request(Endpoint::VerifyEmail, data).await
   }
 
   /// Resend a verification email.
@@ -151,7 +137,7 @@ impl LemmyClient {
     data: ResendVerificationEmail,
   ) -> LemmyResult<SuccessResponse> {
     self
-      .make_request(Method::POST, "account/auth/resend_verification_email", data)
+      .make_request(Endpoint::ResendVerificationEmail, data)
       .await
   }
 
@@ -159,43 +145,46 @@ impl LemmyClient {
   ///
   /// HTTP GET /account/auth/get_captcha
   pub async fn get_captcha(&self) -> LemmyResult<GetCaptchaResponse> {
-    self
-      .make_request(Method::GET, "account/auth/get_captcha", ())
-      .await
+    self.make_request(Endpoint::GetCaptcha, ()).await
   }
 
   /// Return the user associated with the JWT token passed.
   ///
   /// HTTP GET /account
   pub async fn get_current_user(&self) -> LemmyResult<MyUserInfo> {
-    self.make_request(Method::GET, "account", ()).await
+    self.make_request(Endpoint::GetCurrentUser, ()).await
   }
 
   /// Gets number of unreads
   ///
   /// HTTP GET /account/unread_count
   pub async fn unread_counts(&self) -> LemmyResult<UnreadCountsResponse> {
-    self
-      .make_request(Method::GET, "account/unread_counts", ())
-      .await
+    self.make_request(Endpoint::UnreadCounts, ()).await
   }
 
   /// Delete an image that you uploaded.
   ///
   /// HTTP DELETE /account/media
   pub async fn delete_image(&self, data: DeleteImageParams) -> LemmyResult<SuccessResponse> {
-    self
-      .make_request(Method::DELETE, "account/media", data)
-      .await
+    self.make_request(Endpoint::DeleteImage, data).await
   }
 
   /// Gets all media posted by the logged in user.
   ///
   /// HTTP GET /account/media/list
   pub async fn list_media(&self, data: ListMedia) -> LemmyResult<PagedResponse<LocalImageView>> {
-    self
-      .make_request(Method::GET, "account/media/list", data)
-      .await
+    self.make_request(Endpoint::ListMedia, data).await
+  }
+
+  /// Streams all media you have uploaded, fetching pages as they are consumed.
+  ///
+  /// See [`paginate`] for details.
+  pub fn list_media_stream(
+    &self,
+    data: ListMedia,
+    options: PaginationOptions,
+  ) -> impl Stream<Item = LemmyResult<LocalImageView>> + '_ {
+    paginate(data, options, |data| self.list_media(data))
   }
 
   /// Gets all notifications for the logged in user.
@@ -205,9 +194,18 @@ impl LemmyClient {
     &self,
     data: ListNotifications,
   ) -> LemmyResult<PagedResponse<NotificationView>> {
-    self
-      .make_request(Method::GET, "/account/notification/list", data)
-      .await
+    self.make_request(Endpoint::ListNotifications, data).await
+  }
+
+  /// Streams all of your notifications, fetching pages as they are consumed.
+  ///
+  /// See [`paginate`] for details.
+  pub fn list_notifications_stream(
+    &self,
+    data: ListNotifications,
+    options: PaginationOptions,
+  ) -> impl Stream<Item = LemmyResult<NotificationView>> + '_ {
+    paginate(data, options, |data| self.list_notifications(data))
   }
 
   /// Marks all notifications (replies, mentions, private messages) as read.
@@ -215,7 +213,7 @@ impl LemmyClient {
   /// HTTP POST /account/mark_as_read/all
   pub async fn mark_all_notifications_as_read(&self) -> LemmyResult<SuccessResponse> {
     self
-      .make_request(Method::POST, "account/notification/mark_as_read/all", ())
+      .make_request(Endpoint::MarkAllNotificationsAsRead, ())
       .await
   }
 
@@ -227,7 +225,7 @@ impl LemmyClient {
     data: MarkNotificationAsRead,
   ) -> LemmyResult<SuccessResponse> {
     self
-      .make_request(Method::POST, "account/notification/mark_as_read", data)
+      .make_request(Endpoint::MarkNotificationAsRead, data)
       .await
   }
 
@@ -235,36 +233,28 @@ impl LemmyClient {
   ///
   /// HTTP POST /account/delete
   pub async fn delete_account(&self, data: DeleteAccount) -> LemmyResult<SuccessResponse> {
-    self
-      .make_request(Method::POST, "account/delete", data)
-      .await
+    self.make_request(Endpoint::DeleteAccount, data).await
   }
 
   /// Lists login tokens for your user's active sessions.
   ///
   /// HTTP GET /account/list_logins
   pub async fn list_logins(&self) -> LemmyResult<ListLoginsResponse> {
-    self
-      .make_request(Method::GET, "account/login/list", ())
-      .await
+    self.make_request(Endpoint::ListLogins, ()).await
   }
 
   /// Returns an error message if your auth token is invalid.
   ///
   /// HTTP GET /account/validate_auth
   pub async fn validate_auth(&self) -> LemmyResult<SuccessResponse> {
-    self
-      .make_request(Method::GET, "account/validate_auth", ())
-      .await
+    self.make_request(Endpoint::ValidateAuth, ()).await
   }
 
   /// Make donation dialog appear for users of your instance even if a user dismissed it before.
   ///
   /// HTTP POST /account/donation_dialog_shown
   pub async fn donation_dialog_shown(&self) -> LemmyResult<SuccessResponse> {
-    self
-      .make_request(Method::POST, "account/donation_dialog_shown", ())
-      .await
+    self.make_request(Endpoint::DonationDialogShown, ()).await
   }
 
   /// Upload an avatar for the currently authenticated user.
@@ -272,18 +262,18 @@ impl LemmyClient {
   /// HTTP POST /account/avatar
   pub async fn upload_user_avatar(
     &self,
-    data: impl Into<Body>,
+    data: impl Into<UploadBody>,
   ) -> LemmyResult<UploadImageResponse> {
-    self.make_file_request("account/avatar", (), data).await
+    self
+      .make_file_request(Endpoint::UploadUserAvatar, (), data)
+      .await
   }
 
   /// Delete the avatar for the currently authenticated user.
   ///
   /// HTTP DELETE /account/avatar
   pub async fn delete_user_avatar(&self) -> LemmyResult<SuccessResponse> {
-    self
-      .make_request(Method::DELETE, "account/avatar", ())
-      .await
+    self.make_request(Endpoint::DeleteUserAvatar, ()).await
   }
 
   /// Upload a banner for the currently authenticated user.
@@ -291,36 +281,32 @@ impl LemmyClient {
   /// HTTP POST /account/banner
   pub async fn upload_user_banner(
     &self,
-    data: impl Into<Body>,
+    data: impl Into<UploadBody>,
   ) -> LemmyResult<UploadImageResponse> {
-    self.make_file_request("account/banner", (), data).await
+    self
+      .make_file_request(Endpoint::UploadUserBanner, (), data)
+      .await
   }
 
   /// Deletes the banner for the currently authenticated in user.
   ///
   /// HTTP DELETE /account/banner
   pub async fn delete_user_banner(&self) -> LemmyResult<SuccessResponse> {
-    self
-      .make_request(Method::DELETE, "account/banner", ())
-      .await
+    self.make_request(Endpoint::DeleteUserBanner, ()).await
   }
 
   /// Blocks a person.
   ///
   /// HTTP POST /account/block/person
   pub async fn block_person(&self, data: BlockPerson) -> LemmyResult<PersonResponse> {
-    self
-      .make_request(Method::POST, "account/block/person", data)
-      .await
+    self.make_request(Endpoint::BlockPerson, data).await
   }
 
   /// Blocks a community.
   ///
   /// HTTP POST /account/block/community
   pub async fn block_community(&self, data: BlockCommunity) -> LemmyResult<CommunityResponse> {
-    self
-      .make_request(Method::POST, "account/block/community", data)
-      .await
+    self.make_request(Endpoint::BlockCommunity, data).await
   }
 
   /// Prevents posts from communities from the blocked instance from appearing in your feed.
@@ -331,7 +317,7 @@ impl LemmyClient {
     data: UserBlockInstanceCommunitiesParams,
   ) -> LemmyResult<SuccessResponse> {
     self
-      .make_request(Method::POST, "account/block/instance/communities", data)
+      .make_request(Endpoint::UserBlockInstanceCommunities, data)
       .await
   }
 
@@ -343,7 +329,7 @@ impl LemmyClient {
     data: UserBlockInstancePersonsParams,
   ) -> LemmyResult<SuccessResponse> {
     self
-      .make_request(Method::POST, "account/block/instance/persons", data)
+      .make_request(Endpoint::UserBlockInstancePersons, data)
       .await
   }
 
@@ -354,7 +340,7 @@ impl LemmyClient {
     &self,
     data: ListPersonSaved,
   ) -> LemmyResult<PagedResponse<PostCommentCombinedView>> {
-    self.make_request(Method::GET, "account/saved", data).await
+    self.make_request(Endpoint::ListSaved, data).await
   }
 
   /// List posts and comments that were read by the authenticated user in reverse chronological
@@ -362,14 +348,14 @@ impl LemmyClient {
   ///
   /// HTTP GET /account/read
   pub async fn list_read(&self, data: ListPersonRead) -> LemmyResult<PagedResponse<PostView>> {
-    self.make_request(Method::GET, "account/read", data).await
+    self.make_request(Endpoint::ListRead, data).await
   }
 
   /// List posts and comments that were hidden by the authenticated user, ordered by date hidden.
   ///
   /// HTTP GET /account/hidden
   pub async fn list_hidden(&self, data: ListPersonHidden) -> LemmyResult<PagedResponse<PostView>> {
-    self.make_request(Method::GET, "account/hidden", data).await
+    self.make_request(Endpoint::ListHidden, data).await
   }
 
   /// List posts and comments that were liked by the authenticated user.
@@ -379,16 +365,14 @@ impl LemmyClient {
     &self,
     data: ListPersonLiked,
   ) -> LemmyResult<PagedResponse<PostCommentCombinedView>> {
-    self.make_request(Method::GET, "account/liked", data).await
+    self.make_request(Endpoint::ListLiked, data).await
   }
 
   /// Saves your account settings.
   ///
   /// HTTP PUT /account/settings/save
   pub async fn save_user_settings(&self, data: SaveUserSettings) -> LemmyResult<SuccessResponse> {
-    self
-      .make_request(Method::PUT, "account/settings/save", data)
-      .await
+    self.make_request(Endpoint::SaveUserSettings, data).await
   }
 
   /// Exports a backup of your user settings - including your saved content, followed communities,
@@ -396,26 +380,20 @@ impl LemmyClient {
   ///
   /// HTTP GET /account/settings/export
   pub async fn export_settings(&self) -> LemmyResult<UserSettingsBackup> {
-    self
-      .make_request(Method::GET, "account/settings/export", ())
-      .await
+    self.make_request(Endpoint::ExportSettings, ()).await
   }
 
   /// Imports a backup of your user settings.
   ///
   /// HTTP POST /account/settings/import
   pub async fn import_settings(&self, data: UserSettingsBackup) -> LemmyResult<SuccessResponse> {
-    self
-      .make_request(Method::POST, "account/settings/import", data)
-      .await
+    self.make_request(Endpoint::ImportSettings, data).await
   }
 
   /// Export data for the currently authenticated user.
   ///
   /// HTTP GET /account/data/export
   pub async fn export_user_data(&self) -> LemmyResult<ExportDataResponse> {
-    self
-      .make_request(Method::GET, "account/data/export", ())
-      .await
+    self.make_request(Endpoint::ExportUserData, ()).await
   }
 }
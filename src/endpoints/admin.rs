@@ -1,5 +1,5 @@
-use crate::{LemmyClient, LemmyResult};
-use http::Method;
+use crate::{Endpoint, LemmyClient, LemmyResult, PaginationOptions, paginate};
+use futures_util::Stream;
 use lemmy_api_common::{
   self,
   PagedResponse,
@@ -34,12 +34,12 @@ use lemmy_api_common::{
   },
 };
 
-impl LemmyClient {
+client_methods! {
   /// Adds a user to your instance's admin team.
   ///
   /// HTTP POST admin/add
   pub async fn add_admin(&self, data: AddAdmin) -> LemmyResult<AddAdminResponse> {
-    self.make_request(Method::POST, "admin/add", data).await
+    self.make_request(Endpoint::AddAdmin, data).await
   }
 
   /// Get the application a user submitted when they first registered their account
@@ -50,7 +50,7 @@ impl LemmyClient {
     data: GetRegistrationApplication,
   ) -> LemmyResult<RegistrationApplicationResponse> {
     self
-      .make_request(Method::GET, "admin/registration_application", data)
+      .make_request(Endpoint::GetRegistrationApplication, data)
       .await
   }
 
@@ -62,7 +62,7 @@ impl LemmyClient {
     data: ListRegistrationApplications,
   ) -> LemmyResult<PagedResponse<RegistrationApplicationView>> {
     self
-      .make_request(Method::GET, "admin/registration_application/list", data)
+      .make_request(Endpoint::ListRegistrationApplications, data)
       .await
   }
 
@@ -74,7 +74,7 @@ impl LemmyClient {
     data: ApproveRegistrationApplication,
   ) -> LemmyResult<RegistrationApplicationResponse> {
     self
-      .make_request(Method::PUT, "admin/registration_application/approve", data)
+      .make_request(Endpoint::ApproveRegistrationApplication, data)
       .await
   }
 
@@ -82,75 +82,63 @@ impl LemmyClient {
   ///
   /// HTTP POST /admin/purge/person
   pub async fn purge_person(&self, data: PurgePerson) -> LemmyResult<SuccessResponse> {
-    self
-      .make_request(Method::POST, "admin/purge/person", data)
-      .await
+    self.make_request(Endpoint::PurgePerson, data).await
   }
 
   /// Purges a community from the database.
   ///
   /// HTTP POST /admin/purge/community
   pub async fn purge_community(&self, data: PurgeCommunity) -> LemmyResult<SuccessResponse> {
-    self
-      .make_request(Method::POST, "admin/purge/community", data)
-      .await
+    self.make_request(Endpoint::PurgeCommunity, data).await
   }
 
   /// Purges a post from the database.
   ///
   /// HTTP POST /admin/purge/post
   pub async fn purge_post(&self, data: PurgePost) -> LemmyResult<SuccessResponse> {
-    self
-      .make_request(Method::POST, "admin/purge/post", data)
-      .await
+    self.make_request(Endpoint::PurgePost, data).await
   }
 
   /// Purges a comment from the database.
   ///
   /// HTTP POST /admin/purge/comment
   pub async fn purge_comment(&self, data: PurgeComment) -> LemmyResult<SuccessResponse> {
-    self
-      .make_request(Method::POST, "admin/purge/comment", data)
-      .await
+    self.make_request(Endpoint::PurgeComment, data).await
   }
 
   /// Adds a new tagline to the site.
   ///
   /// HTTP POST /admin/tagline
   pub async fn create_tagline(&self, data: CreateTagline) -> LemmyResult<TaglineResponse> {
-    self.make_request(Method::POST, "admin/tagline", data).await
+    self.make_request(Endpoint::CreateTagline, data).await
   }
 
   /// Edits an existing tagline.
   ///
   /// HTTP PUT /admin/tagline
   pub async fn edit_tagline(&self, data: EditTagline) -> LemmyResult<TaglineResponse> {
-    self.make_request(Method::PUT, "admin/tagline", data).await
+    self.make_request(Endpoint::EditTagline, data).await
   }
 
   /// Deletes an existing tagline.
   ///
   /// HTTP DELETE /admin/tagline/delete
   pub async fn delete_tagline(&self, data: DeleteTagline) -> LemmyResult<SuccessResponse> {
-    self
-      .make_request(Method::POST, "admin/tagline/delete", data)
-      .await
+    self.make_request(Endpoint::DeleteTagline, data).await
   }
 
   /// Gets the site's taglines.
   ///
   /// HTTP GET /admin/tagline/list
   pub async fn list_taglines(&self, data: ListTaglines) -> LemmyResult<PagedResponse<Tagline>> {
-    self
-      .make_request(Method::GET, "admin/tagline/list", data)
-      .await
+    self.make_request(Endpoint::ListTaglines, data).await
   }
 
   /// Bans a person from your instance.
   ///
   /// HTTP POST /admin/ban
   pub async fn ban_from_site(&self, data: BanPerson) -> LemmyResult<PersonResponse> {
-    self.make_request(Method::POST, "admin/ban", data).await
+    self.make_request(Endpoint::BanFromSite, data).await
   }
 
   /// Lists users of your site.
@@ -160,7 +148,18 @@ impl LemmyClient {
     &self,
     data: AdminListUsers,
   ) -> LemmyResult<PagedResponse<LocalUserView>> {
-    self.make_request(Method::GET, "admin/users", data).await
+    self.make_request(Endpoint::ListUsers, data).await
+  }
+
+  /// Streams all users on the instance, fetching pages as they are consumed.
+  ///
+  /// See [`paginate`] for details.
+  pub fn list_users_stream(
+    &self,
+    data: AdminListUsers,
+    options: PaginationOptions,
+  ) -> impl Stream<Item = LemmyResult<LocalUserView>> + '_ {
+    paginate(data, options, |data| self.list_users(data))
   }
 
   /// Defederates an instance from the current instance.
@@ -170,9 +169,7 @@ impl LemmyClient {
     &self,
     data: AdminBlockInstanceParams,
   ) -> LemmyResult<SuccessResponse> {
-    self
-      .make_request(Method::POST, "admin/instance/block", data)
-      .await
+    self.make_request(Endpoint::AdminBlockInstance, data).await
   }
 
   /// Allows a given instance to interact with the current instance.
@@ -182,8 +179,6 @@ impl LemmyClient {
     &self,
     data: AdminAllowInstanceParams,
   ) -> LemmyResult<SuccessResponse> {
-    self
-      .make_request(Method::POST, "admin/instance/allow", data)
-      .await
+    self.make_request(Endpoint::AdminAllowInstance, data).await
   }
 }
@@ -1,5 +1,5 @@
-use crate::{LemmyClient, LemmyResult};
-use http::Method;
+use crate::{Endpoint, LemmyClient, LemmyResult, PaginationOptions, paginate};
+use futures_util::Stream;
 use lemmy_api_common::{
   PagedResponse,
   VoteView,
@@ -23,44 +23,40 @@ use lemmy_api_common::{
   report::{CommentReportResponse, CreateCommentReport, ResolveCommentReport},
 };
 
-impl LemmyClient {
+client_methods! {
   /// Gets a comment.
   ///
   /// HTTP GET /comment
   pub async fn get_comment(&self, data: GetComment) -> LemmyResult<CommentResponse> {
-    self.make_request(Method::GET, "comment", data).await
+    self.make_request(Endpoint::GetComment, data).await
   }
 
   /// Creates a new comment.
   ///
   /// HTTP POST /comment
   pub async fn create_comment(&self, data: CreateComment) -> LemmyResult<CommentResponse> {
-    self.make_request(Method::POST, "comment", data).await
+    self.make_request(Endpoint::CreateComment, data).await
   }
 
   /// Edits one of your already-created comments.
   ///
   /// HTTP PUT /comment
   pub async fn edit_comment(&self, data: EditComment) -> LemmyResult<CommentResponse> {
-    self.make_request(Method::PUT, "comment", data).await
+    self.make_request(Endpoint::EditComment, data).await
   }
 
   /// Deletes one of your already-existing comments.
   ///
   /// HTTP POST /comment/delete
   pub async fn delete_comment(&self, data: DeleteComment) -> LemmyResult<CommentResponse> {
-    self
-      .make_request(Method::POST, "comment/delete", data)
-      .await
+    self.make_request(Endpoint::DeleteComment, data).await
   }
 
   /// Removes a post (moderator action).
   ///
   /// HTTP POST /comment/remove
   pub async fn remove_comment(&self, data: RemoveComment) -> LemmyResult<CommentResponse> {
-    self
-      .make_request(Method::POST, "comment/remove", data)
-      .await
+    self.make_request(Endpoint::RemoveComment, data).await
   }
 
   /// Pins a comment to the top of a post's comment section (speak as moderator).
@@ -70,16 +66,14 @@ impl LemmyClient {
     &self,
     data: DistinguishComment,
   ) -> LemmyResult<CommentResponse> {
-    self
-      .make_request(Method::POST, "comment/distinguish", data)
-      .await
+    self.make_request(Endpoint::DistinguishComment, data).await
   }
 
   /// Votes on a comment.
   ///
   /// HTTP POST /comment/like
   pub async fn like_comment(&self, data: CreateCommentLike) -> LemmyResult<CommentResponse> {
-    self.make_request(Method::POST, "comment/like", data).await
+    self.make_request(Endpoint::LikeComment, data).await
   }
 
   /// Gets the votes for a comment.
@@ -89,30 +83,39 @@ impl LemmyClient {
     &self,
     data: ListCommentLikes,
   ) -> LemmyResult<PagedResponse<VoteView>> {
-    self
-      .make_request(Method::GET, "comment/like/list", data)
-      .await
+    self.make_request(Endpoint::ListCommentLikes, data).await
   }
 
   /// Saves a comment to your favorites list.
   ///
   /// HTTP PUT /comment/save
   pub async fn save_comment(&self, data: SaveComment) -> LemmyResult<CommentResponse> {
-    self.make_request(Method::PUT, "comment/save", data).await
+    self.make_request(Endpoint::SaveComment, data).await
   }
 
   /// Lock a comment thread.
   ///
   /// HTTP POST /comment/lock
   pub async fn lock_comment(&self, data: LockComment) -> LemmyResult<CommentResponse> {
-    self.make_request(Method::POST, "comment/lock", data).await
+    self.make_request(Endpoint::LockComment, data).await
   }
 
   /// Gets comments with various filters.
   ///
   /// HTTP GET /comment/list
   pub async fn list_comments(&self, data: GetComments) -> LemmyResult<PagedResponse<CommentView>> {
-    self.make_request(Method::GET, "comment/list", data).await
+    self.make_request(Endpoint::ListComments, data).await
+  }
+
+  /// Streams all comments matching the filters, fetching pages as they are consumed.
+  ///
+  /// See [`paginate`] for details.
+  pub fn list_comments_stream(
+    &self,
+    data: GetComments,
+    options: PaginationOptions,
+  ) -> impl Stream<Item = LemmyResult<CommentView>> + '_ {
+    paginate(data, options, |data| self.list_comments(data))
   }
 
   /// Retrieve a slim representation of comments.
@@ -122,9 +125,7 @@ impl LemmyClient {
     &self,
     data: GetComments,
   ) -> LemmyResult<PagedResponse<CommentSlimView>> {
-    self
-      .make_request(Method::GET, "comment/list/slim", data)
-      .await
+    self.make_request(Endpoint::ListCommentsSlim, data).await
   }
 
   /// Warn a comment.
@@ -134,7 +135,9 @@ impl LemmyClient {
     &self,
     data: CreateCommentWarning,
   ) -> LemmyResult<CommentResponse> {
-    self.make_request(Method::POST, "comment/warn", data).await
+    self
+      .make_request(Endpoint::CreateCommentWarning, data)
+      .await
   }
 
   /// Reports a comment to the moderator team of the community the comment is in, your instance's
@@ -142,9 +145,7 @@ impl LemmyClient {
   ///
   /// HTTP POST /comment/report
   pub async fn report_comment(&self, data: CreateCommentReport) -> LemmyResult<CommentResponse> {
-    self
-      .make_request(Method::POST, "comment/report", data)
-      .await
+    self.make_request(Endpoint::ReportComment, data).await
   }
 
   /// Resolves a report on a comment made in a community you moderate or instance you administrate.
@@ -155,7 +156,7 @@ impl LemmyClient {
     data: ResolveCommentReport,
   ) -> LemmyResult<CommentReportResponse> {
     self
-      .make_request(Method::PUT, "comment/report/resolve", data)
+      .make_request(Endpoint::ResolveCommentReport, data)
       .await
   }
 }
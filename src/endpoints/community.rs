@@ -1,5 +1,4 @@
-use crate::{LemmyClient, LemmyResult};
-use http::Method;
+use crate::{Endpoint, LemmyClient, LemmyResult, UploadBody};
 use lemmy_api_common::{
   PagedResponse,
   SuccessResponse,
@@ -37,35 +36,34 @@ use lemmy_api_common::{
   person::PersonResponse,
   report::{CommunityReportResponse, CreateCommunityReport},
 };
-use reqwest::Body;
 
-impl LemmyClient {
+client_methods! {
   /// Gets a community.
   ///
   /// HTTP GET /community
   pub async fn get_community(&self, data: GetCommunity) -> LemmyResult<GetCommunityResponse> {
-    self.make_request(Method::GET, "community", data).await
+    self.make_request(Endpoint::GetCommunity, data).await
   }
 
   /// Creates a new community.
   ///
   /// HTTP POST /community
   pub async fn create_community(&self, data: CreateCommunity) -> LemmyResult<CommunityResponse> {
-    self.make_request(Method::POST, "community", data).await
+    self.make_request(Endpoint::CreateCommunity, data).await
   }
 
   /// Edits a community.
   ///
   /// HTTP PUT /community
   pub async fn update_community(&self, data: EditCommunity) -> LemmyResult<CommunityResponse> {
-    self.make_request(Method::PUT, "community", data).await
+    self.make_request(Endpoint::UpdateCommunity, data).await
   }
 
   /// Deletes a community.
   ///
   /// HTTP DELETE /community
   pub async fn delete_community(&self, data: DeleteCommunity) -> LemmyResult<CommunityResponse> {
-    self.make_request(Method::DELETE, "community", data).await
+    self.make_request(Endpoint::DeleteCommunity, data).await
   }
 
   /// Fetches a random community.
@@ -75,9 +73,7 @@ impl LemmyClient {
     &self,
     data: GetRandomCommunity,
   ) -> LemmyResult<CommunityResponse> {
-    self
-      .make_request(Method::GET, "community/random", data)
-      .await
+    self.make_request(Endpoint::GetRandomCommunity, data).await
   }
 
   /// Lists communities.
@@ -87,16 +83,14 @@ impl LemmyClient {
     &self,
     data: ListCommunities,
   ) -> LemmyResult<PagedResponse<CommunityView>> {
-    self.make_request(Method::GET, "community/list", data).await
+    self.make_request(Endpoint::ListCommunities, data).await
   }
 
   /// Subscribes to a community.
   ///
   /// HTTP POST /community/follow
   pub async fn follow_community(&self, data: FollowCommunity) -> LemmyResult<CommunityResponse> {
-    self
-      .make_request(Method::POST, "community/follow", data)
-      .await
+    self.make_request(Endpoint::FollowCommunity, data).await
   }
 
   /// Report a community.
@@ -106,9 +100,7 @@ impl LemmyClient {
     &self,
     data: CreateCommunityReport,
   ) -> LemmyResult<CommunityReportResponse> {
-    self
-      .make_request(Method::POST, "community/report", data)
-      .await
+    self.make_request(Endpoint::ReportCommunity, data).await
   }
 
   /// Resolves a community report.
@@ -119,7 +111,7 @@ impl LemmyClient {
     data: CreateCommunityReport,
   ) -> LemmyResult<CommunityReportResponse> {
     self
-      .make_request(Method::PUT, "community/report/resolve", data)
+      .make_request(Endpoint::ResolveCommunityReport, data)
       .await
   }
 
@@ -127,9 +119,7 @@ impl LemmyClient {
   ///
   /// HTTP POST /community/remove
   pub async fn remove_community(&self, data: RemoveCommunity) -> LemmyResult<CommunityResponse> {
-    self
-      .make_request(Method::POST, "community/remove", data)
-      .await
+    self.make_request(Endpoint::RemoveCommunity, data).await
   }
 
   /// Transfers a community you own to another user on that community's moderation team.
@@ -139,18 +129,14 @@ impl LemmyClient {
     &self,
     data: TransferCommunity,
   ) -> LemmyResult<GetCommunityResponse> {
-    self
-      .make_request(Method::POST, "community/transfer", data)
-      .await
+    self.make_request(Endpoint::TransferCommunity, data).await
   }
 
   /// Bans a user from a community.
   ///
   /// HTTP POST /community/ban_user
   pub async fn ban_from_community(&self, data: BanFromCommunity) -> LemmyResult<PersonResponse> {
-    self
-      .make_request(Method::POST, "community/ban_user", data)
-      .await
+    self.make_request(Endpoint::BanFromCommunity, data).await
   }
 
   /// Adds a moderator to your community.
@@ -160,7 +146,7 @@ impl LemmyClient {
     &self,
     data: AddModToCommunity,
   ) -> LemmyResult<AddModToCommunityResponse> {
-    self.make_request(Method::POST, "community/mod", data).await
+    self.make_request(Endpoint::AddModToCommunity, data).await
   }
 
   /// Uploads an icon to represent a community.
@@ -169,9 +155,11 @@ impl LemmyClient {
   pub async fn upload_community_icon(
     &self,
     query: CommunityIdQuery,
-    body: impl Into<Body>,
+    body: impl Into<UploadBody>,
   ) -> LemmyResult<UploadImageResponse> {
-    self.make_file_request("community/icon", query, body).await
+    self
+      .make_file_request(Endpoint::UploadCommunityIcon, query, body)
+      .await
   }
 
   /// Deletes the icon used by a community.
@@ -182,7 +170,7 @@ impl LemmyClient {
     request: CommunityIdQuery,
   ) -> LemmyResult<SuccessResponse> {
     self
-      .make_request(Method::DELETE, "community/icon", request)
+      .make_request(Endpoint::DeleteCommunityIcon, request)
       .await
   }
 
@@ -192,10 +180,10 @@ impl LemmyClient {
   pub async fn upload_community_banner(
     &self,
     query: CommunityIdQuery,
-    body: impl Into<Body>,
+    body: impl Into<UploadBody>,
   ) -> LemmyResult<UploadImageResponse> {
     self
-      .make_file_request("community/banner", query, body)
+      .make_file_request(Endpoint::UploadCommunityBanner, query, body)
       .await
   }
 
@@ -207,7 +195,7 @@ impl LemmyClient {
     request: CommunityIdQuery,
   ) -> LemmyResult<SuccessResponse> {
     self
-      .make_request(Method::DELETE, "community/banner", request)
+      .make_request(Endpoint::DeleteCommunityBanner, request)
       .await
   }
 
@@ -215,23 +203,21 @@ impl LemmyClient {
   ///
   /// HTTP POST /community/tag
   pub async fn create_community_tag(&self, data: CreateCommunityTag) -> LemmyResult<CommunityTag> {
-    self.make_request(Method::POST, "community/tag", data).await
+    self.make_request(Endpoint::CreateCommunityTag, data).await
   }
 
   /// Update an existing tag for a community you moderate.
   ///
   /// HTTP PUT /community/tag
   pub async fn edit_community_tag(&self, data: EditCommunityTag) -> LemmyResult<CommunityTag> {
-    self.make_request(Method::PUT, "community/tag", data).await
+    self.make_request(Endpoint::EditCommunityTag, data).await
   }
 
   /// Delete an existing tag for a community you moderate.
   ///
   /// HTTP DELETE /community/tag
   pub async fn delete_community_tag(&self, data: DeleteCommunityTag) -> LemmyResult<CommunityTag> {
-    self
-      .make_request(Method::DELETE, "community/tag", data)
-      .await
+    self.make_request(Endpoint::DeleteCommunityTag, data).await
   }
 
   /// Set which notifications you want to receive for a community.
@@ -242,7 +228,7 @@ impl LemmyClient {
     data: EditCommunityNotifications,
   ) -> LemmyResult<SuccessResponse> {
     self
-      .make_request(Method::POST, "community/notifications", data)
+      .make_request(Endpoint::EditCommunityNotifications, data)
       .await
   }
 
@@ -254,7 +240,7 @@ impl LemmyClient {
     data: ListCommunityPendingFollows,
   ) -> LemmyResult<PagedResponse<PendingFollowerView>> {
     self
-      .make_request(Method::GET, "community/pending_follows/list", data)
+      .make_request(Endpoint::ListCommunityPendingFollows, data)
       .await
   }
 
@@ -266,7 +252,7 @@ impl LemmyClient {
     data: ApproveCommunityPendingFollower,
   ) -> LemmyResult<SuccessResponse> {
     self
-      .make_request(Method::POST, "community/pending_follows/approve", data)
+      .make_request(Endpoint::ApproveCommunityPendingFollow, data)
       .await
   }
 }
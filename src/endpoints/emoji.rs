@@ -1,5 +1,4 @@
-use crate::{LemmyClient, LemmyResult};
-use http::Method;
+use crate::{Endpoint, LemmyClient, LemmyResult};
 use lemmy_api_common::custom_emoji::{
   CreateCustomEmoji,
   CustomEmojiResponse,
@@ -9,7 +8,7 @@ use lemmy_api_common::custom_emoji::{
   ListCustomEmojisResponse,
 };
 
-impl LemmyClient {
+client_methods! {
   /// Creates a custom emoji.
   ///
   /// HTTP POST /custom_emoji
@@ -17,14 +16,14 @@ impl LemmyClient {
     &self,
     data: CreateCustomEmoji,
   ) -> LemmyResult<CustomEmojiResponse> {
-    self.make_request(Method::POST, "custom_emoji", data).await
+    self.make_request(Endpoint::CreateCustomEmoji, data).await
   }
 
   /// Edits an existing custom emoji.
   ///
   /// HTTP PUT /custom_emoji
   pub async fn edit_custom_emoji(&self, data: EditCustomEmoji) -> LemmyResult<CustomEmojiResponse> {
-    self.make_request(Method::PUT, "custom_emoji", data).await
+    self.make_request(Endpoint::EditCustomEmoji, data).await
   }
 
   /// Deletes an existing custom emoji.
@@ -34,9 +33,7 @@ impl LemmyClient {
     &self,
     data: DeleteCustomEmoji,
   ) -> LemmyResult<CustomEmojiResponse> {
-    self
-      .make_request(Method::POST, "custom_emoji/delete", data)
-      .await
+    self.make_request(Endpoint::DeleteCustomEmoji, data).await
   }
 
   /// List all custom emojis on the instance.
@@ -46,8 +43,6 @@ impl LemmyClient {
     &self,
     data: ListCustomEmojis,
   ) -> LemmyResult<ListCustomEmojisResponse> {
-    self
-      .make_request(Method::GET, "custom_emoji/list", data)
-      .await
+    self.make_request(Endpoint::ListCustomEmojis, data).await
   }
 }
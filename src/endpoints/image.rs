@@ -1,18 +1,21 @@
-use crate::{LemmyClient, LemmyResult};
-use http::Method;
+use crate::{Endpoint, LemmyClient, LemmyResult, UploadBody};
 use lemmy_api_common::{
   PagedResponse,
   SuccessResponse,
   media::{DeleteImageParams, ListMedia, LocalImageView, UploadImageResponse},
 };
-use reqwest::Body;
 
-impl LemmyClient {
+client_methods! {
   /// Upload an image to the instance.
   ///
   /// HTTP POST /image
-  pub async fn upload_image(&self, data: impl Into<Body>) -> LemmyResult<UploadImageResponse> {
-    self.make_file_request("image", (), data).await
+  pub async fn upload_image(
+    &self,
+    data: impl Into<UploadBody>,
+  ) -> LemmyResult<UploadImageResponse> {
+    self
+      .make_file_request(Endpoint::UploadImage, (), data)
+      .await
   }
 
   /// Deletes an image from the instance.
@@ -21,14 +24,14 @@ impl LemmyClient {
   ///
   /// HTTP DELETE /image
   pub async fn delete_image_admin(&self, data: DeleteImageParams) -> LemmyResult<SuccessResponse> {
-    self.make_request(Method::DELETE, "image", data).await
+    self.make_request(Endpoint::DeleteImageAdmin, data).await
   }
 
   /// Gets the pictrs image service health.
   ///
   /// HTTP GET /image/health
   pub async fn pictrs_health(&self) -> LemmyResult<SuccessResponse> {
-    self.make_request(Method::GET, "image/health", ()).await
+    self.make_request(Endpoint::PictrsHealth, ()).await
   }
 
   /// Gets all media posted on an instance. Only usable by the instance's admins.
@@ -38,6 +41,6 @@ impl LemmyClient {
     &self,
     data: ListMedia,
   ) -> LemmyResult<PagedResponse<LocalImageView>> {
-    self.make_request(Method::GET, "image/list", data).await
+    self.make_request(Endpoint::ListAllMedia, data).await
   }
 }
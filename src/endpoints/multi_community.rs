@@ -1,5 +1,4 @@
-use crate::{LemmyClient, LemmyResult};
-use http::Method;
+use crate::{Endpoint, LemmyClient, LemmyResult};
 use lemmy_api_common::{
   PagedResponse,
   SuccessResponse,
@@ -15,7 +14,7 @@ use lemmy_api_common::{
   },
 };
 
-impl LemmyClient {
+client_methods! {
   /// Create a multi community.
   ///
   /// HTTP POST /multi_community
@@ -24,7 +23,7 @@ impl LemmyClient {
     data: CreateMultiCommunity,
   ) -> LemmyResult<GetMultiCommunityResponse> {
     self
-      .make_request(Method::POST, "multi_community", data)
+      .make_request(Endpoint::CreateMultiCommunity, data)
       .await
   }
 
@@ -35,9 +34,7 @@ impl LemmyClient {
     &self,
     data: EditMultiCommunity,
   ) -> LemmyResult<SuccessResponse> {
-    self
-      .make_request(Method::PUT, "multi_community", data)
-      .await
+    self.make_request(Endpoint::EditMultiCommunity, data).await
   }
 
   /// Get a specific multi community.
@@ -47,9 +44,7 @@ impl LemmyClient {
     &self,
     data: GetMultiCommunity,
   ) -> LemmyResult<GetMultiCommunityResponse> {
-    self
-      .make_request(Method::GET, "multi_community", data)
-      .await
+    self.make_request(Endpoint::GetMultiCommunity, data).await
   }
 
   /// Add a community to a multi community.
@@ -60,7 +55,7 @@ impl LemmyClient {
     data: CreateOrDeleteMultiCommunityEntry,
   ) -> LemmyResult<SuccessResponse> {
     self
-      .make_request(Method::POST, "multi_community/entry", data)
+      .make_request(Endpoint::AddMultiCommunityEntry, data)
       .await
   }
 
@@ -72,7 +67,7 @@ impl LemmyClient {
     data: CreateOrDeleteMultiCommunityEntry,
   ) -> LemmyResult<SuccessResponse> {
     self
-      .make_request(Method::DELETE, "multi_community/entry", data)
+      .make_request(Endpoint::RemoveMultiCommunityEntry, data)
       .await
   }
 
@@ -84,7 +79,7 @@ impl LemmyClient {
     data: ListMultiCommunities,
   ) -> LemmyResult<PagedResponse<MultiCommunityView>> {
     self
-      .make_request(Method::GET, "multi_community/list", data)
+      .make_request(Endpoint::ListMultiCommunities, data)
       .await
   }
 
@@ -96,7 +91,7 @@ impl LemmyClient {
     data: FollowMultiCommunity,
   ) -> LemmyResult<SuccessResponse> {
     self
-      .make_request(Method::POST, "multi_community/follow", data)
+      .make_request(Endpoint::FollowMultiCommunity, data)
       .await
   }
 }
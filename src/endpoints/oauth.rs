@@ -1,5 +1,4 @@
-use crate::{LemmyClient, LemmyResult};
-use http::Method;
+use crate::{Endpoint, LemmyClient, LemmyResult};
 use lemmy_api_common::{
   SuccessResponse,
   account::auth::LoginResponse,
@@ -12,7 +11,7 @@ use lemmy_api_common::{
   },
 };
 
-impl LemmyClient {
+client_methods! {
   /// Create an OAuth provider your users can use to register for and log into your instance.
   ///
   /// HTTP POST /oauth_provider
@@ -20,9 +19,7 @@ impl LemmyClient {
     &self,
     data: CreateOAuthProvider,
   ) -> LemmyResult<AdminOAuthProvider> {
-    self
-      .make_request(Method::POST, "oauth_provider", data)
-      .await
+    self.make_request(Endpoint::CreateOauthProvider, data).await
   }
 
   /// Edit one of your instance's OAuth providers.
@@ -32,7 +29,7 @@ impl LemmyClient {
     &self,
     data: EditOAuthProvider,
   ) -> LemmyResult<AdminOAuthProvider> {
-    self.make_request(Method::PUT, "oauth_provider", data).await
+    self.make_request(Endpoint::EditOauthProvider, data).await
   }
 
   /// Remove an OAuth provider from your instance.
@@ -42,9 +39,7 @@ impl LemmyClient {
     &self,
     data: DeleteOAuthProvider,
   ) -> LemmyResult<SuccessResponse> {
-    self
-      .make_request(Method::POST, "oauth_provider/delete", data)
-      .await
+    self.make_request(Endpoint::DeleteOauthProvider, data).await
   }
 
   /// Register/login to an instance using one of the OAuth providers that instance supports.
@@ -55,7 +50,7 @@ impl LemmyClient {
     data: AuthenticateWithOauth,
   ) -> LemmyResult<LoginResponse> {
     self
-      .make_request(Method::POST, "oauth/authenticate", data)
+      .make_request(Endpoint::AuthenticateWithOauth, data)
       .await
   }
 }
@@ -1,5 +1,5 @@
-use crate::{LemmyClient, LemmyResult};
-use http::Method;
+use crate::{Endpoint, LemmyClient, LemmyResult, PaginationOptions, paginate};
+use futures_util::Stream;
 use lemmy_api_common::{
   PagedResponse,
   SuccessResponse,
@@ -12,7 +12,7 @@ use lemmy_api_common::{
   },
 };
 
-impl LemmyClient {
+client_methods! {
   /// Gets the publicly viewable details of a user's account.
   ///
   /// HTTP GET /person
@@ -20,7 +20,7 @@ impl LemmyClient {
     &self,
     data: GetPersonDetails,
   ) -> LemmyResult<GetPersonDetailsResponse> {
-    self.make_request(Method::GET, "person", data).await
+    self.make_request(Endpoint::GetPersonDetails, data).await
   }
 
   /// Lists persons.
@@ -30,7 +30,7 @@ impl LemmyClient {
     &self,
     data: GetPersonDetails,
   ) -> LemmyResult<PagedResponse<PersonView>> {
-    self.make_request(Method::GET, "person/list", data).await
+    self.make_request(Endpoint::ListPersons, data).await
   }
 
   /// List posts and comments made by a user.
@@ -40,13 +40,24 @@ impl LemmyClient {
     &self,
     data: ListPersonContent,
   ) -> LemmyResult<PagedResponse<PostCommentCombinedView>> {
-    self.make_request(Method::GET, "person/content", data).await
+    self.make_request(Endpoint::ListPersonContent, data).await
+  }
+
+  /// Streams all of a user's posts and comments, fetching pages as they are consumed.
+  ///
+  /// See [`paginate`] for details.
+  pub fn list_person_content_stream(
+    &self,
+    data: ListPersonContent,
+    options: PaginationOptions,
+  ) -> impl Stream<Item = LemmyResult<PostCommentCombinedView>> + '_ {
+    paginate(data, options, |data| self.list_person_content(data))
   }
 
   /// Create a note about another user.
   ///
   /// HTTP POST /person/note
   pub async fn create_person_note(&self, data: NotePerson) -> LemmyResult<SuccessResponse> {
-    self.make_request(Method::POST, "person/note", data).await
+    self.make_request(Endpoint::CreatePersonNote, data).await
   }
 }
@@ -1,5 +1,5 @@
-use crate::{LemmyClient, LemmyResult};
-use http::Method;
+use crate::{Endpoint, LemmyClient, LemmyResult, PaginationOptions, paginate};
+use futures_util::Stream;
 use lemmy_api_common::{
   PagedResponse,
   SuccessResponse,
@@ -29,26 +29,26 @@ use lemmy_api_common::{
   report::{CreatePostReport, PostReportResponse, ResolvePostReport},
 };
 
-impl LemmyClient {
+client_methods! {
   /// Gets a post.
   ///
   /// HTTP GET /post
   pub async fn get_post(&self, data: GetPost) -> LemmyResult<GetPostResponse> {
-    self.make_request(Method::GET, "post", data).await
+    self.make_request(Endpoint::GetPost, data).await
   }
 
   /// Creates a post.
   ///
   /// HTTP POST /post
   pub async fn create_post(&self, data: CreatePost) -> LemmyResult<PostResponse> {
-    self.make_request(Method::POST, "post", data).await
+    self.make_request(Endpoint::CreatePost, data).await
   }
 
   /// Edits a post you have already created.
   ///
   /// HTTP PUT /post
   pub async fn edit_post(&self, data: EditPost) -> LemmyResult<PostResponse> {
-    self.make_request(Method::PUT, "post", data).await
+    self.make_request(Endpoint::EditPost, data).await
   }
 
   /// Gets the content type and opengraph data of site linked in a post.
@@ -59,7 +59,7 @@ impl LemmyClient {
     data: GetSiteMetadata,
   ) -> LemmyResult<GetSiteMetadataResponse> {
     self
-      .make_request(Method::GET, "post/site_metadata", data)
+      .make_request(Endpoint::GetLinkedSiteMetadata, data)
       .await
   }
 
@@ -67,23 +67,21 @@ impl LemmyClient {
   ///
   /// HTTP POST /post/delete
   pub async fn delete_post(&self, data: DeletePost) -> LemmyResult<PostResponse> {
-    self.make_request(Method::POST, "post/delete", data).await
+    self.make_request(Endpoint::DeletePost, data).await
   }
 
   /// Removes a post (moderator action).
   ///
   /// HTTP POST /post/remove
   pub async fn remove_post(&self, data: RemovePost) -> LemmyResult<PostResponse> {
-    self.make_request(Method::POST, "post/remove", data).await
+    self.make_request(Endpoint::RemovePost, data).await
   }
 
   /// Marks a post as read.
   ///
   /// HTTP POST /post/mark_as_read
   pub async fn mark_post_as_read(&self, data: MarkPostAsRead) -> LemmyResult<PostResponse> {
-    self
-      .make_request(Method::POST, "post/mark_as_read", data)
-      .await
+    self.make_request(Endpoint::MarkPostAsRead, data).await
   }
 
   /// Marks several posts as read.
@@ -93,58 +91,67 @@ impl LemmyClient {
     &self,
     data: MarkManyPostsAsRead,
   ) -> LemmyResult<SuccessResponse> {
-    self
-      .make_request(Method::POST, "post/mark_as_read/many", data)
-      .await
+    self.make_request(Endpoint::MarkManyPostsAsRead, data).await
   }
 
   /// Hide a post from list views.
   ///
   /// HTTP POST /post/hide
   pub async fn hide_post(&self, data: HidePost) -> LemmyResult<SuccessResponse> {
-    self.make_request(Method::POST, "post/hide", data).await
+    self.make_request(Endpoint::HidePost, data).await
   }
 
   /// Prevents users from commenting on the post (moderator action).
   ///
   /// HTTP POST /post/lock
   pub async fn lock_post(&self, data: LockPost) -> LemmyResult<PostResponse> {
-    self.make_request(Method::POST, "post/lock", data).await
+    self.make_request(Endpoint::LockPost, data).await
   }
 
   /// Pins a post to the top of the community page (moderator action).
   ///
   /// HTTP POST /post/feature
   pub async fn feature_post(&self, data: FeaturePost) -> LemmyResult<PostResponse> {
-    self.make_request(Method::POST, "post/feature", data).await
+    self.make_request(Endpoint::FeaturePost, data).await
   }
 
   /// Gets posts with a variety of filters.
   ///
   /// HTTP GET /post/list
   pub async fn list_posts(&self, data: GetPosts) -> LemmyResult<PagedResponse<PostView>> {
-    self.make_request(Method::GET, "post/list", data).await
+    self.make_request(Endpoint::ListPosts, data).await
+  }
+
+  /// Streams all posts matching the filters, fetching pages as they are consumed.
+  ///
+  /// See [`paginate`] for details.
+  pub fn list_posts_stream(
+    &self,
+    data: GetPosts,
+    options: PaginationOptions,
+  ) -> impl Stream<Item = LemmyResult<PostView>> + '_ {
+    paginate(data, options, |data| self.list_posts(data))
   }
 
   /// Votes on a post.
   ///
   /// HTTP POST /post/like
   pub async fn like_post(&self, data: CreatePostLike) -> LemmyResult<PostResponse> {
-    self.make_request(Method::POST, "post/like", data).await
+    self.make_request(Endpoint::LikePost, data).await
   }
 
   /// Lists the likes for a post.
   ///
   /// HTTP GET /post/like/list
   pub async fn list_post_likes(&self, data: ListPostLikes) -> LemmyResult<PagedResponse<VoteView>> {
-    self.make_request(Method::GET, "post/like/list", data).await
+    self.make_request(Endpoint::ListPostLikes, data).await
   }
 
   /// Saves a post to your favorites list.
   ///
   /// HTTP PUT /post/save
   pub async fn save_post(&self, data: SavePost) -> LemmyResult<PostResponse> {
-    self.make_request(Method::PUT, "post/save", data).await
+    self.make_request(Endpoint::SavePost, data).await
   }
 
   /// Reports a post to the moderator team of the community the post is in, the admin team of your
@@ -152,7 +159,7 @@ impl LemmyClient {
   ///
   /// HTTP POST /post/report
   pub async fn report_post(&self, data: CreatePostReport) -> LemmyResult<PostReportResponse> {
-    self.make_request(Method::POST, "post/report", data).await
+    self.make_request(Endpoint::ReportPost, data).await
   }
 
   /// Resolves a post report (moderator action).
@@ -162,9 +169,7 @@ impl LemmyClient {
     &self,
     data: ResolvePostReport,
   ) -> LemmyResult<PostReportResponse> {
-    self
-      .make_request(Method::PUT, "post/report/resolve", data)
-      .await
+    self.make_request(Endpoint::ResolvePostReport, data).await
   }
 
   /// Edits post notifications.
@@ -175,7 +180,7 @@ impl LemmyClient {
     data: EditPostNotifications,
   ) -> LemmyResult<SuccessResponse> {
     self
-      .make_request(Method::PUT, "post/notifications", data)
+      .make_request(Endpoint::EditPostNotifications, data)
       .await
   }
 
@@ -183,13 +188,13 @@ impl LemmyClient {
   ///
   /// HTTP PUT /post/mod_edit
   pub async fn mod_edit_post(&self, data: ModEditPost) -> LemmyResult<PostResponse> {
-    self.make_request(Method::PUT, "post/mod_edit", data).await
+    self.make_request(Endpoint::ModEditPost, data).await
   }
 
   /// Create a warning for a post.
   ///
   /// HTTP POST /post/warn
   pub async fn create_post_warning(&self, data: CreatePostWarning) -> LemmyResult<PostResponse> {
-    self.make_request(Method::POST, "post/warn", data).await
+    self.make_request(Endpoint::CreatePostWarning, data).await
   }
 }
@@ -1,5 +1,4 @@
-use crate::{LemmyClient, LemmyResult};
-use http::Method;
+use crate::{Endpoint, LemmyClient, LemmyResult};
 use lemmy_api_common::{
   private_message::{
     PrivateMessageResponse,
@@ -8,7 +7,7 @@ use lemmy_api_common::{
   report::{CreatePrivateMessageReport, PrivateMessageReportResponse, ResolvePrivateMessageReport},
 };
 
-impl LemmyClient {
+client_methods! {
   /// Creates and send a private message to another user.
   ///
   /// HTTP POST /private_message
@@ -17,7 +16,7 @@ impl LemmyClient {
     data: CreatePrivateMessage,
   ) -> LemmyResult<PrivateMessageResponse> {
     self
-      .make_request(Method::POST, "private_message", data)
+      .make_request(Endpoint::CreatePrivateMessage, data)
       .await
   }
 
@@ -28,9 +27,7 @@ impl LemmyClient {
     &self,
     data: EditPrivateMessage,
   ) -> LemmyResult<PrivateMessageResponse> {
-    self
-      .make_request(Method::PUT, "private_message", data)
-      .await
+    self.make_request(Endpoint::EditPrivateMessage, data).await
   }
 
   /// Deletes a private that you have already sent.
@@ -41,7 +38,7 @@ impl LemmyClient {
     data: DeletePrivateMessage,
   ) -> LemmyResult<PrivateMessageResponse> {
     self
-      .make_request(Method::POST, "private_message/delete", data)
+      .make_request(Endpoint::DeletePrivateMessage, data)
       .await
   }
 
@@ -54,7 +51,7 @@ impl LemmyClient {
     data: CreatePrivateMessageReport,
   ) -> LemmyResult<PrivateMessageReportResponse> {
     self
-      .make_request(Method::POST, "private_message/report", data)
+      .make_request(Endpoint::ReportPrivateMessage, data)
       .await
   }
 
@@ -66,7 +63,7 @@ impl LemmyClient {
     data: ResolvePrivateMessageReport,
   ) -> LemmyResult<PrivateMessageReportResponse> {
     self
-      .make_request(Method::PUT, "private_message/report/resolve", data)
+      .make_request(Endpoint::ResolvePrivateMessageReport, data)
       .await
   }
 }
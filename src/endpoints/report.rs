@@ -1,11 +1,11 @@
-use crate::{LemmyClient, LemmyResult};
-use http::Method;
+use crate::{Endpoint, LemmyClient, LemmyResult, PaginationOptions, paginate};
+use futures_util::Stream;
 use lemmy_api_common::{
   PagedResponse,
   report::{ListReports, ReportCombinedView},
 };
 
-impl LemmyClient {
+client_methods! {
   /// List all reports.
   ///
   /// HTTP GET /report/list
@@ -13,6 +13,17 @@ impl LemmyClient {
     &self,
     data: ListReports,
   ) -> LemmyResult<PagedResponse<ReportCombinedView>> {
-    self.make_request(Method::GET, "report/list", data).await
+    self.make_request(Endpoint::ListReports, data).await
+  }
+
+  /// Streams all reports matching the filters, fetching pages as they are consumed.
+  ///
+  /// See [`paginate`] for details.
+  pub fn list_reports_stream(
+    &self,
+    data: ListReports,
+    options: PaginationOptions,
+  ) -> impl Stream<Item = LemmyResult<ReportCombinedView>> + '_ {
+    paginate(data, options, |data| self.list_reports(data))
   }
 }
@@ -1,4 +1,12 @@
-use crate::{LemmyClient, lemmy_client::LemmyResult};
+use crate::{
+  Endpoint,
+  LemmyClient,
+  PaginationOptions,
+  UploadBody,
+  lemmy_client::LemmyResult,
+  paginate,
+};
+use futures_util::Stream;
 use lemmy_api_common::{
   PagedResponse,
   SuccessResponse,
@@ -11,28 +19,40 @@ use lemmy_api_common::{
     administration::{CreateSite, EditSite, Search, SearchResponse},
   },
 };
-use reqwest::{Body, Method};
 
-impl LemmyClient {
+client_methods! {
   /// Gets the site.
   ///
+  /// If the client was created with [`RateLimitMode::FromSite`][from_site], its rate limiter is
+  /// updated with the limits in the response. The client's
+  /// [`capabilities`][LemmyClient::capabilities] are updated as well.
+  ///
   /// HTTP GET /site
+  ///
+  /// [from_site]: crate::RateLimitMode::FromSite
   pub async fn get_site(&self) -> LemmyResult<GetSiteResponse> {
-    self.make_request(Method::GET, "site", ()).await
+    let response: GetSiteResponse = self.make_request(Endpoint::GetSite, ()).await?;
+
+    self
+      .rate_limiter
+      .update_from_site(&response.site_view.local_site_rate_limit);
+    self.set_capabilities((&response).into());
+
+    Ok(response)
   }
 
   /// Creates site during initial setup.
   ///
   /// HTTP POST /site
   pub async fn create_site(&self, data: CreateSite) -> LemmyResult<SiteResponse> {
-    self.make_request(Method::POST, "site", data).await
+    self.make_request(Endpoint::CreateSite, data).await
   }
 
   ///Edit settings for the site you administer.
   ///
   /// HTTP PUT /site
   pub async fn edit_site(&self, data: EditSite) -> LemmyResult<SiteResponse> {
-    self.make_request(Method::PUT, "site", data).await
+    self.make_request(Endpoint::EditSite, data).await
   }
 
   /// Upload an icon for your site. This is shown as the site favicon, in site header, and is used as metadata for external instance listings like [join-lemmy.org](https://join-lemmy.org/instances).
@@ -42,9 +62,11 @@ impl LemmyClient {
   /// HTTP POST /site/icon
   pub async fn upload_site_icon(
     &self,
-    request: impl Into<Body>,
+    request: impl Into<UploadBody>,
   ) -> LemmyResult<UploadImageResponse> {
-    self.make_file_request("site/icon", (), request).await
+    self
+      .make_file_request(Endpoint::UploadSiteIcon, (), request)
+      .await
   }
 
   /// Delete your site's icon.
@@ -53,9 +75,7 @@ impl LemmyClient {
   ///
   /// HTTP DELETE /site/icon
   pub async fn delete_site_icon(&self, request: DeleteImageParams) -> LemmyResult<SuccessResponse> {
-    self
-      .make_request(Method::DELETE, "site/icon", request)
-      .await
+    self.make_request(Endpoint::DeleteSiteIcon, request).await
   }
 
   /// Upload a banner for your site. This is shown in the site sidebar and is used as metadata for
@@ -66,9 +86,11 @@ impl LemmyClient {
   /// HTTP POST /site/banner
   pub async fn upload_site_banner(
     &self,
-    request: impl Into<Body>,
+    request: impl Into<UploadBody>,
   ) -> LemmyResult<UploadImageResponse> {
-    self.make_file_request("site/banner", (), request).await
+    self
+      .make_file_request(Endpoint::UploadSiteBanner, (), request)
+      .await
   }
 
   /// Delete your site's icon.
@@ -80,30 +102,39 @@ impl LemmyClient {
     &self,
     request: DeleteImageParams,
   ) -> LemmyResult<SuccessResponse> {
-    self
-      .make_request(Method::DELETE, "site/banner", request)
-      .await
+    self.make_request(Endpoint::DeleteSiteBanner, request).await
   }
 
   /// Gets the modlog.
   ///
   /// HTTP GET /modlog
   pub async fn get_modlog(&self, data: GetModlog) -> LemmyResult<PagedResponse<ModlogView>> {
-    self.make_request(Method::GET, "modlog", data).await
+    self.make_request(Endpoint::GetModlog, data).await
+  }
+
+  /// Streams all modlog entries matching the filters, fetching pages as they are consumed.
+  ///
+  /// See [`paginate`] for details.
+  pub fn get_modlog_stream(
+    &self,
+    data: GetModlog,
+    options: PaginationOptions,
+  ) -> impl Stream<Item = LemmyResult<ModlogView>> + '_ {
+    paginate(data, options, |data| self.get_modlog(data))
   }
 
   /// Searches for content.
   ///
   /// HTTP GET /search
   pub async fn search(&self, data: Search) -> LemmyResult<SearchResponse> {
-    self.make_request(Method::GET, "search", data).await
+    self.make_request(Endpoint::Search, data).await
   }
 
   /// Fetches an object from a non-local instance.
   ///
   /// HTTP GET /resolve_object
   pub async fn resolve_object(&self, data: ResolveObject) -> LemmyResult<SearchResponse> {
-    self.make_request(Method::GET, "resolve_object", data).await
+    self.make_request(Endpoint::ResolveObject, data).await
   }
   /// Gets the instances that are federated with your instance.
   ///
@@ -113,7 +144,7 @@ impl LemmyClient {
     data: GetFederatedInstances,
   ) -> LemmyResult<PagedResponse<FederatedInstanceView>> {
     self
-      .make_request(Method::GET, "federated_instances", data)
+      .make_request(Endpoint::GetFederatedInstances, data)
       .await
   }
 }
@@ -0,0 +1,234 @@
+use crate::{ApiVersion, Endpoint, Feature, TransportError};
+use bytes::Bytes;
+use http::{HeaderMap, StatusCode};
+use lemmy_api_common::error::LemmyErrorType;
+use std::{error::Error, fmt};
+
+/// How many bytes of a response body are shown when a [`ClientError`] is displayed.
+const BODY_SNIPPET_LEN: usize = 256;
+
+/// The error type returned by every [`LemmyClient`][client] method.
+///
+/// [client]: crate::LemmyClient
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ClientError {
+  /// The request could not be sent, or the response could not be received.
+  Transport(TransportError),
+  /// The request did not complete in time, either within the timeout of the HTTP client or the
+  /// deadline of the handle the call was made through, see
+  /// [`LemmyClient::with_deadline`][crate::LemmyClient::with_deadline].
+  Timeout,
+  /// The request could not be built, e.g. because its body failed to serialize.
+  InvalidRequest(TransportError),
+  /// The client could not be created from its options, e.g. because of a malformed proxy URL or
+  /// certificate.
+  InvalidOptions(TransportError),
+  /// The instance responded with one of Lemmy's own API errors.
+  Api {
+    /// HTTP status of the response.
+    status: StatusCode,
+    /// The error reported by Lemmy.
+    error: LemmyErrorType,
+  },
+  /// The instance responded with something that is not JSON, such as an HTML error page from a
+  /// reverse proxy.
+  NonJson {
+    /// HTTP status of the response.
+    status: StatusCode,
+    /// Headers of the response.
+    headers: Box<HeaderMap>,
+    /// The raw response body.
+    body: Bytes,
+  },
+  /// The instance responded with JSON that does not match the expected response type.
+  Decode {
+    /// HTTP status of the response.
+    status: StatusCode,
+    /// Path to the part of the response that failed to decode, e.g. `post_view.post.id`.
+    path: String,
+    /// The underlying deserialization error.
+    source: serde_json::Error,
+    /// The raw response body.
+    body: Bytes,
+  },
+  /// A [`Session`][session] could not be loaded from or saved to its
+  /// [`CredentialStore`][store].
+  ///
+  /// [session]: crate::Session
+  /// [store]: crate::CredentialStore
+  CredentialStore(std::io::Error),
+  /// The endpoint can't be called on the instance, because it speaks a version of the API the
+  /// endpoint doesn't exist in or isn't compatible with. See
+  /// [`ApiVersionMode`][crate::ApiVersionMode].
+  UnsupportedOnServerVersion {
+    /// The endpoint that was called.
+    endpoint: Endpoint,
+    /// The version of the API the instance speaks.
+    version: ApiVersion,
+  },
+  /// The endpoint belongs to a feature the instance doesn't support, so it wasn't called. Only
+  /// returned with [`ClientOptions::check_capabilities`][check].
+  ///
+  /// [check]: crate::ClientOptions::check_capabilities
+  UnsupportedFeature {
+    /// The endpoint that was called.
+    endpoint: Endpoint,
+    /// The feature the endpoint belongs to.
+    feature: Feature,
+  },
+  /// The instance responded that nothing changed since the response that was received last. Only
+  /// returned by [`ConditionalRequests`][conditional] with [`NotModifiedMode::Error`][error], or
+  /// with [`NotModifiedMode::ReturnCached`][cached] when the response received last is no longer
+  /// stored.
+  ///
+  /// [conditional]: crate::ConditionalRequests
+  /// [error]: crate::NotModifiedMode::Error
+  /// [cached]: crate::NotModifiedMode::ReturnCached
+  NotModified,
+  /// The call was cancelled with the [`CancellationToken`][token] of the handle it was made
+  /// through.
+  ///
+  /// [token]: crate::CancellationToken
+  Cancelled,
+}
+
+impl ClientError {
+  /// Returns the Lemmy API error, if this is one.
+  pub fn api_error(&self) -> Option<&LemmyErrorType> {
+    match self {
+      Self::Api { error, .. } => Some(error),
+      _ => None,
+    }
+  }
+
+  /// Returns the HTTP status of the response that caused the error, if a response was received.
+  pub fn status(&self) -> Option<StatusCode> {
+    match self {
+      Self::Api { status, .. } | Self::NonJson { status, .. } | Self::Decode { status, .. } => {
+        Some(*status)
+      }
+      Self::NotModified => Some(StatusCode::NOT_MODIFIED),
+      _ => None,
+    }
+  }
+
+  /// Returns the raw response body that caused the error, if one is kept.
+  pub fn body(&self) -> Option<&Bytes> {
+    match self {
+      Self::NonJson { body, .. } | Self::Decode { body, .. } => Some(body),
+      _ => None,
+    }
+  }
+
+  /// A copy of the error for another caller waiting on the same request. Sources that can't be
+  /// cloned are replaced by their message.
+  pub(crate) fn duplicate(&self) -> Self {
+    let message = |e: &dyn Error| -> TransportError { e.to_string().into() };
+
+    match self {
+      Self::Transport(e) => Self::Transport(message(e.as_ref())),
+      Self::Timeout => Self::Timeout,
+      Self::InvalidRequest(e) => Self::InvalidRequest(message(e.as_ref())),
+      Self::InvalidOptions(e) => Self::InvalidOptions(message(e.as_ref())),
+      Self::Api { status, error } => Self::Api {
+        status: *status,
+        error: error.clone(),
+      },
+      Self::NonJson {
+        status,
+        headers,
+        body,
+      } => Self::NonJson {
+        status: *status,
+        headers: headers.clone(),
+        body: body.clone(),
+      },
+      Self::Decode {
+        status,
+        path,
+        source,
+        body,
+      } => Self::Decode {
+        status: *status,
+        path: path.clone(),
+        source: serde::de::Error::custom(source),
+        body: body.clone(),
+      },
+      Self::CredentialStore(e) => {
+        Self::CredentialStore(std::io::Error::new(e.kind(), e.to_string()))
+      }
+      Self::UnsupportedOnServerVersion { endpoint, version } => Self::UnsupportedOnServerVersion {
+        endpoint: *endpoint,
+        version: *version,
+      },
+      Self::UnsupportedFeature { endpoint, feature } => Self::UnsupportedFeature {
+        endpoint: *endpoint,
+        feature: *feature,
+      },
+      Self::NotModified => Self::NotModified,
+      Self::Cancelled => Self::Cancelled,
+    }
+  }
+}
+
+/// Lossily decode the start of a response body for display.
+fn body_snippet(body: &[u8]) -> String {
+  let snippet = String::from_utf8_lossy(&body[..body.len().min(BODY_SNIPPET_LEN)]);
+
+  if body.len() > BODY_SNIPPET_LEN {
+    format!("{snippet}…")
+  } else {
+    snippet.into_owned()
+  }
+}
+
+impl fmt::Display for ClientError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Transport(e) => write!(f, "transport error: {e}"),
+      Self::Timeout => f.write_str("request timed out"),
+      Self::InvalidRequest(e) => write!(f, "invalid request: {e}"),
+      Self::InvalidOptions(e) => write!(f, "invalid client options: {e}"),
+      Self::Api { status, error } => write!(f, "Lemmy API error ({status}): {error}"),
+      Self::NonJson { status, body, .. } => write!(
+        f,
+        "received non-JSON response ({status}): {}",
+        body_snippet(body)
+      ),
+      Self::Decode {
+        status,
+        path,
+        source,
+        ..
+      } => write!(
+        f,
+        "could not decode response ({status}) at `{path}`: {source}"
+      ),
+      Self::CredentialStore(e) => write!(f, "could not access the credential store: {e}"),
+      Self::UnsupportedOnServerVersion { endpoint, version } => write!(
+        f,
+        "`{}` is not supported by instances on API {version}",
+        endpoint.name()
+      ),
+      Self::UnsupportedFeature { endpoint, feature } => write!(
+        f,
+        "`{}` needs {feature}, which the instance doesn't support",
+        endpoint.name()
+      ),
+      Self::NotModified => f.write_str("response not modified since it was last received"),
+      Self::Cancelled => f.write_str("request cancelled"),
+    }
+  }
+}
+
+impl Error for ClientError {
+  fn source(&self) -> Option<&(dyn Error + 'static)> {
+    match self {
+      Self::Transport(e) | Self::InvalidRequest(e) | Self::InvalidOptions(e) => Some(e.as_ref()),
+      Self::Decode { source, .. } => Some(source),
+      Self::CredentialStore(e) => Some(e),
+      _ => None,
+    }
+  }
+}
@@ -0,0 +1,123 @@
+//! Spans and events for the `tracing` feature.
+
+use crate::{
+  ClientError,
+  Endpoint,
+  LemmyResult,
+  redact::{REDACTED, SECRET_HEADERS, redact_json, redact_query},
+};
+use bytes::Bytes;
+use http::{HeaderMap, HeaderValue, Request, Response};
+use serde_json::Value;
+use std::{future::Future, time::Duration};
+use tracing::{Instrument, Span, debug, field::Empty, trace};
+use web_time::Instant;
+
+/// Run a call to an endpoint, retries included, in a span describing it.
+pub(crate) async fn instrument<R>(
+  endpoint: Endpoint,
+  request: &Request<Vec<u8>>,
+  call: impl Future<Output = LemmyResult<R>>,
+) -> LemmyResult<R> {
+  let span = tracing::debug_span!(
+    "lemmy_request",
+    endpoint = endpoint.name(),
+    method = %request.method(),
+    route = request.uri().path(),
+    status = Empty,
+    latency_ms = Empty,
+    retries = Empty,
+  );
+
+  async move {
+    trace!(
+      query = request.uri().query().map(redact_query),
+      headers = ?redact_headers(request.headers()),
+      body = %redact_body(request.body()),
+      "sending request"
+    );
+
+    let start = Instant::now();
+    let result = call.await;
+    Span::current().record("latency_ms", start.elapsed().as_millis());
+
+    match &result {
+      Ok(_) => debug!("request succeeded"),
+      Err(error) => debug!(%error, "request failed"),
+    }
+
+    result
+  }
+  .instrument(span)
+  .await
+}
+
+/// Record the outcome of the last attempt at a request.
+pub(crate) fn record_response(attempt: u32, result: &Result<Response<Bytes>, ClientError>) {
+  let span = Span::current();
+  span.record("retries", attempt - 1);
+
+  if let Ok(response) = result {
+    span.record("status", response.status().as_u16());
+    trace!(
+      headers = ?redact_headers(response.headers()),
+      body = %redact_body(response.body()),
+      "received response"
+    );
+  }
+}
+
+/// Note that an attempt at a request failed and is about to be retried.
+pub(crate) fn record_retry(
+  attempt: u32,
+  delay: Duration,
+  result: &Result<Response<Bytes>, ClientError>,
+) {
+  match result {
+    Ok(response) => debug!(
+      attempt,
+      ?delay,
+      status = response.status().as_u16(),
+      "retrying"
+    ),
+    Err(error) => debug!(attempt, ?delay, %error, "retrying"),
+  }
+}
+
+/// Log the request a call would have sent, for dry runs.
+pub(crate) fn record_dry_run(endpoint: Endpoint, request: &Request<Vec<u8>>) {
+  tracing::info!(
+    endpoint = endpoint.name(),
+    method = %request.method(),
+    route = request.uri().path(),
+    query = request.uri().query().map(redact_query),
+    body = %redact_body(request.body()),
+    "dry run, not sending request"
+  );
+}
+
+fn redact_headers(headers: &HeaderMap) -> HeaderMap {
+  let mut headers = headers.clone();
+  for name in SECRET_HEADERS {
+    if headers.contains_key(&name) {
+      headers.insert(name, HeaderValue::from_static(REDACTED));
+    }
+  }
+
+  headers
+}
+
+/// Bodies that aren't JSON, such as images, are only logged by size.
+fn redact_body(body: &[u8]) -> String {
+  if body.is_empty() {
+    return String::new();
+  }
+
+  match serde_json::from_slice::<Value>(body) {
+    Ok(mut json) => {
+      redact_json(&mut json);
+      json.to_string()
+    }
+    Err(_) => format!("<{} bytes>", body.len()),
+  }
+}
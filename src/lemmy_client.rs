@@ -1,85 +1,517 @@
-use crate::{ClientOptions, client_options::ClientOptionsInternal};
+use crate::{
+  ApiVersion,
+  ApiVersionMode,
+  CancellationToken,
+  Capabilities,
+  ClientError,
+  ClientOptions,
+  Endpoint,
+  Feature,
+  MetricsRecorder,
+  Middleware,
+  RequestContext,
+  RequestMetrics,
+  RetryPolicy,
+  Transport,
+  UploadBody,
+  cache::{Decoded, cache_key},
+  capabilities::SiteInfo,
+  client_options::ClientOptionsInternal,
+  rate_limit::RateLimiter,
+  redact::redact_json,
+  retry,
+  session::Session,
+};
+use bytes::Bytes;
+use futures_util::{
+  FutureExt,
+  future::{self, Either, WeakShared},
+};
 use http::{
   HeaderMap,
   HeaderValue,
   Method,
-  header::{AUTHORIZATION, InvalidHeaderValue, USER_AGENT},
+  Request,
+  Response,
+  StatusCode,
+  header::{AUTHORIZATION, CONTENT_LENGTH, CONTENT_TYPE, InvalidHeaderValue, USER_AGENT},
+  request::Builder,
+};
+use lemmy_api_common::{
+  account::auth::{Login, LoginResponse},
+  error::LemmyErrorType,
+  media::UploadImageResponse,
+};
+use serde::{Deserialize, Serialize, de::IgnoredAny};
+use serde_json::Value;
+use std::{
+  borrow::Cow,
+  collections::HashMap,
+  fmt,
+  future::Future,
+  ops::ControlFlow,
+  pin::pin,
+  sync::{
+    Arc,
+    Mutex,
+    OnceLock,
+    atomic::{AtomicU64, Ordering},
+  },
+  time::Duration,
 };
-use lemmy_api_common::{error::LemmyErrorType, media::UploadImageResponse};
-use reqwest::{Body, Client, RequestBuilder};
-use serde::{Deserialize, Serialize};
-use std::{borrow::Cow, fmt};
+use web_time::Instant;
 
 /// A return type for the lemmy result
-pub type LemmyResult<R> = Result<R, LemmyErrorType>;
-
-fn build_route(
-  route: &str,
-  ClientOptionsInternal { domain, secure }: &ClientOptionsInternal,
-) -> String {
-  format!(
-    "http{}://{}/api/v4/{route}",
-    if *secure { "s" } else { "" },
-    domain.as_ref()
-  )
+pub type LemmyResult<R> = Result<R, ClientError>;
+
+/// Join a route relative to the instance's base URL, e.g. `api/v4/post/list`, onto it.
+fn build_route(route: &str, options: &ClientOptionsInternal) -> LemmyResult<String> {
+  match &options.base_url {
+    Ok(base_url) => Ok(format!("{base_url}{route}")),
+    Err(e) => Err(ClientError::InvalidOptions(Box::new(e.clone()))),
+  }
 }
 
-fn map_other_error<E: ToString>(e: E) -> LemmyErrorType {
-  LemmyErrorType::Unknown(e.to_string())
+fn map_request_error<E>(e: E) -> ClientError
+where
+  E: std::error::Error + Send + Sync + 'static,
+{
+  ClientError::InvalidRequest(Box::new(e))
 }
 
-fn deserialize_response<Response>(res: &str) -> Result<Response, LemmyErrorType>
+fn deserialize_response<R>(res: http::Response<Bytes>) -> LemmyResult<R>
 where
-  Response: for<'de> Deserialize<'de>,
+  R: for<'de> Deserialize<'de> + Clone + Send + Sync + 'static,
 {
-  serde_json::from_str::<Response>(res)
-    .map_err(|_| serde_json::from_str::<LemmyErrorType>(res).unwrap_or_else(map_other_error))
+  // Responses returned again from a cache are only decoded the first time.
+  let decoded = res.extensions().get::<Decoded>();
+  if let Some(response) = decoded.and_then(Decoded::get) {
+    return Ok(response);
+  }
+
+  let mut deserializer = serde_json::Deserializer::from_slice(res.body());
+
+  match serde_path_to_error::deserialize::<_, R>(&mut deserializer) {
+    Ok(response) => {
+      if let Some(decoded) = decoded {
+        decoded.set(response.clone());
+      }
+
+      Ok(response)
+    }
+    Err(e) => {
+      let (parts, body) = res.into_parts();
+
+      if let Ok(error) = serde_json::from_slice::<LemmyErrorType>(&body) {
+        Err(ClientError::Api {
+          status: parts.status,
+          error,
+        })
+      } else if serde_json::from_slice::<IgnoredAny>(&body).is_ok() {
+        Err(ClientError::Decode {
+          status: parts.status,
+          path: e.path().to_string(),
+          source: e.into_inner(),
+          body,
+        })
+      } else {
+        Err(ClientError::NonJson {
+          status: parts.status,
+          headers: Box::new(parts.headers),
+          body,
+        })
+      }
+    }
+  }
+}
+
+/// [`Request`] is not [`Clone`] because of its extensions, which this crate does not use.
+fn clone_request(request: &Request<Vec<u8>>) -> Request<Vec<u8>> {
+  let mut clone = Request::new(request.body().clone());
+  *clone.method_mut() = request.method().clone();
+  *clone.uri_mut() = request.uri().clone();
+  *clone.version_mut() = request.version();
+  *clone.headers_mut() = request.headers().clone();
+
+  clone
+}
+
+/// Like [`clone_request`], for responses shared between coalesced calls. Their extensions are
+/// kept, since caches use them.
+fn clone_response(response: &Response<Bytes>) -> Response<Bytes> {
+  let mut clone = Response::new(response.body().clone());
+  *clone.status_mut() = response.status();
+  *clone.version_mut() = response.version();
+  *clone.headers_mut() = response.headers().clone();
+  *clone.extensions_mut() = response.extensions().clone();
+
+  clone
+}
+
+/// Whether an error fetching the site from API v4 means the instance doesn't serve it: a 404, or
+/// a successful response that isn't JSON, such as the page a frontend serves for unknown paths.
+fn serves_no_v4(error: &ClientError) -> bool {
+  match error {
+    ClientError::NonJson { status, .. } => status.is_success() || *status == StatusCode::NOT_FOUND,
+    error => error.status() == Some(StatusCode::NOT_FOUND),
+  }
 }
 
-async fn send_request(request_builder: RequestBuilder) -> Result<String, LemmyErrorType> {
-  request_builder
-    .send()
-    .await
-    .map_err(map_other_error)?
-    .text()
-    .await
-    .map_err(map_other_error)
+/// The raw result of sending a request, with how many attempts it took and how many bytes of
+/// request and response bodies the attempts that reached the transport sent and received.
+struct Exchange {
+  result: LemmyResult<Response<Bytes>>,
+  attempts: u32,
+  bytes_sent: usize,
+  bytes_received: usize,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+type BoxedExchange = futures_util::future::BoxFuture<'static, Arc<Exchange>>;
+#[cfg(target_arch = "wasm32")]
+type BoxedExchange = futures_util::future::LocalBoxFuture<'static, Arc<Exchange>>;
+
+/// GET requests in flight that identical calls wait on with
+/// [`ClientOptions::coalesce_requests`], by their [`cache_key`]. Only weak handles are kept, so
+/// that a request is dropped, and its transport aborts it, once every call waiting on it is.
+type InFlight = Mutex<HashMap<String, (u64, WeakShared<BoxedExchange>)>>;
+
+/// Removes a request from the [`InFlight`] map once it is done or dropped, unless a newer request
+/// with the same key took its place.
+struct InFlightEntry {
+  in_flight: Arc<InFlight>,
+  key: String,
+  id: u64,
+}
+
+impl Drop for InFlightEntry {
+  fn drop(&mut self) {
+    let mut in_flight = self.in_flight.lock().unwrap_or_else(|e| e.into_inner());
+    if in_flight
+      .get(&self.key)
+      .is_some_and(|(id, _)| *id == self.id)
+    {
+      in_flight.remove(&self.key);
+    }
+  }
+}
+
+/// Send a request until it succeeds or the [`RetryPolicy`] gives up, passing every attempt through
+/// the [`Middleware`], and the result through them in reverse.
+async fn exchange(
+  transport: &dyn Transport,
+  rate_limiter: &RateLimiter,
+  middleware: &[Arc<dyn Middleware>],
+  retry_policy: &RetryPolicy,
+  context: &RequestContext,
+  request: &Request<Vec<u8>>,
+) -> Exchange {
+  let mut attempt = 1;
+  let mut bytes_sent = 0;
+  let mut bytes_received = 0;
+
+  let result = loop {
+    let mut attempt_request = clone_request(request);
+    if let ControlFlow::Break(result) = middleware
+      .iter()
+      .try_for_each(|middleware| middleware.before_request(context, &mut attempt_request))
+    {
+      break result;
+    }
+
+    rate_limiter.acquire(context.endpoint).await;
+    bytes_sent += attempt_request.body().len();
+    let result = transport.send(attempt_request).await;
+    bytes_received += result.as_ref().map_or(0, |response| response.body().len());
+
+    match retry_policy.retry_delay(request.method(), attempt, &result) {
+      Some(delay) => {
+        #[cfg(feature = "tracing")]
+        crate::instrument::record_retry(attempt, delay, &result);
+        retry::sleep(delay).await;
+        attempt += 1;
+      }
+      None => break result,
+    }
+  };
+  let result = middleware.iter().rev().fold(result, |result, middleware| {
+    middleware.after_response(context, request, result)
+  });
+
+  Exchange {
+    result,
+    attempts: attempt,
+    bytes_sent,
+    bytes_received,
+  }
 }
 
 /// API wrapper for Lemmy
 pub struct LemmyClient {
   headers: HeaderMap,
-  client: Client,
+  transport: Arc<dyn Transport>,
+  pub(crate) rate_limiter: Arc<RateLimiter>,
+  /// Shared by the handles from [`with_deadline`][Self::with_deadline] and
+  /// [`with_cancellation`][Self::with_cancellation].
+  pub(crate) session: Arc<Session>,
+  /// The version detected with [`ApiVersionMode::Detect`], shared by [`as_user`][Self::as_user].
+  api_version: Arc<OnceLock<ApiVersion>>,
+  /// The last [`Capabilities`] fetched, shared by [`as_user`][Self::as_user].
+  capabilities: Arc<Mutex<Option<Capabilities>>>,
+  pub(crate) metrics: Option<Arc<dyn MetricsRecorder>>,
+  pub(crate) middleware: Vec<Arc<dyn Middleware>>,
+  /// GET requests in flight, shared by [`as_user`][Self::as_user].
+  in_flight: Arc<InFlight>,
+  /// How long each call may take, see [`with_deadline`][Self::with_deadline].
+  deadline: Option<Duration>,
+  /// Cancels the calls made with the client, see [`with_cancellation`][Self::with_cancellation].
+  cancellation: Option<CancellationToken>,
   options: ClientOptionsInternal,
 }
 
 /// Methods for [`LemmyClient`] that don't have to do with specific endpoints
 /// on Lemmy's API.
 impl LemmyClient {
-  /// Creates a new [`LemmyClient`].
+  /// Creates a new [`LemmyClient`] that sends requests with [`reqwest`], or with the browser's
+  /// fetch API when targeting WASM with the `wasm` feature.
   /// # Examples
   /// ```
   /// # use lemmy_client::{LemmyClient, ClientOptions};
-  /// let client = LemmyClient::new(ClientOptions {
-  ///     domain: "lemmy.ml",
-  ///     secure: true
-  /// });
+  /// let client = LemmyClient::new(ClientOptions::new("lemmy.ml"));
   /// ```
+  ///
+  /// If the client can't be created from the [`HttpOptions`][crate::HttpOptions], e.g. because
+  /// the proxy URL is malformed, every request fails with [`ClientError::InvalidOptions`], like it
+  /// does with an invalid base URL. Use [`try_new`][LemmyClient::try_new] to find out right away.
+  #[cfg(any(feature = "native", all(feature = "wasm", target_arch = "wasm32")))]
   pub fn new<Domain>(options: ClientOptions<Domain>) -> Self
+  where
+    Domain: Into<Cow<'static, str>>,
+  {
+    match crate::transport::default_transport(&options.http) {
+      Ok(transport) => Self::with_transport(options, transport),
+      Err(e) => Self::with_transport(options, crate::transport::Unavailable(e)),
+    }
+  }
+
+  /// Creates a new [`LemmyClient`] like [`new`][LemmyClient::new], failing with
+  /// [`ClientError::InvalidOptions`] right away if the options are invalid.
+  /// # Examples
+  /// ```
+  /// # use lemmy_client::{LemmyClient, ClientOptions, HttpOptions};
+  /// let options = ClientOptions {
+  ///   http: HttpOptions {
+  ///     proxy: Some("not a url".to_owned()),
+  ///     ..HttpOptions::default()
+  ///   },
+  ///   ..ClientOptions::new("lemmy.ml")
+  /// };
+  /// assert!(LemmyClient::try_new(options).is_err());
+  /// ```
+  #[cfg(any(feature = "native", all(feature = "wasm", target_arch = "wasm32")))]
+  pub fn try_new<Domain>(options: ClientOptions<Domain>) -> Result<Self, ClientError>
+  where
+    Domain: Into<Cow<'static, str>>,
+  {
+    let transport = crate::transport::default_transport(&options.http)?;
+    let client = Self::with_transport(options, transport);
+    if let Err(e) = &client.options.base_url {
+      return Err(ClientError::InvalidOptions(Box::new(e.clone())));
+    }
+
+    Ok(client)
+  }
+
+  /// Creates a new [`LemmyClient`] that sends requests with a pre-configured [`reqwest::Client`].
+  /// The [`HttpOptions`][crate::HttpOptions] are ignored.
+  /// # Examples
+  /// ```
+  /// # use lemmy_client::{LemmyClient, ClientOptions};
+  /// let client = reqwest::Client::builder().https_only(true).build().unwrap();
+  /// let client = LemmyClient::with_reqwest_client(ClientOptions::new("lemmy.ml"), client);
+  /// ```
+  #[cfg(feature = "native")]
+  pub fn with_reqwest_client<Domain>(
+    options: ClientOptions<Domain>,
+    client: reqwest::Client,
+  ) -> Self
+  where
+    Domain: Into<Cow<'static, str>>,
+  {
+    Self::with_transport(options, crate::ReqwestTransport::new(client))
+  }
+
+  /// Creates a new [`LemmyClient`] that sends requests through a custom [`Transport`].
+  /// # Examples
+  /// ```
+  /// # use lemmy_client::{LemmyClient, ClientOptions, ReqwestTransport};
+  /// let transport = ReqwestTransport::new(reqwest::Client::new());
+  /// let client = LemmyClient::with_transport(ClientOptions::new("lemmy.ml"), transport);
+  /// ```
+  pub fn with_transport<Domain>(
+    options: ClientOptions<Domain>,
+    transport: impl Transport + 'static,
+  ) -> Self
   where
     Domain: Into<Cow<'static, str>>,
   {
     // Private non-generic function for creating a new ['LemmyClient']
     // to cut down on monomorphized code.
-    fn inner(options: ClientOptionsInternal) -> LemmyClient {
+    fn inner(options: ClientOptionsInternal, transport: Arc<dyn Transport>) -> LemmyClient {
       LemmyClient {
         headers: HeaderMap::new(),
-        client: Client::new(),
+        transport,
+        rate_limiter: Arc::new(RateLimiter::new(options.rate_limit)),
+        session: Arc::default(),
+        api_version: Arc::new(OnceLock::new()),
+        capabilities: Arc::new(Mutex::new(None)),
+        metrics: None,
+        middleware: Vec::new(),
+        in_flight: Arc::default(),
+        deadline: None,
+        cancellation: None,
         options,
       }
     }
 
-    inner(options.into())
+    inner(options.into(), Arc::new(transport))
+  }
+
+  /// Creates a client that acts as another account, identified by its JWT.
+  ///
+  /// The new client shares the transport, and with it the connection pool, as well as the rate
+  /// limiter, detected API version, capabilities, metrics recorder, middleware, options, deadline
+  /// and cancellation token of this one. It starts out with the same headers, but with its own JWT
+  /// and without a [`Session`].
+  /// # Examples
+  /// ```
+  /// # use lemmy_client::{LemmyClient, ClientOptions};
+  /// let client = LemmyClient::new(ClientOptions::new("lemmy.ml"));
+  /// let moderator = client.as_user("moderator-jwt").unwrap();
+  /// ```
+  pub fn as_user(&self, jwt: &str) -> Result<LemmyClient, InvalidHeaderValue> {
+    let mut client = self.handle();
+    client.session = Arc::default();
+    client.set_jwt(jwt)?;
+
+    Ok(client)
+  }
+
+  /// Creates a handle to the client whose calls fail with [`ClientError::Timeout`] if they take
+  /// longer than `deadline`, counted from when the call is made, including rate limiting and
+  /// retries. The call's request is dropped once the deadline passes, which aborts it on WASM too.
+  ///
+  /// The handle shares everything with this client, including its [`Session`], and only differs
+  /// in its deadline.
+  /// ```
+  /// # use lemmy_client::{ClientError, LemmyClient};
+  /// # use lemmy_client::lemmy_api_common::post::GetPosts;
+  /// # use std::time::Duration;
+  /// # async fn load(client: &LemmyClient) {
+  /// match client
+  ///   .with_deadline(Duration::from_secs(5))
+  ///   .list_posts(GetPosts::default())
+  ///   .await
+  /// {
+  ///   Ok(posts) => println!("{} posts", posts.items.len()),
+  ///   Err(ClientError::Timeout) => println!("The instance is slow, try again later."),
+  ///   Err(e) => println!("{e}"),
+  /// }
+  /// # }
+  /// ```
+  pub fn with_deadline(&self, deadline: Duration) -> LemmyClient {
+    LemmyClient {
+      deadline: Some(deadline),
+      ..self.handle()
+    }
+  }
+
+  /// Creates a handle to the client whose calls fail with [`ClientError::Cancelled`] once `token`
+  /// is cancelled. See [`CancellationToken`].
+  ///
+  /// The handle shares everything with this client, including its [`Session`], and only differs
+  /// in its cancellation token.
+  pub fn with_cancellation(&self, token: &CancellationToken) -> LemmyClient {
+    LemmyClient {
+      cancellation: Some(token.clone()),
+      ..self.handle()
+    }
+  }
+
+  /// Another client sharing everything with this one.
+  fn handle(&self) -> LemmyClient {
+    LemmyClient {
+      headers: self.headers.clone(),
+      transport: self.transport.clone(),
+      rate_limiter: self.rate_limiter.clone(),
+      session: self.session.clone(),
+      api_version: self.api_version.clone(),
+      capabilities: self.capabilities.clone(),
+      metrics: self.metrics.clone(),
+      middleware: self.middleware.clone(),
+      in_flight: self.in_flight.clone(),
+      deadline: self.deadline,
+      cancellation: self.cancellation.clone(),
+      options: self.options.clone(),
+    }
+  }
+
+  /// Run a call to an endpoint, failing it once the deadline of the handle it was made through
+  /// passes or its cancellation token is cancelled.
+  async fn scoped<R>(
+    &self,
+    endpoint: Endpoint,
+    call: impl Future<Output = LemmyResult<R>>,
+  ) -> LemmyResult<R> {
+    if self.deadline.is_none() && self.cancellation.is_none() {
+      return call.await;
+    }
+
+    let start = Instant::now();
+
+    let deadline = pin!(async {
+      match self.deadline {
+        Some(deadline) => retry::sleep(deadline).await,
+        None => future::pending().await,
+      }
+      ClientError::Timeout
+    });
+    let cancelled = pin!(async {
+      match &self.cancellation {
+        Some(token) => token.cancelled().await,
+        None => future::pending().await,
+      }
+      ClientError::Cancelled
+    });
+
+    // Polled before the call, so that calls made with a cancelled token never send anything.
+    let interrupted = future::select(cancelled, deadline);
+    match future::select(interrupted, pin!(call)).await {
+      Either::Left((interruption, _)) => {
+        let error = interruption.factor_first().0;
+        self.record_unobserved(endpoint, start, &error);
+
+        Err(error)
+      }
+      Either::Right((result, _)) => result,
+    }
+  }
+
+  /// Record the metrics of a call that failed without a response to [observe][Self::observe],
+  /// because its request couldn't be built or it was interrupted while waiting for one.
+  fn record_unobserved(&self, endpoint: Endpoint, start: Instant, error: &ClientError) {
+    if let Some(metrics) = &self.metrics {
+      metrics.record(&RequestMetrics {
+        domain: &self.options.domain,
+        endpoint,
+        latency: start.elapsed(),
+        retries: 0,
+        bytes_sent: 0,
+        bytes_received: 0,
+        status: None,
+        error: Some(error),
+      });
+    }
   }
 
   /// Returns whether or not the client is making requests over HTTPS.
@@ -93,6 +525,126 @@ impl LemmyClient {
     &self.options.domain
   }
 
+  /// Returns the version of the API the client talks to the instance with, or `None` if it is
+  /// still to be [detected][ApiVersionMode::Detect].
+  pub fn api_version(&self) -> Option<ApiVersion> {
+    match self.options.api_version {
+      ApiVersionMode::Fixed(version) => Some(version),
+      ApiVersionMode::Detect => self.api_version.get().copied(),
+    }
+  }
+
+  /// Asks the instance which version of the API it speaks, by fetching its site from API v4 and,
+  /// if it responds with a 404 or a page that isn't JSON, from API v3, and reading the version of
+  /// Lemmy it runs. Other errors, such as server errors, are returned as they are. This also
+  /// refreshes the [`capabilities`][Self::capabilities].
+  ///
+  /// With [`ApiVersionMode::Detect`], this happens before the first request and the result is
+  /// used for all further requests. Call it up front to find out what the instance supports, or
+  /// to fail early if it is unreachable. With [`ApiVersionMode::Fixed`], the instance is asked
+  /// anyway but the fixed version keeps being used.
+  pub async fn detect_api_version(&self) -> LemmyResult<ApiVersion> {
+    self
+      .refresh_capabilities()
+      .await
+      .map(|capabilities| capabilities.api_version)
+  }
+
+  /// Returns what the instance supports, fetching it with
+  /// [`refresh_capabilities`][Self::refresh_capabilities] unless it is already known.
+  pub async fn capabilities(&self) -> LemmyResult<Capabilities> {
+    let known = self
+      .capabilities
+      .lock()
+      .unwrap_or_else(|e| e.into_inner())
+      .clone();
+
+    match known {
+      Some(capabilities) => Ok(capabilities),
+      None => self.refresh_capabilities().await,
+    }
+  }
+
+  /// Fetches what the instance supports from its site, on whichever version of the API it speaks.
+  /// [`get_site`][Self::get_site] refreshes them as well.
+  pub async fn refresh_capabilities(&self) -> LemmyResult<Capabilities> {
+    self
+      .scoped(Endpoint::GetSite, async {
+        // A 404, or a page that isn't JSON in place of the site, means the instance doesn't serve
+        // API v4. Any other error means it is down or broken, and is returned as is.
+        let site = match self.fetch_site_info(ApiVersion::V4).await {
+          Err(e) if serves_no_v4(&e) => self.fetch_site_info(ApiVersion::V3).await?,
+          result => result?,
+        };
+        let capabilities = Capabilities::from(site);
+
+        if self.options.api_version == ApiVersionMode::Detect {
+          let _ = self.api_version.set(capabilities.api_version);
+        }
+        self.set_capabilities(capabilities.clone());
+
+        Ok(capabilities)
+      })
+      .await
+  }
+
+  pub(crate) fn set_capabilities(&self, capabilities: Capabilities) {
+    *self.capabilities.lock().unwrap_or_else(|e| e.into_inner()) = Some(capabilities);
+  }
+
+  /// Fetch the site from the given version of the API.
+  async fn fetch_site_info(&self, version: ApiVersion) -> LemmyResult<SiteInfo> {
+    let route = format!("{}{}", version.base_path(), Endpoint::GetSite.path());
+    let request = self
+      .create_request_builder(Method::GET, &route, "")?
+      .body(Vec::new())
+      .map_err(map_request_error)?;
+
+    self
+      .send_with_retries(
+        &RequestContext::new(Endpoint::GetSite, Value::Null),
+        &request,
+      )
+      .await
+  }
+
+  /// Resolve the HTTP method and route of an endpoint on the version of the API the instance
+  /// speaks, relative to its base URL, checking that the instance supports it if the options ask
+  /// for it.
+  async fn route(&self, endpoint: Endpoint) -> LemmyResult<(Method, String)> {
+    let version = match self.api_version() {
+      Some(version) => version,
+      None => self.detect_api_version().await?,
+    };
+
+    let Some(path) = endpoint.path_for(version) else {
+      return Err(ClientError::UnsupportedOnServerVersion { endpoint, version });
+    };
+
+    if self.options.check_capabilities
+      && let Some(feature) = Feature::of(endpoint)
+      && !self.capabilities().await?.supports(feature)
+    {
+      return Err(ClientError::UnsupportedFeature { endpoint, feature });
+    }
+
+    Ok((
+      endpoint.method_for(version),
+      format!("{}{path}", version.base_path()),
+    ))
+  }
+
+  /// Sets the [`MetricsRecorder`] that every request the client sends is reported to.
+  pub fn set_metrics_recorder(&mut self, recorder: impl MetricsRecorder + 'static) {
+    self.metrics = Some(Arc::new(recorder));
+  }
+
+  /// Adds a [`Middleware`] that every request the client sends passes through, after the ones
+  /// added before it.
+  pub fn add_middleware(&mut self, middleware: impl Middleware + 'static) {
+    self.middleware.push(Arc::new(middleware));
+  }
+
   /// Returns a map of headers that will be included with each request.
   pub fn headers(&self) -> &HeaderMap {
     &self.headers
@@ -106,6 +658,8 @@ impl LemmyClient {
 
   /// Set the Authorization header with a JWT token.
   /// There is no need to include the "Bearer" part of the header value.
+  ///
+  /// The JWT of an active [`Session`][crate::Session] takes precedence over this one.
   pub fn set_jwt(&mut self, jwt: &str) -> Result<(), InvalidHeaderValue> {
     self.headers.insert(
       AUTHORIZATION,
@@ -130,61 +684,405 @@ impl LemmyClient {
     self.headers.remove(AUTHORIZATION);
   }
 
-  /// Create a [`RequestBuilder`] to use for making requests.
-  fn create_request_builder(&self, method: &Method, path: &str) -> RequestBuilder {
-    let route = build_route(path, &self.options);
+  /// Create a request [`Builder`] with the route, relative to the base URL, and the headers shared
+  /// by every request.
+  fn create_request_builder(
+    &self,
+    method: Method,
+    route: &str,
+    query: &str,
+  ) -> LemmyResult<Builder> {
+    let mut route = build_route(route, &self.options)?;
+    if !query.is_empty() {
+      route.push('?');
+      route.push_str(query);
+    }
 
-    let mut request_builder = match *method {
-      Method::GET => self.client.get(route),
-      Method::POST => self.client.post(route),
-      Method::PUT => self.client.put(route),
-      Method::DELETE => self.client.delete(route),
-      _ => unreachable!("This crate does not use other HTTP methods."),
+    let mut request_builder = Request::builder().method(method).uri(route);
+
+    if let Some(headers) = request_builder.headers_mut() {
+      if !self.headers.contains_key(USER_AGENT) {
+        headers.insert(
+          USER_AGENT,
+          HeaderValue::from_static("Lemmy-Client-rs/1.0.0"),
+        );
+      }
+
+      headers.extend(self.headers.clone());
+
+      if let Some(authorization) = self.session.authorization() {
+        headers.insert(AUTHORIZATION, authorization);
+      }
+    }
+
+    Ok(request_builder)
+  }
+
+  /// Hand a request off to the transport, throttled by the client's rate limiter, retried
+  /// according to its [`RetryPolicy`][crate::RetryPolicy] and passed through its [`Middleware`],
+  /// and deserialize the response. With the `tracing` feature, this happens in a span describing
+  /// the call.
+  async fn send_with_retries<R>(
+    &self,
+    context: &RequestContext,
+    request: &Request<Vec<u8>>,
+  ) -> LemmyResult<R>
+  where
+    R: for<'de> Deserialize<'de> + Clone + Send + Sync + 'static,
+  {
+    self
+      .observe(context, request, self.exchange_or_join(context, request))
+      .await
+  }
+
+  /// Hand a request with a streamed body off to the transport like
+  /// [`send_with_retries`][Self::send_with_retries], but only once, since its body can only be
+  /// read once. [`Middleware`] see the request without its body.
+  async fn send_streaming<R>(
+    &self,
+    context: &RequestContext,
+    request: Request<UploadBody>,
+  ) -> LemmyResult<R>
+  where
+    R: for<'de> Deserialize<'de> + Clone + Send + Sync + 'static,
+  {
+    let (parts, body) = request.into_parts();
+    let request = Request::from_parts(parts, Vec::new());
+    let sent = body.sent();
+
+    let exchange = async {
+      let mut attempt_request = clone_request(&request);
+      let mut bytes_received = 0;
+      let result = match self
+        .middleware
+        .iter()
+        .try_for_each(|middleware| middleware.before_request(context, &mut attempt_request))
+      {
+        ControlFlow::Break(result) => result,
+        ControlFlow::Continue(()) => {
+          self.rate_limiter.acquire(context.endpoint).await;
+          let (parts, _) = attempt_request.into_parts();
+          let result = self
+            .transport
+            .send_streaming(Request::from_parts(parts, body))
+            .await;
+          bytes_received = result.as_ref().map_or(0, |response| response.body().len());
+
+          result
+        }
+      };
+      let result = self
+        .middleware
+        .iter()
+        .rev()
+        .fold(result, |result, middleware| {
+          middleware.after_response(context, &request, result)
+        });
+
+      Exchange {
+        result,
+        attempts: 1,
+        bytes_sent: sent.load(Ordering::Relaxed) as usize,
+        bytes_received,
+      }
+    };
+
+    self.observe(context, &request, exchange).await
+  }
+
+  /// Deserialize the response of an exchange, recording its metrics and, with the `tracing`
+  /// feature, its outcome.
+  async fn observe<R>(
+    &self,
+    context: &RequestContext,
+    request: &Request<Vec<u8>>,
+    exchange: impl Future<Output = Exchange>,
+  ) -> LemmyResult<R>
+  where
+    R: for<'de> Deserialize<'de> + Clone + Send + Sync + 'static,
+  {
+    let endpoint = context.endpoint;
+    let call = async {
+      // Metrics cost nothing unless a recorder is set.
+      let start = self.metrics.is_some().then(Instant::now);
+      let Exchange {
+        result,
+        attempts,
+        bytes_sent,
+        bytes_received,
+      } = exchange.await;
+
+      #[cfg(feature = "tracing")]
+      crate::instrument::record_response(attempts, &result);
+      let status = result.as_ref().ok().map(http::Response::status);
+      let response = result.and_then(deserialize_response);
+
+      if let (Some(metrics), Some(start)) = (&self.metrics, start) {
+        metrics.record(&RequestMetrics {
+          domain: &self.options.domain,
+          endpoint,
+          latency: start.elapsed(),
+          retries: attempts - 1,
+          bytes_sent,
+          bytes_received,
+          status,
+          error: response.as_ref().err(),
+        });
+      }
+
+      response
+    };
+
+    #[cfg(feature = "tracing")]
+    let call = crate::instrument::instrument(endpoint, request, call);
+    #[cfg(not(feature = "tracing"))]
+    let _ = request;
+
+    call.await
+  }
+
+  /// Send a request, or with [`ClientOptions::coalesce_requests`], wait for an identical GET
+  /// request that is already in flight.
+  async fn exchange_or_join(
+    &self,
+    context: &RequestContext,
+    request: &Request<Vec<u8>>,
+  ) -> Exchange {
+    if !self.options.coalesce_requests || request.method() != Method::GET {
+      return exchange(
+        self.transport.as_ref(),
+        &self.rate_limiter,
+        &self.middleware,
+        &self.options.retry_policy,
+        context,
+        request,
+      )
+      .await;
+    }
+
+    let key = cache_key(request);
+    let shared = {
+      let mut in_flight = self.in_flight.lock().unwrap_or_else(|e| e.into_inner());
+      match in_flight.get(&key).and_then(|(_, weak)| weak.upgrade()) {
+        Some(shared) => shared,
+        None => {
+          static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+          let entry = InFlightEntry {
+            in_flight: self.in_flight.clone(),
+            key: key.clone(),
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+          };
+          let id = entry.id;
+          let transport = self.transport.clone();
+          let rate_limiter = self.rate_limiter.clone();
+          let middleware = self.middleware.clone();
+          let retry_policy = self.options.retry_policy.clone();
+          let context = context.clone();
+          let request = clone_request(request);
+
+          // Owns everything it needs, so that the waiters left can finish it if the first one is
+          // dropped.
+          let future = async move {
+            let _entry = entry;
+
+            Arc::new(
+              exchange(
+                transport.as_ref(),
+                &rate_limiter,
+                &middleware,
+                &retry_policy,
+                &context,
+                &request,
+              )
+              .await,
+            )
+          };
+
+          #[cfg(not(target_arch = "wasm32"))]
+          let shared = future.boxed().shared();
+          #[cfg(target_arch = "wasm32")]
+          let shared = future.boxed_local().shared();
+          if let Some(weak) = shared.downgrade() {
+            in_flight.insert(key, (id, weak));
+          }
+
+          shared
+        }
+      }
+    };
+
+    Arc::try_unwrap(shared.await).unwrap_or_else(|shared| Exchange {
+      result: match &shared.result {
+        Ok(response) => Ok(clone_response(response)),
+        Err(error) => Err(error.duplicate()),
+      },
+      ..*shared
+    })
+  }
+
+  /// The context [`Middleware`] see a call to an endpoint in, with secrets such as passwords
+  /// redacted from its data.
+  fn request_context(&self, endpoint: Endpoint, data: &impl Serialize) -> RequestContext {
+    // Only middleware look at the data, so it isn't converted without any.
+    let data = if self.middleware.is_empty() {
+      Value::Null
+    } else {
+      let mut data = serde_json::to_value(data).unwrap_or_default();
+      redact_json(&mut data);
+
+      data
+    };
+
+    RequestContext::new(endpoint, data)
+  }
+
+  /// Send a request, retrying it once with a fresh JWT if it failed because the
+  /// [`Session`][crate::Session]'s JWT was no longer accepted.
+  async fn send_request<R>(
+    &self,
+    context: RequestContext,
+    mut request: Request<Vec<u8>>,
+  ) -> LemmyResult<R>
+  where
+    R: for<'de> Deserialize<'de> + Clone + Send + Sync + 'static,
+  {
+    let error = match self.send_with_retries(&context, &request).await {
+      Err(error) => error,
+      result => return result,
     };
 
-    if !self.headers.contains_key(USER_AGENT) {
-      request_builder = request_builder.header(USER_AGENT, "Lemmy-Client-rs/1.0.0");
+    match self
+      .recover_session(
+        context.endpoint,
+        request.headers().get(AUTHORIZATION),
+        &error,
+      )
+      .await
+    {
+      Some(authorization) => {
+        request.headers_mut().insert(AUTHORIZATION, authorization);
+        self.send_with_retries(&context, &request).await
+      }
+      None => Err(error),
     }
+  }
 
-    request_builder.headers(self.headers.clone())
+  /// Log in without going through [`send_request`][Self::send_request], so that a session can
+  /// log in again from within it.
+  pub(crate) async fn send_login(&self, login: Login) -> LemmyResult<LoginResponse> {
+    self
+      .scoped(Endpoint::Login, async {
+        let start = Instant::now();
+        let mut request = self
+          .build_request(Endpoint::Login, &login)
+          .await
+          .inspect_err(|e| self.record_unobserved(Endpoint::Login, start, e))?;
+        request.headers_mut().remove(AUTHORIZATION);
+        let context = self.request_context(Endpoint::Login, &login);
+
+        self.send_with_retries(&context, &request).await
+      })
+      .await
   }
 
   pub(crate) async fn make_request<Response>(
     &self,
-    method: Method,
-    path: &str,
+    endpoint: Endpoint,
     body: impl Serialize + Clone + fmt::Debug,
   ) -> LemmyResult<Response>
   where
     // TODO in the future, we can use trait aliases for these: https://doc.rust-lang.org/unstable-book/language-features/trait-alias.html
-    Response: for<'de> Deserialize<'de>,
+    Response: for<'de> Deserialize<'de> + Clone + Send + Sync + 'static,
   {
-    let request_builder = self.create_request_builder(&method, path);
+    self
+      .scoped(endpoint, async {
+        let start = Instant::now();
+        let request = self
+          .build_request(endpoint, &body)
+          .await
+          .inspect_err(|e| self.record_unobserved(endpoint, start, e))?;
 
-    let request_builder = match method {
-      Method::GET | Method::DELETE => request_builder.query(&body),
-      Method::POST | Method::PUT => request_builder.json(&body),
-      _ => unreachable!("This crate does not use other HTTP methods."),
-    };
+        self
+          .send_request(self.request_context(endpoint, &body), request)
+          .await
+      })
+      .await
+  }
 
-    let res = send_request(request_builder).await?;
+  /// Build the request for a call to `endpoint`, with `body` in the query or as JSON depending on
+  /// its HTTP method.
+  pub(crate) async fn build_request(
+    &self,
+    endpoint: Endpoint,
+    body: &impl Serialize,
+  ) -> LemmyResult<Request<Vec<u8>>> {
+    let (method, route) = self.route(endpoint).await?;
 
-    deserialize_response(&res)
+    match method {
+      Method::GET | Method::DELETE => {
+        let query = serde_urlencoded::to_string(body).map_err(map_request_error)?;
+        self
+          .create_request_builder(method, &route, &query)?
+          .body(Vec::new())
+      }
+      Method::POST | Method::PUT => {
+        let json = serde_json::to_vec(body).map_err(map_request_error)?;
+        self
+          .create_request_builder(method, &route, "")?
+          .header(CONTENT_TYPE, "application/json")
+          .body(json)
+      }
+      _ => unreachable!("This crate does not use other HTTP methods."),
+    }
+    .map_err(map_request_error)
   }
 
+  /// Upload a file. Bodies held in memory are sent like any other request, and others are
+  /// streamed, without retries.
   pub(crate) async fn make_file_request(
     &self,
-    path: &str,
+    endpoint: Endpoint,
     query: impl Serialize + Clone + fmt::Debug,
-    body: impl Into<Body>,
+    body: impl Into<UploadBody>,
   ) -> LemmyResult<UploadImageResponse> {
-    let request_builder = self
-      .create_request_builder(&Method::POST, path)
-      .query(&query)
-      .body(body);
+    let body = body.into().into_buffered();
+
+    self
+      .scoped(endpoint, async {
+        let start = Instant::now();
+        let context = self.request_context(endpoint, &query);
+        let request = async {
+          let query = serde_urlencoded::to_string(&query).map_err(map_request_error)?;
+          let (method, route) = self.route(endpoint).await?;
+          let builder = self.create_request_builder(method, &route, &query)?;
 
-    let res = send_request(request_builder).await?;
+          match body {
+            Ok(bytes) => builder.body(Either::Left(Vec::from(bytes))),
+            Err(body) => match body.size() {
+              Some(size) => builder.header(CONTENT_LENGTH, size),
+              None => builder,
+            }
+            .body(Either::Right(body)),
+          }
+          .map_err(map_request_error)
+        }
+        .await
+        .inspect_err(|e| self.record_unobserved(endpoint, start, e))?;
 
-    deserialize_response(&res)
+        let (parts, body) = request.into_parts();
+        match body {
+          Either::Left(bytes) => {
+            self
+              .send_request(context, Request::from_parts(parts, bytes))
+              .await
+          }
+          Either::Right(body) => {
+            self
+              .send_streaming(&context, Request::from_parts(parts, body))
+              .await
+          }
+        }
+      })
+      .await
   }
 }
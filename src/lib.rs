@@ -1,4 +1,6 @@
 #![warn(missing_docs)]
+// Clients are shared through `Arc` on every target, even though they are not `Send` on WASM.
+#![cfg_attr(target_arch = "wasm32", allow(clippy::arc_with_non_send_sync))]
 #![doc(
   html_favicon_url = "https://raw.githubusercontent.com/LemmyNet/lemmy-ui/main/src/assets/icons/favicon.svg"
 )]
@@ -14,10 +16,7 @@
 //! use lemmy_api_common::account::auth::Login;
 //!
 //! async fn get_site_test() {
-//!   let mut client = LemmyClient::new(ClientOptions {
-//!     domain: "lemmy.ml",
-//!     secure: true
-//!   });
+//!   let mut client = LemmyClient::new(ClientOptions::new("lemmy.ml"));
 //!   let res = client.get_site().await;
 //!
 //!   assert!(res.is_ok());
@@ -36,10 +35,66 @@
 //! }
 //! ```
 
+mod api_version;
+mod base_url;
+#[cfg(all(feature = "blocking", not(target_arch = "wasm32")))]
+pub mod blocking;
+mod bulk;
+mod cache;
+mod cancel;
+mod capabilities;
 mod client_options;
+mod conditional;
 mod endpoints;
+mod error;
+#[cfg(feature = "tracing")]
+mod instrument;
 mod lemmy_client;
+mod metrics;
+mod middleware;
+mod multi_account;
+mod pagination;
+mod pool;
+mod rate_limit;
+mod redact;
+mod retry;
+mod session;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod transport;
+mod upload;
 
-pub use client_options::ClientOptions;
+pub use api_version::{ApiVersion, ApiVersionMode};
+pub use base_url::{BaseUrl, InvalidBaseUrl};
+pub use bulk::{Bulk, BulkItem, BulkOperation, BulkOutcome, BulkReport};
+#[cfg(not(target_arch = "wasm32"))]
+pub use cache::FileCache;
+pub use cache::{CacheStore, CachedResponse, MemoryCache, ResponseCache};
+pub use cancel::CancellationToken;
+pub use capabilities::{Capabilities, Feature};
+pub use client_options::{ClientOptions, HttpOptions};
+pub use conditional::{ConditionalRequests, NotModifiedMode};
+pub use endpoints::Endpoint;
+pub use error::ClientError;
 pub use lemmy_api_common;
 pub use lemmy_client::{LemmyClient, LemmyResult};
+#[cfg(feature = "metrics")]
+pub use metrics::MetricsFacade;
+pub use metrics::{MetricsRecorder, RequestMetrics};
+pub use middleware::{Middleware, RequestContext};
+pub use multi_account::MultiAccountClient;
+pub use pagination::{PagedRequest, PaginationOptions, paginate, paginate_pages};
+pub use pool::{LemmyClientPool, PoolOptions};
+pub use rate_limit::{Limit, RateLimitCategory, RateLimitMode, RateLimits};
+pub use retry::{Backoff, RetryPolicy};
+#[cfg(not(target_arch = "wasm32"))]
+pub use session::FileStore;
+#[cfg(target_arch = "wasm32")]
+pub use session::LocalStorageStore;
+pub use session::{CredentialStore, MemoryStore, Session, SessionData};
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+pub use transport::FetchTransport;
+#[cfg(feature = "native")]
+pub use transport::ReqwestTransport;
+pub use transport::{MaybeSend, MaybeSendSync, Transport, TransportError, TransportFuture};
+pub use upload::{UploadBody, UploadProgress};
@@ -0,0 +1,134 @@
+use crate::{ClientError, Endpoint, transport::MaybeSendSync};
+use http::StatusCode;
+use std::{borrow::Cow, time::Duration};
+
+/// What happened to one request to an instance, handed to a [`MetricsRecorder`] once the request
+/// is done.
+///
+/// Calls that fail before their request is sent, e.g. because the endpoint is missing from the
+/// instance's version of the API, and calls that are cancelled or time out are recorded as well,
+/// without a status.
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct RequestMetrics<'a> {
+  /// The instance the request was sent to.
+  pub domain: &'a str,
+  /// The endpoint that was called.
+  pub endpoint: Endpoint,
+  /// How long the request took, including rate limiting and retries.
+  pub latency: Duration,
+  /// How many times the request was retried.
+  pub retries: u32,
+  /// Size of the request body, summed over the attempts handed to the [`Transport`][transport].
+  ///
+  /// [transport]: crate::Transport
+  pub bytes_sent: usize,
+  /// Size of the response bodies, summed over the same attempts as
+  /// [`bytes_sent`][Self::bytes_sent].
+  pub bytes_received: usize,
+  /// Status of the last response, if one was received.
+  pub status: Option<StatusCode>,
+  /// Why the request failed, if it did.
+  pub error: Option<&'a ClientError>,
+}
+
+impl RequestMetrics<'_> {
+  /// A label for grouping requests by outcome: `success`, the Lemmy error type for API errors,
+  /// e.g. `not_logged_in`, or the kind of error otherwise, e.g. `timeout` or `decode`.
+  pub fn outcome(&self) -> Cow<'static, str> {
+    let Some(error) = self.error else {
+      return Cow::Borrowed("success");
+    };
+
+    match error {
+      ClientError::Api { error, .. } => serde_json::to_value(error)
+        .ok()
+        .and_then(|json| json.get("error")?.as_str().map(str::to_owned))
+        .map_or(Cow::Borrowed("api"), Cow::Owned),
+      ClientError::Transport(_) => "transport".into(),
+      ClientError::Timeout => "timeout".into(),
+      ClientError::InvalidRequest(_) => "invalid_request".into(),
+      ClientError::InvalidOptions(_) => "invalid_options".into(),
+      ClientError::NonJson { .. } => "non_json".into(),
+      ClientError::Decode { .. } => "decode".into(),
+      ClientError::CredentialStore(_) => "credential_store".into(),
+      ClientError::UnsupportedOnServerVersion { .. } => "unsupported_on_server_version".into(),
+      ClientError::UnsupportedFeature { .. } => "unsupported_feature".into(),
+      ClientError::NotModified => "not_modified".into(),
+      ClientError::Cancelled => "cancelled".into(),
+    }
+  }
+}
+
+/// Receives [`RequestMetrics`] for every request a [`LemmyClient`][client] sends, e.g. to feed
+/// dashboards. Set it with [`LemmyClient::set_metrics_recorder`][set].
+///
+/// Clients without a recorder don't collect any metrics. With the `metrics` feature,
+/// `MetricsFacade` records them with the `metrics` crate.
+/// ```
+/// # use lemmy_client::{MetricsRecorder, RequestMetrics};
+/// # use std::sync::atomic::{AtomicUsize, Ordering};
+/// #[derive(Default)]
+/// struct BytesReceived(AtomicUsize);
+///
+/// impl MetricsRecorder for BytesReceived {
+///   fn record(&self, metrics: &RequestMetrics) {
+///     self.0.fetch_add(metrics.bytes_received, Ordering::Relaxed);
+///   }
+/// }
+/// ```
+///
+/// [client]: crate::LemmyClient
+/// [set]: crate::LemmyClient::set_metrics_recorder
+pub trait MetricsRecorder: MaybeSendSync {
+  /// Records a finished request.
+  fn record(&self, metrics: &RequestMetrics);
+}
+
+impl<R: MetricsRecorder + ?Sized> MetricsRecorder for std::sync::Arc<R> {
+  fn record(&self, metrics: &RequestMetrics) {
+    (**self).record(metrics)
+  }
+}
+
+/// A [`MetricsRecorder`] that records with the [`metrics`](::metrics) crate, so that the metrics
+/// end up wherever its installed recorder exports them to, e.g. Prometheus.
+///
+/// Every metric is labeled with the `domain` and `endpoint`:
+/// - `lemmy_client_requests_total`: counter of requests, also labeled with their
+///   [`outcome`][RequestMetrics::outcome].
+/// - `lemmy_client_request_duration_seconds`: histogram of request latencies.
+/// - `lemmy_client_request_retries_total`: counter of retries.
+/// - `lemmy_client_sent_bytes_total` and `lemmy_client_received_bytes_total`: counters of body
+///   sizes.
+#[cfg(feature = "metrics")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MetricsFacade;
+
+#[cfg(feature = "metrics")]
+impl MetricsRecorder for MetricsFacade {
+  fn record(&self, metrics: &RequestMetrics) {
+    let domain = metrics.domain.to_owned();
+    let endpoint = metrics.endpoint.name();
+    let labels = [
+      ("domain", domain.clone()),
+      ("endpoint", endpoint.to_owned()),
+    ];
+
+    ::metrics::counter!(
+      "lemmy_client_requests_total",
+      "domain" => domain,
+      "endpoint" => endpoint,
+      "outcome" => metrics.outcome(),
+    )
+    .increment(1);
+    ::metrics::histogram!("lemmy_client_request_duration_seconds", &labels)
+      .record(metrics.latency.as_secs_f64());
+    ::metrics::counter!("lemmy_client_request_retries_total", &labels)
+      .increment(metrics.retries.into());
+    ::metrics::counter!("lemmy_client_sent_bytes_total", &labels)
+      .increment(metrics.bytes_sent as u64);
+    ::metrics::counter!("lemmy_client_received_bytes_total", &labels)
+      .increment(metrics.bytes_received as u64);
+  }
+}
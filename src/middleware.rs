@@ -0,0 +1,110 @@
+use crate::{Endpoint, LemmyResult, transport::MaybeSendSync};
+use bytes::Bytes;
+use http::{Request, Response};
+use serde_json::Value;
+use std::ops::ControlFlow;
+
+/// What a request that passes through a [`Middleware`] is for.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct RequestContext {
+  /// The endpoint being called.
+  pub endpoint: Endpoint,
+  /// The data the endpoint was called with as JSON, e.g. the `GetPosts` passed to
+  /// [`list_posts`][crate::LemmyClient::list_posts], or the query of an image upload. `Null` for
+  /// endpoints without data.
+  ///
+  /// Secrets such as passwords, tokens and TOTP codes are replaced with `"[REDACTED]"`, so that
+  /// middleware can log the data safely. Only the request itself holds them.
+  pub data: Value,
+}
+
+impl RequestContext {
+  pub(crate) fn new(endpoint: Endpoint, data: Value) -> Self {
+    Self { endpoint, data }
+  }
+}
+
+/// Hooks into every request a [`LemmyClient`][client] sends, e.g. to add headers that change
+/// between requests, sign requests, serve responses from a cache or rewrite errors. Register it
+/// with [`LemmyClient::add_middleware`][add].
+///
+/// Middleware run in the order they were added before a request is sent, and in reverse order
+/// once it is done. Both hooks do nothing by default.
+/// ```
+/// # use http::{HeaderValue, Request};
+/// # use lemmy_client::{Middleware, RequestContext};
+/// # use std::{
+/// #   ops::ControlFlow,
+/// #   sync::atomic::{AtomicU64, Ordering},
+/// # };
+/// /// Numbers every request.
+/// #[derive(Default)]
+/// struct RequestId(AtomicU64);
+///
+/// impl Middleware for RequestId {
+///   fn before_request(
+///     &self,
+///     _context: &RequestContext,
+///     request: &mut Request<Vec<u8>>,
+///   ) -> ControlFlow<lemmy_client::LemmyResult<http::Response<bytes::Bytes>>> {
+///     let id = self.0.fetch_add(1, Ordering::Relaxed);
+///     request
+///       .headers_mut()
+///       .insert("x-request-id", HeaderValue::from(id));
+///
+///     ControlFlow::Continue(())
+///   }
+/// }
+/// ```
+///
+/// [client]: crate::LemmyClient
+/// [add]: crate::LemmyClient::add_middleware
+pub trait Middleware: MaybeSendSync {
+  /// Called before each attempt at sending a request, retries included, with the request
+  /// complete with headers and body.
+  ///
+  /// Returning [`ControlFlow::Break`] skips sending the request and the later middleware's
+  /// `before_request`, and uses the result instead, without retrying it.
+  /// [`after_response`][Self::after_response] is still called.
+  fn before_request(
+    &self,
+    context: &RequestContext,
+    request: &mut Request<Vec<u8>>,
+  ) -> ControlFlow<LemmyResult<Response<Bytes>>> {
+    let _ = (context, request);
+    ControlFlow::Continue(())
+  }
+
+  /// Called with the raw response once a request is done, retries included, before it is
+  /// decoded. `request` is the request as the client built it, before any middleware changed it.
+  /// Returns the result to use instead.
+  fn after_response(
+    &self,
+    context: &RequestContext,
+    request: &Request<Vec<u8>>,
+    result: LemmyResult<Response<Bytes>>,
+  ) -> LemmyResult<Response<Bytes>> {
+    let _ = (context, request);
+    result
+  }
+}
+
+impl<M: Middleware + ?Sized> Middleware for std::sync::Arc<M> {
+  fn before_request(
+    &self,
+    context: &RequestContext,
+    request: &mut Request<Vec<u8>>,
+  ) -> ControlFlow<LemmyResult<Response<Bytes>>> {
+    (**self).before_request(context, request)
+  }
+
+  fn after_response(
+    &self,
+    context: &RequestContext,
+    request: &Request<Vec<u8>>,
+    result: LemmyResult<Response<Bytes>>,
+  ) -> LemmyResult<Response<Bytes>> {
+    (**self).after_response(context, request, result)
+  }
+}
@@ -0,0 +1,154 @@
+use crate::{ClientError, LemmyClient, LemmyResult};
+use futures_util::future::join_all;
+use lemmy_api_common::{
+  account::auth::{Login, LoginResponse},
+  oauth::AuthenticateWithOauth,
+};
+use std::{
+  collections::BTreeMap,
+  future::Future,
+  sync::{Arc, RwLock},
+};
+
+/// Acts on behalf of several accounts on the same instance, with one connection pool shared by
+/// all of them.
+///
+/// Accounts are registered under a name of your choosing with [`login`][Self::login],
+/// [`authenticate_with_oauth`][Self::authenticate_with_oauth] or
+/// [`add_account`][Self::add_account]. Each one gets its own [`LemmyClient`] created with
+/// [`LemmyClient::as_user`], so every call is scoped to that account's JWT.
+///
+/// ```
+/// use lemmy_client::{ClientOptions, LemmyClient, MultiAccountClient};
+/// use lemmy_api_common::{account::auth::Login, post::actions::moderation::FeaturePost};
+///
+/// async fn feature_everywhere(moderators: Vec<(String, Login)>, feature: FeaturePost) {
+///   let accounts = MultiAccountClient::new(LemmyClient::new(ClientOptions::new("lemmy.ml")));
+///   for (name, login) in moderators {
+///     accounts.login(name, login).await.unwrap();
+///   }
+///
+///   let results = accounts
+///     .for_each_account(|_, client| {
+///       let feature = feature.clone();
+///       async move { client.feature_post(feature).await }
+///     })
+///     .await;
+///
+///   for (name, result) in results {
+///     println!("{name}: {}", if result.is_ok() { "done" } else { "failed" });
+///   }
+/// }
+/// ```
+pub struct MultiAccountClient {
+  client: LemmyClient,
+  accounts: RwLock<BTreeMap<String, Arc<LemmyClient>>>,
+}
+
+impl MultiAccountClient {
+  /// Creates a registry without any accounts. Every account's client is derived from `client`.
+  pub fn new(client: LemmyClient) -> Self {
+    Self {
+      client,
+      accounts: RwLock::default(),
+    }
+  }
+
+  /// Returns the client the accounts' clients are derived from. Requests made with it are not
+  /// scoped to any of the accounts.
+  pub fn client(&self) -> &LemmyClient {
+    &self.client
+  }
+
+  /// Logs in and registers the account under `name`, replacing any account of the same name.
+  ///
+  /// If the instance doesn't hand out a JWT, e.g. because the account's email isn't verified
+  /// yet, nothing is registered and the response is returned as is.
+  pub async fn login(&self, name: impl Into<String>, data: Login) -> LemmyResult<LoginResponse> {
+    let response = self.client.login(data).await?;
+    self.register(name.into(), &response)?;
+
+    Ok(response)
+  }
+
+  /// Logs in through an OAuth provider and registers the account under `name`, replacing any
+  /// account of the same name.
+  ///
+  /// If the instance doesn't hand out a JWT, e.g. because a registration application is
+  /// pending, nothing is registered and the response is returned as is.
+  pub async fn authenticate_with_oauth(
+    &self,
+    name: impl Into<String>,
+    data: AuthenticateWithOauth,
+  ) -> LemmyResult<LoginResponse> {
+    let response = self.client.authenticate_with_oauth(data).await?;
+    self.register(name.into(), &response)?;
+
+    Ok(response)
+  }
+
+  fn register(&self, name: String, response: &LoginResponse) -> LemmyResult<()> {
+    if let Some(jwt) = &response.jwt {
+      self
+        .add_account(name, jwt)
+        .map_err(|e| ClientError::InvalidRequest(Box::new(e)))?;
+    }
+
+    Ok(())
+  }
+
+  /// Registers an account that is already logged in under `name`, replacing any account of the
+  /// same name. There is no need to include the "Bearer" part of the JWT.
+  pub fn add_account(
+    &self,
+    name: impl Into<String>,
+    jwt: &str,
+  ) -> Result<(), http::header::InvalidHeaderValue> {
+    let client = self.client.as_user(jwt)?;
+    self.write().insert(name.into(), Arc::new(client));
+
+    Ok(())
+  }
+
+  /// Unregisters an account, returning its client. This doesn't log the account out.
+  pub fn remove_account(&self, name: &str) -> Option<Arc<LemmyClient>> {
+    self.write().remove(name)
+  }
+
+  /// Returns the client for the account registered under `name`.
+  pub fn as_user(&self, name: &str) -> Option<Arc<LemmyClient>> {
+    self.read().get(name).cloned()
+  }
+
+  /// Returns the names of all registered accounts, in alphabetical order.
+  pub fn accounts(&self) -> Vec<String> {
+    self.read().keys().cloned().collect()
+  }
+
+  /// Runs the same operation as every registered account concurrently, returning the result for
+  /// each account by name.
+  pub async fn for_each_account<F, Fut>(&self, mut f: F) -> BTreeMap<String, Fut::Output>
+  where
+    F: FnMut(&str, Arc<LemmyClient>) -> Fut,
+    Fut: Future,
+  {
+    let accounts = self.read().clone();
+    let (names, futures): (Vec<_>, Vec<_>) = accounts
+      .into_iter()
+      .map(|(name, client)| {
+        let future = f(&name, client);
+        (name, future)
+      })
+      .unzip();
+
+    names.into_iter().zip(join_all(futures).await).collect()
+  }
+
+  fn read(&self) -> std::sync::RwLockReadGuard<'_, BTreeMap<String, Arc<LemmyClient>>> {
+    self.accounts.read().unwrap_or_else(|e| e.into_inner())
+  }
+
+  fn write(&self) -> std::sync::RwLockWriteGuard<'_, BTreeMap<String, Arc<LemmyClient>>> {
+    self.accounts.write().unwrap_or_else(|e| e.into_inner())
+  }
+}
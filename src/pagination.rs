@@ -0,0 +1,188 @@
+use crate::LemmyResult;
+use futures_util::{Stream, StreamExt, TryStreamExt, stream};
+use lemmy_api_common::{
+  PagedResponse,
+  PaginationCursor,
+  account::{ListPersonHidden, ListPersonLiked, ListPersonRead, ListPersonSaved},
+  comment::{GetComments, actions::moderation::ListCommentLikes},
+  community::{
+    ListCommunities,
+    ListMultiCommunities,
+    actions::moderation::ListCommunityPendingFollows,
+  },
+  federation::GetFederatedInstances,
+  media::ListMedia,
+  modlog::GetModlog,
+  notification::ListNotifications,
+  person::actions::ListPersonContent,
+  post::{GetPosts, actions::moderation::ListPostLikes},
+  report::ListReports,
+  site::administration::{AdminListUsers, ListRegistrationApplications},
+  tagline::ListTaglines,
+};
+use std::future::Future;
+
+/// A request for an endpoint that returns a [`PagedResponse`].
+pub trait PagedRequest: Clone {
+  /// Sets the cursor of the page to fetch. `None` fetches the first page.
+  fn set_page_cursor(&mut self, cursor: Option<PaginationCursor>);
+
+  /// Sets the maximum number of items per page.
+  fn set_limit(&mut self, limit: i64);
+}
+
+macro_rules! impl_paged_request {
+  ($($request:ty),* $(,)?) => {
+    $(
+      impl PagedRequest for $request {
+        fn set_page_cursor(&mut self, cursor: Option<PaginationCursor>) {
+          self.page_cursor = cursor;
+        }
+
+        fn set_limit(&mut self, limit: i64) {
+          self.limit = Some(limit);
+        }
+      }
+    )*
+  };
+}
+
+impl_paged_request!(
+  AdminListUsers,
+  GetComments,
+  GetFederatedInstances,
+  GetModlog,
+  GetPosts,
+  ListCommentLikes,
+  ListCommunities,
+  ListCommunityPendingFollows,
+  ListMedia,
+  ListMultiCommunities,
+  ListNotifications,
+  ListPersonContent,
+  ListPersonHidden,
+  ListPersonLiked,
+  ListPersonRead,
+  ListPersonSaved,
+  ListPostLikes,
+  ListRegistrationApplications,
+  ListReports,
+  ListTaglines,
+);
+
+/// Options for paginating through an endpoint with [`paginate`] or [`paginate_pages`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PaginationOptions {
+  /// Number of items to request per page. Uses the request's own limit, or the instance's
+  /// default, if not set.
+  pub page_size: Option<i64>,
+  /// Stop after this many items. Only used by [`paginate`].
+  pub max_items: Option<usize>,
+  /// Cursor to resume from, e.g. a [`next_page`][PagedResponse::next_page] saved from an earlier
+  /// run. Starts at the request's own cursor, or the first page, if not set.
+  pub start_cursor: Option<PaginationCursor>,
+}
+
+/// Fetches pages one after another by following [`next_page`][PagedResponse::next_page] until
+/// the last page, yielding each page as it arrives.
+///
+/// Use this over [`paginate`] to save the cursor of each page, so that a later run can resume
+/// with [`PaginationOptions::start_cursor`]. The stream ends after the first error.
+///
+/// ```
+/// use futures_util::TryStreamExt;
+/// use lemmy_client::{LemmyClient, PaginationOptions, paginate_pages};
+///
+/// async fn walk_pages(client: &LemmyClient) {
+///   let pages = paginate_pages(
+///     Default::default(),
+///     PaginationOptions::default(),
+///     |request| client.list_posts(request),
+///   );
+///   futures_util::pin_mut!(pages);
+///
+///   while let Some(page) = pages.try_next().await.unwrap() {
+///     // Persist `page.next_page` to resume later.
+///   }
+/// }
+/// ```
+pub fn paginate_pages<'a, Request, Item, Fetch, Fut>(
+  mut request: Request,
+  options: PaginationOptions,
+  fetch: Fetch,
+) -> impl Stream<Item = LemmyResult<PagedResponse<Item>>> + 'a
+where
+  Request: PagedRequest + 'a,
+  Item: 'a,
+  Fetch: FnMut(Request) -> Fut + 'a,
+  Fut: Future<Output = LemmyResult<PagedResponse<Item>>> + 'a,
+{
+  if let Some(page_size) = options.page_size {
+    request.set_limit(page_size);
+  }
+  if let Some(cursor) = options.start_cursor {
+    request.set_page_cursor(Some(cursor));
+  }
+
+  stream::unfold(Some((request, fetch)), |state| async move {
+    let (mut request, mut fetch) = state?;
+
+    match fetch(request.clone()).await {
+      Ok(page) => {
+        // An empty page is also treated as the end to avoid looping on a misbehaving instance.
+        let next = match &page.next_page {
+          Some(cursor) if !page.items.is_empty() => {
+            request.set_page_cursor(Some(cursor.clone()));
+            Some((request, fetch))
+          }
+          _ => None,
+        };
+
+        Some((Ok(page), next))
+      }
+      Err(e) => Some((Err(e), None)),
+    }
+  })
+}
+
+/// Fetches every item of a paged endpoint, following [`next_page`][PagedResponse::next_page]
+/// until the last page or until [`PaginationOptions::max_items`] items have been yielded.
+///
+/// Pages are only requested once the items of the previous page have been consumed. The stream
+/// ends after the first error.
+///
+/// ```
+/// use futures_util::TryStreamExt;
+/// use lemmy_client::{LemmyClient, PaginationOptions, paginate};
+///
+/// async fn newest_titles(client: &LemmyClient) -> Vec<String> {
+///   let options = PaginationOptions {
+///     max_items: Some(100),
+///     ..Default::default()
+///   };
+///
+///   paginate(Default::default(), options, |request| client.list_posts(request))
+///     .map_ok(|post| post.post.name)
+///     .try_collect()
+///     .await
+///     .unwrap()
+/// }
+/// ```
+pub fn paginate<'a, Request, Item, Fetch, Fut>(
+  request: Request,
+  options: PaginationOptions,
+  fetch: Fetch,
+) -> impl Stream<Item = LemmyResult<Item>> + 'a
+where
+  Request: PagedRequest + 'a,
+  Item: 'a,
+  Fetch: FnMut(Request) -> Fut + 'a,
+  Fut: Future<Output = LemmyResult<PagedResponse<Item>>> + 'a,
+{
+  let max_items = options.max_items.unwrap_or(usize::MAX);
+
+  paginate_pages(request, options, fetch)
+    .map_ok(|page| stream::iter(page.items.into_iter().map(Ok)))
+    .try_flatten()
+    .take(max_items)
+}
@@ -0,0 +1,392 @@
+use crate::{
+  ApiVersionMode,
+  ClientOptions,
+  HttpOptions,
+  LemmyClient,
+  LemmyResult,
+  MetricsRecorder,
+  Middleware,
+  PaginationOptions,
+  RateLimitMode,
+  RetryPolicy,
+  Transport,
+  TransportFuture,
+  paginate,
+};
+use futures_util::{StreamExt, TryStreamExt, stream};
+use http::Request;
+use lemmy_api_common::{federation::GetFederatedInstances, site::GetSiteResponse};
+use std::{
+  collections::{BTreeMap, HashSet, VecDeque},
+  future::Future,
+  pin::Pin,
+  sync::{Arc, Mutex, RwLock},
+  task::{Context, Poll, Waker},
+};
+
+/// Options for a [`LemmyClientPool`].
+///
+/// The defaults allow 4 concurrent requests per instance and fan out to 16 instances at a
+/// time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PoolOptions {
+  /// Whether the pool's clients use HTTPS.
+  pub secure: bool,
+  /// The retry policy of the pool's clients.
+  pub retry_policy: RetryPolicy,
+  /// The rate limiting of the pool's clients. Each instance is throttled separately.
+  pub rate_limit: RateLimitMode,
+  /// Which version of the API the pool's clients talk to their instance with. With
+  /// [`ApiVersionMode::Detect`], each client detects the version of its own instance.
+  pub api_version: ApiVersionMode,
+  /// Connection settings of the transport shared by the pool's clients, when the pool is created
+  /// with [`new`][LemmyClientPool::new].
+  pub http: HttpOptions,
+  /// How many requests may be in flight to the same instance at once. `None` means no limit.
+  pub max_concurrent_requests_per_host: Option<usize>,
+  /// How many instances the fan-out helpers, like
+  /// [`for_each_instance`][LemmyClientPool::for_each_instance], talk to at once.
+  pub max_concurrent_hosts: usize,
+}
+
+impl Default for PoolOptions {
+  fn default() -> Self {
+    Self {
+      secure: true,
+      retry_policy: RetryPolicy::default(),
+      rate_limit: RateLimitMode::default(),
+      api_version: ApiVersionMode::default(),
+      http: HttpOptions::default(),
+      max_concurrent_requests_per_host: Some(4),
+      max_concurrent_hosts: 16,
+    }
+  }
+}
+
+/// A minimal runtime-agnostic semaphore that hands out permits in the order they were asked for.
+#[derive(Debug)]
+struct Semaphore {
+  state: Mutex<SemaphoreState>,
+}
+
+#[derive(Debug, Default)]
+struct SemaphoreState {
+  permits: usize,
+  /// Tasks waiting for a permit, oldest first.
+  waiters: VecDeque<(u64, Waker)>,
+  /// Waiters that were handed a permit but haven't been polled since.
+  granted: HashSet<u64>,
+  next_id: u64,
+}
+
+impl Semaphore {
+  fn new(permits: usize) -> Self {
+    Self {
+      state: Mutex::new(SemaphoreState {
+        permits,
+        ..Default::default()
+      }),
+    }
+  }
+
+  fn acquire(&self) -> Acquire<'_> {
+    Acquire {
+      semaphore: self,
+      id: None,
+    }
+  }
+
+  /// Hands a returned permit to the oldest waiter, waking only that one.
+  fn release(&self) {
+    let waker = {
+      let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+      match state.waiters.pop_front() {
+        Some((id, waker)) => {
+          state.granted.insert(id);
+          Some(waker)
+        }
+        None => {
+          state.permits += 1;
+          None
+        }
+      }
+    };
+
+    if let Some(waker) = waker {
+      waker.wake();
+    }
+  }
+}
+
+/// Waits for a [`Permit`], keeping its place in the queue between polls.
+struct Acquire<'a> {
+  semaphore: &'a Semaphore,
+  /// Set once the future is queued.
+  id: Option<u64>,
+}
+
+impl<'a> Future for Acquire<'a> {
+  type Output = Permit<'a>;
+
+  fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+    let semaphore = self.semaphore;
+    let mut state = semaphore.state.lock().unwrap_or_else(|e| e.into_inner());
+
+    match self.id {
+      None if state.permits > 0 && state.waiters.is_empty() => {
+        state.permits -= 1;
+        Poll::Ready(Permit(semaphore))
+      }
+      None => {
+        let id = state.next_id;
+        state.next_id += 1;
+        state.waiters.push_back((id, cx.waker().clone()));
+        self.id = Some(id);
+        Poll::Pending
+      }
+      Some(id) if state.granted.remove(&id) => {
+        self.id = None;
+        Poll::Ready(Permit(semaphore))
+      }
+      Some(id) => {
+        if let Some((_, waker)) = state.waiters.iter_mut().find(|(waiter, _)| *waiter == id) {
+          waker.clone_from(cx.waker());
+        }
+        Poll::Pending
+      }
+    }
+  }
+}
+
+impl Drop for Acquire<'_> {
+  fn drop(&mut self) {
+    let Some(id) = self.id else {
+      return;
+    };
+
+    let granted = {
+      let mut state = self
+        .semaphore
+        .state
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+      state.waiters.retain(|(waiter, _)| *waiter != id);
+      state.granted.remove(&id)
+    };
+
+    // Pass on a permit that was handed to this waiter after it gave up.
+    if granted {
+      self.semaphore.release();
+    }
+  }
+}
+
+struct Permit<'a>(&'a Semaphore);
+
+impl Drop for Permit<'_> {
+  fn drop(&mut self) {
+    self.0.release();
+  }
+}
+
+/// Limits how many requests may be in flight through the shared transport to one instance.
+struct HostLimit {
+  transport: Arc<dyn Transport>,
+  semaphore: Semaphore,
+}
+
+impl Transport for HostLimit {
+  fn send(&self, request: Request<Vec<u8>>) -> TransportFuture<'_> {
+    Box::pin(async move {
+      let _permit = self.semaphore.acquire().await;
+      self.transport.send(request).await
+    })
+  }
+}
+
+/// Clients for many Lemmy instances, created on demand and sharing one [`Transport`], and
+/// with it one connection pool.
+///
+/// ```no_run
+/// use lemmy_client::{LemmyClientPool, PoolOptions};
+///
+/// async fn versions() {
+///   let pool = LemmyClientPool::new(PoolOptions::default());
+///   let sites = pool
+///     .get_federated_sites("lemmy.ml", Default::default())
+///     .await
+///     .unwrap();
+///
+///   for (domain, site) in sites {
+///     match site {
+///       Ok(site) => println!("{domain}: {}", site.version),
+///       Err(e) => println!("{domain}: {e}"),
+///     }
+///   }
+/// }
+/// ```
+pub struct LemmyClientPool {
+  transport: Arc<dyn Transport>,
+  options: PoolOptions,
+  metrics: Option<Arc<dyn MetricsRecorder>>,
+  middleware: Vec<Arc<dyn Middleware>>,
+  clients: RwLock<BTreeMap<String, Arc<LemmyClient>>>,
+}
+
+impl LemmyClientPool {
+  /// Creates a pool whose clients send requests with one shared [`reqwest`] client, built from
+  /// the [`HttpOptions`] in `options`, or with the browser's fetch API when targeting WASM with
+  /// the `wasm` feature.
+  ///
+  /// If the shared client can't be created from the [`HttpOptions`], every request fails with
+  /// [`ClientError::InvalidOptions`][crate::ClientError::InvalidOptions].
+  #[cfg(any(feature = "native", all(feature = "wasm", target_arch = "wasm32")))]
+  pub fn new(options: PoolOptions) -> Self {
+    match crate::transport::default_transport(&options.http) {
+      Ok(transport) => Self::with_transport(options, transport),
+      Err(e) => Self::with_transport(options, crate::transport::Unavailable(e)),
+    }
+  }
+
+  /// Creates a pool whose clients send requests through a shared custom [`Transport`].
+  pub fn with_transport(options: PoolOptions, transport: impl Transport + 'static) -> Self {
+    Self {
+      transport: Arc::new(transport),
+      options,
+      metrics: None,
+      middleware: Vec::new(),
+      clients: RwLock::default(),
+    }
+  }
+
+  /// Sets the [`MetricsRecorder`] that the requests of clients created from now on are reported
+  /// to. [`RequestMetrics::domain`][crate::RequestMetrics::domain] tells the instances apart.
+  pub fn set_metrics_recorder(&mut self, recorder: impl MetricsRecorder + 'static) {
+    self.metrics = Some(Arc::new(recorder));
+  }
+
+  /// Adds a [`Middleware`] that the requests of clients created from now on pass through.
+  pub fn add_middleware(&mut self, middleware: impl Middleware + 'static) {
+    self.middleware.push(Arc::new(middleware));
+  }
+
+  /// Returns the client for an instance, creating it if needed.
+  pub fn client(&self, domain: &str) -> Arc<LemmyClient> {
+    if let Some(client) = self
+      .clients
+      .read()
+      .unwrap_or_else(|e| e.into_inner())
+      .get(domain)
+    {
+      return client.clone();
+    }
+
+    let PoolOptions {
+      secure,
+      retry_policy,
+      rate_limit,
+      api_version,
+      http,
+      max_concurrent_requests_per_host,
+      ..
+    } = self.options.clone();
+
+    self
+      .clients
+      .write()
+      .unwrap_or_else(|e| e.into_inner())
+      .entry(domain.to_owned())
+      .or_insert_with(|| {
+        let options = ClientOptions {
+          secure,
+          retry_policy,
+          rate_limit,
+          api_version,
+          http,
+          ..ClientOptions::new(domain.to_owned())
+        };
+        let mut client = match max_concurrent_requests_per_host {
+          Some(permits) => LemmyClient::with_transport(
+            options,
+            HostLimit {
+              transport: self.transport.clone(),
+              semaphore: Semaphore::new(permits.max(1)),
+            },
+          ),
+          None => LemmyClient::with_transport(options, self.transport.clone()),
+        };
+        client.metrics = self.metrics.clone();
+        client.middleware = self.middleware.clone();
+
+        Arc::new(client)
+      })
+      .clone()
+  }
+
+  /// Returns the domains of all instances a client has been created for.
+  pub fn domains(&self) -> Vec<String> {
+    self
+      .clients
+      .read()
+      .unwrap_or_else(|e| e.into_inner())
+      .keys()
+      .cloned()
+      .collect()
+  }
+
+  /// Runs an operation against each instance, a few instances at a time, returning the result
+  /// for each instance by domain.
+  pub async fn for_each_instance<D, F, Fut>(
+    &self,
+    domains: impl IntoIterator<Item = D>,
+    mut f: F,
+  ) -> BTreeMap<String, Fut::Output>
+  where
+    D: Into<String>,
+    F: FnMut(Arc<LemmyClient>) -> Fut,
+    Fut: Future,
+  {
+    let futures = domains.into_iter().map(|domain| {
+      let domain = domain.into();
+      let future = f(self.client(&domain));
+      async move { (domain, future.await) }
+    });
+
+    stream::iter(futures)
+      .buffer_unordered(self.options.max_concurrent_hosts.max(1))
+      .collect()
+      .await
+  }
+
+  /// Calls [`get_site`][LemmyClient::get_site] on every Lemmy instance that `domain` lists in
+  /// [`get_federated_instances`][LemmyClient::get_federated_instances].
+  ///
+  /// Fails only if the list of instances can't be fetched. Errors of the individual instances
+  /// are returned along with their domain.
+  pub async fn get_federated_sites(
+    &self,
+    domain: &str,
+    request: GetFederatedInstances,
+  ) -> LemmyResult<BTreeMap<String, LemmyResult<GetSiteResponse>>> {
+    let client = self.client(domain);
+    let domains: Vec<String> = paginate(request, PaginationOptions::default(), |request| {
+      client.get_federated_instances(request)
+    })
+    .try_filter_map(|view| async move {
+      let is_lemmy = view
+        .instance
+        .software
+        .is_some_and(|software| software.eq_ignore_ascii_case("lemmy"));
+
+      Ok(is_lemmy.then_some(view.instance.domain))
+    })
+    .try_collect()
+    .await?;
+
+    Ok(
+      self
+        .for_each_instance(domains, |client| async move { client.get_site().await })
+        .await,
+    )
+  }
+}
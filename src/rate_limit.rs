@@ -0,0 +1,286 @@
+use crate::{Endpoint, retry};
+use lemmy_api_common::site::LocalSiteRateLimit;
+use std::{sync::Mutex, time::Duration};
+use web_time::Instant;
+
+/// The rate limit buckets Lemmy sorts its endpoints into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RateLimitCategory {
+  /// Used by every endpoint that does not fall into one of the other categories.
+  Message,
+  /// Creating posts.
+  Post,
+  /// Registering accounts.
+  Register,
+  /// Uploading images.
+  Image,
+  /// Creating comments.
+  Comment,
+  /// Searching and resolving objects.
+  Search,
+  /// Importing user settings.
+  ImportUserSettings,
+}
+
+impl RateLimitCategory {
+  const COUNT: usize = 7;
+
+  fn index(self) -> usize {
+    self as usize
+  }
+}
+
+impl Endpoint {
+  /// The rate limit bucket the instance counts calls to this endpoint against.
+  pub fn rate_limit_category(self) -> RateLimitCategory {
+    match self {
+      Endpoint::CreatePost => RateLimitCategory::Post,
+      Endpoint::RegisterAccount | Endpoint::AuthenticateWithOauth => RateLimitCategory::Register,
+      Endpoint::UploadImage
+      | Endpoint::UploadUserAvatar
+      | Endpoint::UploadUserBanner
+      | Endpoint::UploadCommunityIcon
+      | Endpoint::UploadCommunityBanner
+      | Endpoint::UploadSiteIcon
+      | Endpoint::UploadSiteBanner => RateLimitCategory::Image,
+      Endpoint::CreateComment => RateLimitCategory::Comment,
+      Endpoint::Search | Endpoint::ResolveObject => RateLimitCategory::Search,
+      Endpoint::ImportSettings => RateLimitCategory::ImportUserSettings,
+      _ => RateLimitCategory::Message,
+    }
+  }
+}
+
+/// How many requests may be made in a given interval.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limit {
+  /// Maximum number of requests per interval.
+  pub max_requests: u32,
+  /// Length of the interval.
+  pub interval: Duration,
+}
+
+impl Limit {
+  fn new(max_requests: i32, interval_seconds: i32) -> Self {
+    Self {
+      max_requests: max_requests.try_into().unwrap_or_default(),
+      interval: Duration::from_secs(interval_seconds.try_into().unwrap_or_default()),
+    }
+  }
+}
+
+/// Limits for every [`RateLimitCategory`]. A category without a limit is not throttled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RateLimits {
+  /// Limit for [`RateLimitCategory::Message`].
+  pub message: Option<Limit>,
+  /// Limit for [`RateLimitCategory::Post`].
+  pub post: Option<Limit>,
+  /// Limit for [`RateLimitCategory::Register`].
+  pub register: Option<Limit>,
+  /// Limit for [`RateLimitCategory::Image`].
+  pub image: Option<Limit>,
+  /// Limit for [`RateLimitCategory::Comment`].
+  pub comment: Option<Limit>,
+  /// Limit for [`RateLimitCategory::Search`].
+  pub search: Option<Limit>,
+  /// Limit for [`RateLimitCategory::ImportUserSettings`].
+  pub import_user_settings: Option<Limit>,
+}
+
+impl RateLimits {
+  /// Returns the limit for a category.
+  pub fn get(&self, category: RateLimitCategory) -> Option<Limit> {
+    match category {
+      RateLimitCategory::Message => self.message,
+      RateLimitCategory::Post => self.post,
+      RateLimitCategory::Register => self.register,
+      RateLimitCategory::Image => self.image,
+      RateLimitCategory::Comment => self.comment,
+      RateLimitCategory::Search => self.search,
+      RateLimitCategory::ImportUserSettings => self.import_user_settings,
+    }
+  }
+}
+
+impl From<&LocalSiteRateLimit> for RateLimits {
+  fn from(limits: &LocalSiteRateLimit) -> Self {
+    Self {
+      message: Some(Limit::new(
+        limits.message_max_requests,
+        limits.message_interval_seconds,
+      )),
+      post: Some(Limit::new(
+        limits.post_max_requests,
+        limits.post_interval_seconds,
+      )),
+      register: Some(Limit::new(
+        limits.register_max_requests,
+        limits.register_interval_seconds,
+      )),
+      image: Some(Limit::new(
+        limits.image_max_requests,
+        limits.image_interval_seconds,
+      )),
+      comment: Some(Limit::new(
+        limits.comment_max_requests,
+        limits.comment_interval_seconds,
+      )),
+      search: Some(Limit::new(
+        limits.search_max_requests,
+        limits.search_interval_seconds,
+      )),
+      import_user_settings: Some(Limit::new(
+        limits.import_user_settings_max_requests,
+        limits.import_user_settings_interval_seconds,
+      )),
+    }
+  }
+}
+
+/// Whether and how [`LemmyClient`][client] throttles its own requests to stay within the
+/// instance's rate limits.
+///
+/// ```
+/// # use lemmy_client::{ClientOptions, LemmyClient, RateLimitMode};
+/// async fn bulk_job() {
+///   let client = LemmyClient::new(ClientOptions {
+///     rate_limit: RateLimitMode::FromSite,
+///     ..ClientOptions::new("lemmy.ml")
+///   });
+///
+///   // Configures the limiter with the limits the instance publishes.
+///   client.get_site().await.unwrap();
+/// }
+/// ```
+///
+/// [client]: crate::LemmyClient
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RateLimitMode {
+  /// Requests are never throttled.
+  #[default]
+  Disabled,
+  /// Requests are throttled with fixed limits.
+  Fixed(RateLimits),
+  /// Requests are throttled with the limits from the instance's
+  /// [`LocalSiteRateLimit`], which are picked up whenever
+  /// [`get_site`][get_site] is called. Until then, requests are not throttled.
+  ///
+  /// [get_site]: crate::LemmyClient::get_site
+  FromSite,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+  limit: Limit,
+  tokens: f64,
+  updated: Instant,
+}
+
+impl Bucket {
+  fn new(limit: Limit) -> Self {
+    Self {
+      limit,
+      tokens: f64::from(limit.max_requests),
+      updated: Instant::now(),
+    }
+  }
+
+  /// Takes a token if one is available, otherwise returns how long until one will be.
+  fn try_take(&mut self) -> Result<(), Duration> {
+    let Limit {
+      max_requests,
+      interval,
+    } = self.limit;
+    if max_requests == 0 || interval.is_zero() {
+      return Ok(());
+    }
+
+    let now = Instant::now();
+    let refill_rate = f64::from(max_requests) / interval.as_secs_f64();
+    self.tokens =
+      (self.tokens + refill_rate * (now - self.updated).as_secs_f64()).min(f64::from(max_requests));
+    self.updated = now;
+
+    if self.tokens >= 1.0 {
+      self.tokens -= 1.0;
+      Ok(())
+    } else {
+      Err(Duration::from_secs_f64((1.0 - self.tokens) / refill_rate))
+    }
+  }
+}
+
+/// Token bucket limiter with one bucket per [`RateLimitCategory`].
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+  mode: RateLimitMode,
+  buckets: Mutex<[Option<Bucket>; RateLimitCategory::COUNT]>,
+}
+
+impl RateLimiter {
+  pub fn new(mode: RateLimitMode) -> Self {
+    let limiter = Self {
+      mode,
+      buckets: Mutex::new([None; RateLimitCategory::COUNT]),
+    };
+
+    if let RateLimitMode::Fixed(limits) = mode {
+      limiter.configure(&limits);
+    }
+
+    limiter
+  }
+
+  fn configure(&self, limits: &RateLimits) {
+    let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+
+    for category in [
+      RateLimitCategory::Message,
+      RateLimitCategory::Post,
+      RateLimitCategory::Register,
+      RateLimitCategory::Image,
+      RateLimitCategory::Comment,
+      RateLimitCategory::Search,
+      RateLimitCategory::ImportUserSettings,
+    ] {
+      let bucket = &mut buckets[category.index()];
+      let limit = limits.get(category);
+
+      // Keep the tokens of unchanged buckets so re-fetching the site doesn't reset them.
+      if bucket.map(|b| b.limit) != limit {
+        *bucket = limit.map(Bucket::new);
+      }
+    }
+  }
+
+  /// Updates the limits from the site's settings if the limiter is in
+  /// [`RateLimitMode::FromSite`] mode.
+  pub fn update_from_site(&self, limits: &LocalSiteRateLimit) {
+    if self.mode == RateLimitMode::FromSite {
+      self.configure(&limits.into());
+    }
+  }
+
+  /// Waits until a request to `endpoint` is allowed.
+  pub async fn acquire(&self, endpoint: Endpoint) {
+    if self.mode == RateLimitMode::Disabled {
+      return;
+    }
+
+    let index = endpoint.rate_limit_category().index();
+
+    loop {
+      let wait = {
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+
+        match buckets[index].as_mut().map(Bucket::try_take) {
+          None | Some(Ok(())) => return,
+          Some(Err(wait)) => wait,
+        }
+      };
+
+      retry::sleep(wait).await;
+    }
+  }
+}
@@ -0,0 +1,80 @@
+//! Keeping secrets out of cassettes, logs and the data seen by middleware.
+
+#[cfg(any(feature = "testing", feature = "tracing"))]
+use http::{HeaderName, header};
+use serde_json::Value;
+
+/// Placeholder that replaces secrets.
+pub(crate) const REDACTED: &str = "[REDACTED]";
+
+/// Headers that carry credentials.
+#[cfg(any(feature = "testing", feature = "tracing"))]
+pub(crate) const SECRET_HEADERS: [HeaderName; 3] =
+  [header::AUTHORIZATION, header::COOKIE, header::SET_COOKIE];
+
+/// Fields of request and response bodies, and of queries, that hold secrets: passwords, login and
+/// verification tokens, TOTP secrets, OAuth codes and secrets, API keys, captcha answers and
+/// private keys.
+const SECRET_FIELDS: &[&str] = &[
+  "admin_password",
+  "api_key",
+  "captcha_answer",
+  "client_secret",
+  "code",
+  "jwt",
+  "jwt_secret",
+  "new_password",
+  "new_password_verify",
+  "old_password",
+  "password",
+  "password_encrypted",
+  "password_verify",
+  "pkce_code_verifier",
+  "private_key",
+  "token",
+  "totp_2fa_secret",
+  "totp_2fa_token",
+  "totp_secret_url",
+  "totp_token",
+  "verification_token",
+];
+
+/// Whether a field of a body or query holds a secret.
+pub(crate) fn is_secret_field(name: &str) -> bool {
+  SECRET_FIELDS.contains(&name)
+}
+
+/// Replaces the values of secret fields, at any depth, with [`REDACTED`].
+pub(crate) fn redact_json(value: &mut Value) {
+  match value {
+    Value::Object(fields) => {
+      for (name, value) in fields {
+        if is_secret_field(name) && !value.is_null() {
+          *value = Value::from(REDACTED);
+        } else {
+          redact_json(value);
+        }
+      }
+    }
+    Value::Array(values) => values.iter_mut().for_each(redact_json),
+    _ => {}
+  }
+}
+
+/// Replaces the values of secret fields of a URL query with [`REDACTED`].
+#[cfg(any(feature = "testing", feature = "tracing"))]
+pub(crate) fn redact_query(query: &str) -> String {
+  let pairs = serde_urlencoded::from_str::<Vec<(String, String)>>(query).unwrap_or_default();
+  let pairs = pairs
+    .into_iter()
+    .map(|(key, value)| {
+      if is_secret_field(&key) {
+        (key, REDACTED.to_owned())
+      } else {
+        (key, value)
+      }
+    })
+    .collect::<Vec<_>>();
+
+  serde_urlencoded::to_string(pairs).unwrap_or_default()
+}
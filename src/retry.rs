@@ -0,0 +1,191 @@
+use crate::ClientError;
+use bytes::Bytes;
+use http::{HeaderMap, Method, Response, StatusCode, header::RETRY_AFTER};
+use lemmy_api_common::error::LemmyErrorType;
+use std::time::Duration;
+use web_time::SystemTime;
+
+/// The curve used to space out retries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backoff {
+  /// Wait the same amount of time before every retry.
+  Constant(Duration),
+  /// Double the delay after every retry, starting at `initial` and never exceeding `max`.
+  Exponential {
+    /// Delay before the first retry.
+    initial: Duration,
+    /// Upper bound for the delay.
+    max: Duration,
+  },
+}
+
+impl Backoff {
+  /// The delay before retry number `retry`, starting at 1.
+  fn delay(&self, retry: u32) -> Duration {
+    match *self {
+      Self::Constant(delay) => delay,
+      Self::Exponential { initial, max } => initial
+        .saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)))
+        .min(max),
+    }
+  }
+}
+
+/// Controls if and how [`LemmyClient`][client] retries failed requests.
+///
+/// A request is retried when the transport fails, when the instance responds with one of
+/// [`retry_statuses`][RetryPolicy::retry_statuses], or when Lemmy responds with one of
+/// [`retry_errors`][RetryPolicy::retry_errors]. Requests that are not idempotent (HTTP POST, e.g.
+/// [`create_post`][create_post]) are only retried when the instance rate limited them, since it
+/// is then known that they were not processed, unless
+/// [`retry_non_idempotent`][RetryPolicy::retry_non_idempotent] is set.
+///
+/// ```
+/// # use lemmy_client::{ClientOptions, RetryPolicy};
+/// let options = ClientOptions {
+///   retry_policy: RetryPolicy {
+///     max_attempts: 5,
+///     ..RetryPolicy::default()
+///   },
+///   ..ClientOptions::new("lemmy.ml")
+/// };
+/// ```
+///
+/// [client]: crate::LemmyClient
+/// [create_post]: crate::LemmyClient::create_post
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+  /// Total number of attempts, including the first one. A value of 1 disables retries.
+  pub max_attempts: u32,
+  /// How long to wait between attempts.
+  pub backoff: Backoff,
+  /// Fraction of each delay, between 0 and 1, that is randomized to keep many clients from
+  /// retrying at the same instant.
+  pub jitter: f64,
+  /// HTTP statuses that cause a retry.
+  pub retry_statuses: Vec<StatusCode>,
+  /// Lemmy API errors that cause a retry.
+  pub retry_errors: Vec<LemmyErrorType>,
+  /// Whether transport failures and timeouts cause a retry.
+  pub retry_transport_errors: bool,
+  /// Whether requests that are not idempotent are retried as well.
+  pub retry_non_idempotent: bool,
+  /// Whether to wait for the duration given in a `Retry-After` response header, if present,
+  /// instead of using [`backoff`][RetryPolicy::backoff].
+  pub respect_retry_after: bool,
+  /// Upper bound for any single delay, including ones requested by `Retry-After`.
+  pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+  fn default() -> Self {
+    Self {
+      max_attempts: 3,
+      backoff: Backoff::Exponential {
+        initial: Duration::from_millis(500),
+        max: Duration::from_secs(30),
+      },
+      jitter: 0.5,
+      retry_statuses: vec![
+        StatusCode::TOO_MANY_REQUESTS,
+        StatusCode::BAD_GATEWAY,
+        StatusCode::SERVICE_UNAVAILABLE,
+        StatusCode::GATEWAY_TIMEOUT,
+      ],
+      retry_errors: vec![LemmyErrorType::TooManyRequests],
+      retry_transport_errors: true,
+      retry_non_idempotent: false,
+      respect_retry_after: true,
+      max_delay: Duration::from_secs(60),
+    }
+  }
+}
+
+impl RetryPolicy {
+  /// A policy that never retries.
+  pub fn never() -> Self {
+    Self {
+      max_attempts: 1,
+      ..Self::default()
+    }
+  }
+
+  /// Decides whether a request should be retried after `attempt` attempts, and if so, how long
+  /// to wait first.
+  pub(crate) fn retry_delay(
+    &self,
+    method: &Method,
+    attempt: u32,
+    result: &Result<Response<Bytes>, ClientError>,
+  ) -> Option<Duration> {
+    if attempt >= self.max_attempts {
+      return None;
+    }
+
+    let idempotent = self.retry_non_idempotent || *method != Method::POST;
+
+    let retry_after = match result {
+      Err(ClientError::Transport(_) | ClientError::Timeout)
+        if self.retry_transport_errors && idempotent =>
+      {
+        None
+      }
+      Err(_) => return None,
+      Ok(res) => {
+        let status = res.status();
+        if status.is_success() {
+          return None;
+        }
+
+        let error = serde_json::from_slice::<LemmyErrorType>(res.body()).ok();
+        // A rate limited request was rejected before being processed, so it can always be
+        // replayed safely.
+        let rate_limited =
+          status == StatusCode::TOO_MANY_REQUESTS || error == Some(LemmyErrorType::TooManyRequests);
+        let retryable = self.retry_statuses.contains(&status)
+          || error.is_some_and(|e| self.retry_errors.contains(&e));
+
+        if !(retryable && (idempotent || rate_limited)) {
+          return None;
+        }
+
+        self
+          .respect_retry_after
+          .then(|| parse_retry_after(res.headers()))
+          .flatten()
+      }
+    };
+
+    let delay = retry_after.unwrap_or_else(|| self.jittered(self.backoff.delay(attempt)));
+
+    Some(delay.min(self.max_delay))
+  }
+
+  fn jittered(&self, delay: Duration) -> Duration {
+    let jitter = self.jitter.clamp(0.0, 1.0);
+
+    delay.mul_f64(1.0 - jitter * fastrand::f64())
+  }
+}
+
+/// Reads a `Retry-After` header given either in seconds or as an HTTP date.
+fn parse_retry_after(headers: &HeaderMap) -> Option<Duration> {
+  let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+
+  if let Ok(seconds) = value.parse::<u64>() {
+    return Some(Duration::from_secs(seconds));
+  }
+
+  let date = httpdate::parse_http_date(value).ok()?;
+  let now = std::time::UNIX_EPOCH
+    + SystemTime::now()
+      .duration_since(web_time::UNIX_EPOCH)
+      .ok()?;
+
+  Some(date.duration_since(now).unwrap_or_default())
+}
+
+/// Waits for `duration` without tying the client to a specific async runtime.
+pub(crate) async fn sleep(duration: Duration) {
+  futures_timer::Delay::new(duration).await;
+}
@@ -0,0 +1,458 @@
+use crate::{ClientError, Endpoint, LemmyClient, LemmyResult, transport::MaybeSendSync};
+use http::HeaderValue;
+use lemmy_api_common::{
+  SensitiveString,
+  account::{
+    MyUserInfo,
+    auth::{Login, LoginResponse},
+  },
+  error::LemmyErrorType,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+  io,
+  sync::{Arc, RwLock},
+};
+
+/// Everything known about the account a [`Session`] is logged in as.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionData {
+  /// The response to the login that started the session. Its JWT is refreshed whenever the
+  /// session logs in again.
+  pub login: LoginResponse,
+  /// The account's info, as of the last time the session was started or restored.
+  pub user: Option<MyUserInfo>,
+  /// The credentials to log in again with once the JWT stops working. Only kept when
+  /// [`Session::relogin`] is enabled.
+  pub credentials: Option<Login>,
+}
+
+/// Where a [`Session`] persists its [`SessionData`], so that it can be restored with
+/// [`LemmyClient::restore_session`] after a restart.
+///
+/// Keep in mind that a session with [`relogin`][Session::relogin] enabled stores the account's
+/// password.
+pub trait CredentialStore: MaybeSendSync {
+  /// Loads the stored session, if there is one.
+  fn load(&self) -> io::Result<Option<SessionData>>;
+
+  /// Replaces the stored session.
+  fn save(&self, session: &SessionData) -> io::Result<()>;
+
+  /// Removes the stored session.
+  fn clear(&self) -> io::Result<()>;
+}
+
+/// A [`CredentialStore`] that keeps the session in memory. This is the store a client uses
+/// unless another one is set.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+  session: RwLock<Option<SessionData>>,
+}
+
+impl CredentialStore for MemoryStore {
+  fn load(&self) -> io::Result<Option<SessionData>> {
+    Ok(
+      self
+        .session
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone(),
+    )
+  }
+
+  fn save(&self, session: &SessionData) -> io::Result<()> {
+    *self.session.write().unwrap_or_else(|e| e.into_inner()) = Some(session.clone());
+
+    Ok(())
+  }
+
+  fn clear(&self) -> io::Result<()> {
+    *self.session.write().unwrap_or_else(|e| e.into_inner()) = None;
+
+    Ok(())
+  }
+}
+
+/// A [`CredentialStore`] that keeps the session in a JSON file. On Unix, the file is only
+/// readable by its owner.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone)]
+pub struct FileStore {
+  path: std::path::PathBuf,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl FileStore {
+  /// Creates a store that keeps the session in the file at `path`.
+  pub fn new(path: impl Into<std::path::PathBuf>) -> Self {
+    Self { path: path.into() }
+  }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl CredentialStore for FileStore {
+  fn load(&self) -> io::Result<Option<SessionData>> {
+    match std::fs::read(&self.path) {
+      Ok(file) => Ok(Some(serde_json::from_slice(&file)?)),
+      Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+      Err(e) => Err(e),
+    }
+  }
+
+  fn save(&self, session: &SessionData) -> io::Result<()> {
+    use std::io::Write;
+
+    // Written next to the file and renamed over it, so that a crash or a full disk never leaves a
+    // partly written session behind. The random suffix keeps concurrent writers apart.
+    let temp = self
+      .path
+      .with_extension(format!("{:016x}.tmp", fastrand::u64(..)));
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    let written = options
+      .open(&temp)
+      .and_then(|mut file| file.write_all(&serde_json::to_vec(session)?))
+      .and_then(|()| std::fs::rename(&temp, &self.path));
+    if written.is_err() {
+      let _ = std::fs::remove_file(&temp);
+    }
+
+    written
+  }
+
+  fn clear(&self) -> io::Result<()> {
+    match std::fs::remove_file(&self.path) {
+      Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+      _ => Ok(()),
+    }
+  }
+}
+
+/// A [`CredentialStore`] that keeps the session in the browser's `localStorage`.
+#[cfg(target_arch = "wasm32")]
+#[derive(Debug, Clone)]
+pub struct LocalStorageStore {
+  key: String,
+}
+
+#[cfg(target_arch = "wasm32")]
+impl LocalStorageStore {
+  /// Creates a store that keeps the session under `key`.
+  pub fn new(key: impl Into<String>) -> Self {
+    Self { key: key.into() }
+  }
+
+  fn storage() -> io::Result<web_sys::Storage> {
+    web_sys::window()
+      .ok_or_else(|| io::Error::other("no window"))?
+      .local_storage()
+      .map_err(|e| io::Error::other(format!("{e:?}")))?
+      .ok_or_else(|| io::Error::other("localStorage is not available"))
+  }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl CredentialStore for LocalStorageStore {
+  fn load(&self) -> io::Result<Option<SessionData>> {
+    Self::storage()?
+      .get_item(&self.key)
+      .map_err(|e| io::Error::other(format!("{e:?}")))?
+      .map(|session| serde_json::from_str(&session).map_err(io::Error::from))
+      .transpose()
+  }
+
+  fn save(&self, session: &SessionData) -> io::Result<()> {
+    Self::storage()?
+      .set_item(&self.key, &serde_json::to_string(session)?)
+      .map_err(|e| io::Error::other(format!("{e:?}")))
+  }
+
+  fn clear(&self) -> io::Result<()> {
+    Self::storage()?
+      .remove_item(&self.key)
+      .map_err(|e| io::Error::other(format!("{e:?}")))
+  }
+}
+
+#[derive(Debug, Default)]
+struct SessionState {
+  data: Option<SessionData>,
+  expired: bool,
+}
+
+/// Tracks the account a [`LemmyClient`] is logged in as.
+///
+/// Start a session with [`LemmyClient::start_session`] instead of calling
+/// [`login`][LemmyClient::login] and [`set_jwt`][LemmyClient::set_jwt] by hand. While a session
+/// is active, its JWT is sent with every request, taking precedence over one set with
+/// [`set_jwt`][LemmyClient::set_jwt].
+///
+/// When a request fails because the JWT is no longer accepted, the session is marked as
+/// [expired][Session::is_expired]. With [`relogin`][Session::relogin] enabled, the session
+/// instead logs in again with the stored credentials and retries the request once.
+///
+/// ```no_run
+/// use lemmy_client::{ClientOptions, FileStore, LemmyClient, Session};
+///
+/// async fn resume() {
+///   let mut client = LemmyClient::new(ClientOptions::new("lemmy.ml"));
+///   client.set_session(Session::new(FileStore::new("session.json")).relogin(true));
+///
+///   match client.restore_session().await.unwrap() {
+///     Some(session) => println!("Welcome back!"),
+///     None => { /* Ask for credentials and call `start_session`. */ }
+///   }
+/// }
+/// ```
+pub struct Session {
+  store: Box<dyn CredentialStore>,
+  state: RwLock<SessionState>,
+  relogin: bool,
+}
+
+impl Default for Session {
+  fn default() -> Self {
+    Self::new(MemoryStore::default())
+  }
+}
+
+impl Session {
+  /// Creates a session that persists itself in `store`, without logging in again
+  /// automatically.
+  pub fn new(store: impl CredentialStore + 'static) -> Self {
+    Self {
+      store: Box::new(store),
+      state: RwLock::default(),
+      relogin: false,
+    }
+  }
+
+  /// Sets whether the session logs in again with the stored credentials once the JWT stops
+  /// working. Enabling this keeps the account's password in the [`CredentialStore`].
+  pub fn relogin(mut self, enabled: bool) -> Self {
+    self.relogin = enabled;
+    self
+  }
+
+  /// Returns the data of the active session.
+  pub fn data(&self) -> Option<SessionData> {
+    self.read().data.clone()
+  }
+
+  /// Returns the info of the account the session is logged in as.
+  pub fn user(&self) -> Option<MyUserInfo> {
+    self.read().data.as_ref()?.user.clone()
+  }
+
+  /// Returns the JWT of the active session.
+  pub fn jwt(&self) -> Option<SensitiveString> {
+    self.read().data.as_ref()?.login.jwt.clone()
+  }
+
+  /// Returns whether a session has been started or restored.
+  pub fn is_active(&self) -> bool {
+    self.read().data.is_some()
+  }
+
+  /// Returns whether the instance stopped accepting the session's JWT and the session could
+  /// not log in again.
+  pub fn is_expired(&self) -> bool {
+    self.read().expired
+  }
+
+  fn read(&self) -> std::sync::RwLockReadGuard<'_, SessionState> {
+    self.state.read().unwrap_or_else(|e| e.into_inner())
+  }
+
+  fn set(&self, data: Option<SessionData>) {
+    *self.state.write().unwrap_or_else(|e| e.into_inner()) = SessionState {
+      data,
+      expired: false,
+    };
+  }
+
+  /// Changes the data of the active session, returning the result.
+  fn update(&self, f: impl FnOnce(&mut SessionData)) -> Option<SessionData> {
+    let mut state = self.state.write().unwrap_or_else(|e| e.into_inner());
+    let data = state.data.as_mut()?;
+    f(data);
+
+    Some(data.clone())
+  }
+
+  fn expire(&self) {
+    self
+      .state
+      .write()
+      .unwrap_or_else(|e| e.into_inner())
+      .expired = true;
+  }
+
+  fn persist(&self) -> LemmyResult<()> {
+    match &self.read().data {
+      Some(data) => self.store.save(data),
+      None => self.store.clear(),
+    }
+    .map_err(ClientError::CredentialStore)
+  }
+
+  /// Ends the session and removes it from the store.
+  pub(crate) fn clear(&self) -> LemmyResult<()> {
+    self.set(None);
+    self.persist()
+  }
+
+  /// The `Authorization` header for the session's JWT.
+  pub(crate) fn authorization(&self) -> Option<HeaderValue> {
+    HeaderValue::try_from(format!("Bearer {}", &*self.jwt()?)).ok()
+  }
+}
+
+/// Whether an error means the instance no longer accepts the JWT the request was sent with.
+fn is_auth_error(error: &ClientError) -> bool {
+  matches!(
+    error.api_error(),
+    Some(LemmyErrorType::NotLoggedIn | LemmyErrorType::IncorrectLogin)
+  )
+}
+
+impl LemmyClient {
+  /// Returns the client's [`Session`].
+  pub fn session(&self) -> &Session {
+    &self.session
+  }
+
+  /// Replaces the client's [`Session`], e.g. to persist it in a different [`CredentialStore`].
+  pub fn set_session(&mut self, session: Session) {
+    self.session = Arc::new(session);
+  }
+
+  /// Logs in and starts a session as that account, fetching its info with
+  /// [`get_current_user`][LemmyClient::get_current_user] and persisting it in the session's
+  /// [`CredentialStore`].
+  ///
+  /// If the instance doesn't hand out a JWT, e.g. because the account's email isn't verified
+  /// yet, no session is started and the response is returned as is.
+  pub async fn start_session(&self, login: Login) -> LemmyResult<LoginResponse> {
+    let response = self.login(login.clone()).await?;
+    if response.jwt.is_none() {
+      return Ok(response);
+    }
+
+    let credentials = self.session.relogin.then_some(Login {
+      totp_2fa_token: None,
+      ..login
+    });
+    self.session.set(Some(SessionData {
+      login: response.clone(),
+      user: None,
+      credentials,
+    }));
+
+    match self.get_current_user().await {
+      Ok(user) => {
+        self.session.update(|data| data.user = Some(user));
+        self.session.persist()?;
+
+        Ok(response)
+      }
+      Err(e) => {
+        self.session.set(None);
+        Err(e)
+      }
+    }
+  }
+
+  /// Restores the session persisted in the session's [`CredentialStore`] and refreshes the
+  /// account's info. Returns `None` if no session was stored.
+  ///
+  /// Fails if the stored JWT is no longer accepted and the session couldn't log in again, in
+  /// which case the session stays [expired][Session::is_expired].
+  pub async fn restore_session(&self) -> LemmyResult<Option<SessionData>> {
+    let Some(data) = self
+      .session
+      .store
+      .load()
+      .map_err(ClientError::CredentialStore)?
+    else {
+      return Ok(None);
+    };
+    self.session.set(Some(data));
+
+    let user = self.get_current_user().await?;
+    let data = self.session.update(|data| data.user = Some(user));
+    self.session.persist()?;
+
+    Ok(data)
+  }
+
+  /// Logs out of the active session and removes it from the session's [`CredentialStore`].
+  pub async fn end_session(&self) -> LemmyResult<()> {
+    if self.session.is_active() && !self.session.is_expired() {
+      match self
+        .make_request::<lemmy_api_common::SuccessResponse>(Endpoint::Logout, ())
+        .await
+      {
+        Err(e) if !is_auth_error(&e) => return Err(e),
+        _ => {}
+      }
+    }
+
+    self.session.clear()
+  }
+
+  /// Called when a request sent with `authorization` failed with `error`. Returns the header to
+  /// retry the request with, if it should be retried.
+  pub(crate) async fn recover_session(
+    &self,
+    endpoint: Endpoint,
+    authorization: Option<&HeaderValue>,
+    error: &ClientError,
+  ) -> Option<HeaderValue> {
+    if !is_auth_error(error)
+      || matches!(endpoint, Endpoint::Login | Endpoint::Logout)
+      || authorization.is_none()
+    {
+      return None;
+    }
+
+    let current = self.session.authorization()?;
+    // Another request already logged in again since this one was sent.
+    if Some(&current) != authorization {
+      return Some(current);
+    }
+
+    let credentials = self
+      .session
+      .relogin
+      .then(|| self.session.data()?.credentials)
+      .flatten();
+    let jwt = match credentials {
+      Some(credentials) => self
+        .send_login(credentials)
+        .await
+        .ok()
+        .and_then(|response| response.jwt),
+      None => None,
+    };
+
+    match jwt {
+      Some(jwt) => {
+        self.session.update(|data| data.login.jwt = Some(jwt));
+        // The new JWT works for this client either way, so failing to persist it shouldn't
+        // fail the request.
+        let _ = self.session.persist();
+
+        self.session.authorization()
+      }
+      None => {
+        self.session.expire();
+        None
+      }
+    }
+  }
+}
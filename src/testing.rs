@@ -0,0 +1,195 @@
+//! An in-memory mock Lemmy instance for running integration tests without network access.
+//!
+//! [`MockServer`] implements the `/api/v4` routes for sites, accounts, communities, posts,
+//! comments, votes and notifications on top of an in-memory data model. It can be plugged
+//! straight into a [`LemmyClient`] as its [`Transport`], or served over real HTTP on localhost
+//! with [`MockServer::start`] for tests that go through the whole network stack.
+//!
+//! To test against the behaviour of a real instance instead, record the exchanges of a client
+//! with a [`Recorder`] once and replay them offline with a [`Replayer`].
+//!
+//! The first account registered on a mock instance becomes its admin, just like on a real
+//! instance. Routes the mock doesn't implement respond with a `not_found` error.
+//!
+//! ```
+//! use lemmy_client::{
+//!   lemmy_api_common::{account::auth::Register, community::actions::CreateCommunity},
+//!   testing::MockServer,
+//! };
+//!
+//! async fn create_community() {
+//!   let server = MockServer::new();
+//!   let mut client = server.client();
+//!
+//!   let jwt = client
+//!     .register_account(Register {
+//!       username: "alice".to_owned(),
+//!       password: "hunter2hunter2".to_owned().into(),
+//!       password_verify: "hunter2hunter2".to_owned().into(),
+//!       ..Default::default()
+//!     })
+//!     .await
+//!     .unwrap()
+//!     .jwt
+//!     .unwrap();
+//!   client.set_jwt(&jwt.into_inner());
+//!
+//!   let community = client
+//!     .create_community(CreateCommunity {
+//!       name: "rust".to_owned(),
+//!       title: "Rust".to_owned(),
+//!       ..Default::default()
+//!     })
+//!     .await
+//!     .unwrap();
+//!
+//!   assert_eq!(community.community_view.community.name, "rust");
+//! }
+//! ```
+
+use crate::{ClientOptions, LemmyClient, Transport, TransportFuture};
+use bytes::Bytes;
+use http::{
+  HeaderValue,
+  Request,
+  Response,
+  header::{AUTHORIZATION, CONTENT_TYPE, COOKIE, SET_COOKIE},
+};
+use model::Instance;
+use std::sync::{Arc, Mutex};
+
+mod cassette;
+mod model;
+#[cfg(not(target_arch = "wasm32"))]
+mod server;
+
+pub use cassette::{
+  Cassette,
+  Interaction,
+  REDACTED,
+  RecordedBody,
+  RecordedRequest,
+  RecordedResponse,
+  Recorder,
+  Replayer,
+  UnrecordedRequest,
+};
+#[cfg(not(target_arch = "wasm32"))]
+pub use server::RunningMockServer;
+
+/// Domain used by a [`MockServer`] that isn't served over HTTP.
+const MOCK_DOMAIN: &str = "mock.lemmy";
+
+/// An in-memory Lemmy instance. See the [module docs](self) for details.
+///
+/// Clones share the same instance, so a test can hand out clients for several users while
+/// keeping one handle around.
+#[derive(Clone)]
+pub struct MockServer {
+  instance: Arc<Mutex<Instance>>,
+}
+
+impl Default for MockServer {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl std::fmt::Debug for MockServer {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("MockServer").finish_non_exhaustive()
+  }
+}
+
+impl MockServer {
+  /// Creates an empty instance.
+  pub fn new() -> Self {
+    Self {
+      instance: Arc::new(Mutex::new(Instance::new(MOCK_DOMAIN.to_owned()))),
+    }
+  }
+
+  /// Creates an empty instance that reports running the given version of Lemmy, e.g. `0.19.5`,
+  /// for testing how clients handle features that older or newer versions lack.
+  ///
+  /// The instance still only serves `/api/v4`, whatever the version.
+  pub fn with_version(version: impl Into<String>) -> Self {
+    let mut instance = Instance::new(MOCK_DOMAIN.to_owned());
+    instance.set_version(version.into());
+
+    Self {
+      instance: Arc::new(Mutex::new(instance)),
+    }
+  }
+
+  /// Creates a client that talks to this instance in-process, without going over the network.
+  pub fn client(&self) -> LemmyClient {
+    LemmyClient::with_transport(
+      ClientOptions {
+        secure: false,
+        ..ClientOptions::new(MOCK_DOMAIN)
+      },
+      self.clone(),
+    )
+  }
+
+  /// Handles a single request to the instance.
+  ///
+  /// Requests outside of `/api/v4` and to routes the mock doesn't implement get a `not_found`
+  /// error. Like on a real instance, logging in sets a `jwt` cookie, which is accepted in place of
+  /// the `Authorization` header, and logging out clears it.
+  pub fn handle(&self, request: &Request<impl AsRef<[u8]>>) -> Response<Bytes> {
+    let route = request
+      .uri()
+      .path()
+      .strip_prefix("/api/v4/")
+      .unwrap_or_default();
+    let query = request.uri().query().unwrap_or_default();
+    let headers = request.headers();
+    let jwt = headers
+      .get(AUTHORIZATION)
+      .and_then(|value| value.to_str().ok())
+      .and_then(|value| value.strip_prefix("Bearer "))
+      .or_else(|| {
+        headers
+          .get_all(COOKIE)
+          .iter()
+          .filter_map(|value| value.to_str().ok())
+          .flat_map(|value| value.split(';'))
+          .find_map(|cookie| cookie.trim().strip_prefix("jwt="))
+      });
+
+    let (status, body) = self
+      .instance
+      .lock()
+      .unwrap_or_else(|e| e.into_inner())
+      .handle(request.method(), route, query, jwt, request.body().as_ref());
+
+    let cookie = match body.get("jwt").and_then(|jwt| jwt.as_str()) {
+      Some(jwt) if status.is_success() => {
+        Some(format!("jwt={jwt}; Path=/; HttpOnly; SameSite=Lax"))
+      }
+      _ if route == "account/auth/logout" => Some("jwt=; Path=/; Max-Age=0".to_owned()),
+      _ => None,
+    };
+
+    let mut response = Response::new(Bytes::from(body.to_string()));
+    *response.status_mut() = status;
+    response
+      .headers_mut()
+      .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    if let Some(cookie) = cookie.and_then(|cookie| HeaderValue::try_from(cookie).ok()) {
+      response.headers_mut().insert(SET_COOKIE, cookie);
+    }
+
+    response
+  }
+}
+
+impl Transport for MockServer {
+  fn send(&self, request: Request<Vec<u8>>) -> TransportFuture<'_> {
+    let response = self.handle(&request);
+
+    Box::pin(async move { Ok(response) })
+  }
+}
@@ -0,0 +1,385 @@
+//! Recording real exchanges with an instance and replaying them later.
+
+use crate::{
+  ClientError,
+  Transport,
+  TransportFuture,
+  redact::{SECRET_HEADERS, redact_json, redact_query},
+};
+use bytes::Bytes;
+use http::{HeaderMap, Method, Request, Response, StatusCode, Uri};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+  collections::BTreeMap,
+  error::Error,
+  fmt,
+  sync::{Arc, Mutex},
+};
+
+/// Placeholder that replaces secrets in recorded interactions.
+pub const REDACTED: &str = crate::redact::REDACTED;
+
+/// The body of a recorded request or response.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordedBody {
+  /// A JSON body, stored as JSON to keep cassettes readable.
+  Json(Value),
+  /// A body that is valid UTF-8 but not JSON.
+  Text(String),
+  /// Any other body, such as an uploaded image.
+  Binary(Vec<u8>),
+}
+
+impl RecordedBody {
+  /// Captures a body, with any secrets redacted. Returns `None` for empty bodies.
+  fn record(body: &[u8]) -> Option<Self> {
+    if body.is_empty() {
+      None
+    } else if let Ok(mut json) = serde_json::from_slice::<Value>(body) {
+      redact_json(&mut json);
+      Some(Self::Json(json))
+    } else if let Ok(text) = std::str::from_utf8(body) {
+      Some(Self::Text(text.to_owned()))
+    } else {
+      Some(Self::Binary(body.to_vec()))
+    }
+  }
+
+  fn into_bytes(self) -> Bytes {
+    match self {
+      Self::Json(json) => json.to_string().into(),
+      Self::Text(text) => text.into(),
+      Self::Binary(bytes) => bytes.into(),
+    }
+  }
+}
+
+fn record_headers(headers: &HeaderMap) -> BTreeMap<String, String> {
+  headers
+    .iter()
+    .filter_map(|(name, value)| {
+      let value = if SECRET_HEADERS.contains(name) {
+        REDACTED
+      } else {
+        value.to_str().ok()?
+      };
+
+      Some((name.to_string(), value.to_owned()))
+    })
+    .collect()
+}
+
+fn record_uri(uri: &Uri) -> String {
+  let uri = uri.to_string();
+
+  match uri.split_once('?') {
+    Some((base, query)) => format!("{base}?{}", redact_query(query)),
+    None => uri,
+  }
+}
+
+/// A request captured by a [`Recorder`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedRequest {
+  /// The HTTP method.
+  pub method: String,
+  /// The full URL, including the query. Secrets in the query are replaced with [`REDACTED`].
+  pub uri: String,
+  /// The request headers. Credentials are replaced with [`REDACTED`].
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  pub headers: BTreeMap<String, String>,
+  /// The request body. Passwords, tokens and other secrets are replaced with [`REDACTED`].
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub body: Option<RecordedBody>,
+}
+
+impl RecordedRequest {
+  fn record(request: &Request<Vec<u8>>) -> Self {
+    Self {
+      method: request.method().to_string(),
+      uri: record_uri(request.uri()),
+      headers: record_headers(request.headers()),
+      body: RecordedBody::record(request.body()),
+    }
+  }
+
+  /// Whether `other` is the same request, ignoring headers.
+  fn matches(&self, other: &Self) -> bool {
+    self.method == other.method && self.uri == other.uri && self.body == other.body
+  }
+}
+
+/// A response captured by a [`Recorder`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedResponse {
+  /// The HTTP status code.
+  pub status: u16,
+  /// The response headers. Cookies are replaced with [`REDACTED`].
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  pub headers: BTreeMap<String, String>,
+  /// The response body. JWTs and other secrets are replaced with [`REDACTED`].
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub body: Option<RecordedBody>,
+}
+
+impl RecordedResponse {
+  fn record(response: &Response<Bytes>) -> Self {
+    Self {
+      status: response.status().as_u16(),
+      headers: record_headers(response.headers()),
+      body: RecordedBody::record(response.body()),
+    }
+  }
+
+  fn replay(&self) -> Result<Response<Bytes>, ClientError> {
+    let invalid = |e: http::Error| ClientError::Transport(Box::new(e));
+    let mut response =
+      Response::builder().status(StatusCode::from_u16(self.status).map_err(|e| invalid(e.into()))?);
+
+    for (name, value) in &self.headers {
+      response = response.header(name, value);
+    }
+
+    response
+      .body(
+        self
+          .body
+          .clone()
+          .map(RecordedBody::into_bytes)
+          .unwrap_or_default(),
+      )
+      .map_err(invalid)
+  }
+}
+
+/// A request and the response the instance gave to it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Interaction {
+  /// The request sent by the client.
+  pub request: RecordedRequest,
+  /// The response sent by the instance.
+  pub response: RecordedResponse,
+}
+
+/// A list of recorded [`Interaction`]s, stored as JSON.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Cassette {
+  /// The interactions, in the order they were recorded.
+  pub interactions: Vec<Interaction>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Cassette {
+  /// Reads a cassette from a JSON file.
+  pub fn load(path: impl AsRef<std::path::Path>) -> std::io::Result<Self> {
+    let file = std::fs::read(path)?;
+
+    Ok(serde_json::from_slice(&file)?)
+  }
+
+  /// Writes the cassette to a JSON file, creating missing parent directories.
+  pub fn save(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+    let path = path.as_ref();
+    if let Some(parent) = path.parent() {
+      std::fs::create_dir_all(parent)?;
+    }
+
+    std::fs::write(path, serde_json::to_vec_pretty(self)?)
+  }
+}
+
+/// A [`Transport`] that forwards requests to another transport and records every exchange
+/// into a [`Cassette`].
+///
+/// Credentials are redacted before anything is recorded: the `Authorization` and cookie
+/// headers, and every JSON field or query parameter holding a secret, such as a password, JWT,
+/// TOTP secret or OAuth code.
+///
+/// Clones share the same cassette, so keep one around to save it once the client is done.
+///
+/// ```no_run
+/// use lemmy_client::{
+///   ClientOptions,
+///   LemmyClient,
+///   ReqwestTransport,
+///   testing::Recorder,
+/// };
+///
+/// async fn record() {
+///   let recorder = Recorder::new(ReqwestTransport::default());
+///   let client = LemmyClient::with_transport(ClientOptions::new("lemmy.ml"), recorder.clone());
+///
+///   client.get_site().await.unwrap();
+///   recorder.cassette().save("tests/cassettes/get_site.json").unwrap();
+/// }
+/// ```
+pub struct Recorder<T> {
+  transport: Arc<T>,
+  cassette: Arc<Mutex<Cassette>>,
+}
+
+// Not derived to avoid requiring `T: Clone`.
+impl<T> Clone for Recorder<T> {
+  fn clone(&self) -> Self {
+    Self {
+      transport: self.transport.clone(),
+      cassette: self.cassette.clone(),
+    }
+  }
+}
+
+impl<T> fmt::Debug for Recorder<T> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("Recorder").finish_non_exhaustive()
+  }
+}
+
+impl<T: Transport> Recorder<T> {
+  /// Creates a recorder that sends requests with `transport`.
+  pub fn new(transport: T) -> Self {
+    Self {
+      transport: Arc::new(transport),
+      cassette: Arc::default(),
+    }
+  }
+
+  /// Returns a copy of everything recorded so far.
+  pub fn cassette(&self) -> Cassette {
+    self
+      .cassette
+      .lock()
+      .unwrap_or_else(|e| e.into_inner())
+      .clone()
+  }
+}
+
+impl<T: Transport> Transport for Recorder<T> {
+  fn send(&self, request: Request<Vec<u8>>) -> TransportFuture<'_> {
+    let recorded = RecordedRequest::record(&request);
+
+    Box::pin(async move {
+      let response = self.transport.send(request).await?;
+
+      self
+        .cassette
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .interactions
+        .push(Interaction {
+          request: recorded,
+          response: RecordedResponse::record(&response),
+        });
+
+      Ok(response)
+    })
+  }
+}
+
+/// The error a [`Replayer`] fails with when the cassette has no response for a request. It is
+/// returned as a [`ClientError::Transport`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnrecordedRequest {
+  /// The HTTP method of the request.
+  pub method: Method,
+  /// The full URL of the request.
+  pub uri: String,
+}
+
+impl fmt::Display for UnrecordedRequest {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "no unused interaction in the cassette matches {} {}",
+      self.method, self.uri
+    )
+  }
+}
+
+impl Error for UnrecordedRequest {}
+
+/// A [`Transport`] that answers requests from a [`Cassette`] without touching the network.
+///
+/// A request matches an interaction with the same method, URL and body, ignoring headers and
+/// with secrets redacted the same way as when recording. Each interaction is only replayed
+/// once, in the order they were recorded, so repeated requests get the responses the
+/// instance gave at the time. Requests without a match fail with [`UnrecordedRequest`].
+///
+/// ```no_run
+/// use lemmy_client::{
+///   ClientOptions,
+///   LemmyClient,
+///   testing::{Cassette, Replayer},
+/// };
+///
+/// async fn replay() {
+///   let cassette = Cassette::load("tests/cassettes/get_site.json").unwrap();
+///   let client = LemmyClient::with_transport(
+///     ClientOptions::new("lemmy.ml"),
+///     Replayer::new(cassette),
+///   );
+///
+///   client.get_site().await.unwrap();
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Replayer {
+  interactions: Mutex<Vec<Option<Interaction>>>,
+}
+
+impl Replayer {
+  /// Creates a replayer that answers with the interactions of `cassette`.
+  pub fn new(cassette: Cassette) -> Self {
+    Self {
+      interactions: Mutex::new(cassette.interactions.into_iter().map(Some).collect()),
+    }
+  }
+
+  /// Returns the number of interactions that haven't been replayed yet.
+  pub fn remaining(&self) -> usize {
+    self
+      .interactions
+      .lock()
+      .unwrap_or_else(|e| e.into_inner())
+      .iter()
+      .flatten()
+      .count()
+  }
+
+  fn replay(&self, request: &Request<Vec<u8>>) -> Result<Response<Bytes>, ClientError> {
+    let recorded = RecordedRequest::record(request);
+    let mut interactions = self.interactions.lock().unwrap_or_else(|e| e.into_inner());
+
+    interactions
+      .iter_mut()
+      .find(|interaction| {
+        interaction
+          .as_ref()
+          .is_some_and(|interaction| interaction.request.matches(&recorded))
+      })
+      .and_then(Option::take)
+      .ok_or_else(|| {
+        ClientError::Transport(Box::new(UnrecordedRequest {
+          method: request.method().clone(),
+          uri: record_uri(request.uri()),
+        }))
+      })?
+      .response
+      .replay()
+  }
+}
+
+impl Transport for Replayer {
+  fn send(&self, request: Request<Vec<u8>>) -> TransportFuture<'_> {
+    let response = self.replay(&request);
+
+    Box::pin(async move { response })
+  }
+}
+
+impl From<Cassette> for Replayer {
+  fn from(cassette: Cassette) -> Self {
+    Self::new(cassette)
+  }
+}
@@ -0,0 +1,986 @@
+//! The in-memory data model behind [`MockServer`][crate::testing::MockServer].
+//!
+//! Responses are rendered straight to JSON in the shape Lemmy's API uses, so the client
+//! deserializes them exactly like it would a real instance's.
+
+use http::{Method, StatusCode};
+use lemmy_api_common::{
+  comment::{
+    GetComment,
+    GetComments,
+    actions::{CreateComment, CreateCommentLike},
+  },
+  community::{
+    GetCommunity,
+    ListCommunities,
+    actions::{CreateCommunity, FollowCommunity},
+  },
+  error::LemmyErrorType,
+  notification::ListNotifications,
+  person::GetPersonDetails,
+  post::{
+    GetPost,
+    GetPosts,
+    actions::{CreatePost, CreatePostLike, DeletePost, EditPost},
+  },
+};
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::{Value, json};
+use std::collections::{HashMap, HashSet};
+
+/// Timestamp used for everything created on the mock instance, to keep responses
+/// deterministic.
+const TIMESTAMP: &str = "2024-01-01T00:00:00Z";
+
+/// Page size used when a list request doesn't specify a limit.
+const DEFAULT_LIMIT: usize = 20;
+
+type HandlerResult = Result<Value, LemmyErrorType>;
+
+struct Person {
+  id: i32,
+  name: String,
+  password: String,
+  admin: bool,
+}
+
+struct Community {
+  id: i32,
+  name: String,
+  title: String,
+  followers: HashSet<i32>,
+}
+
+struct Post {
+  id: i32,
+  name: String,
+  body: Option<String>,
+  url: Option<String>,
+  creator_id: i32,
+  community_id: i32,
+  deleted: bool,
+  votes: HashMap<i32, bool>,
+}
+
+struct Comment {
+  id: i32,
+  content: String,
+  post_id: i32,
+  path: String,
+  creator_id: i32,
+  votes: HashMap<i32, bool>,
+}
+
+struct Notification {
+  id: i32,
+  recipient_id: i32,
+  creator_id: i32,
+  comment_id: i32,
+  read: bool,
+}
+
+/// Tallies a vote map into `(score, upvotes, downvotes)`.
+fn tally(votes: &HashMap<i32, bool>) -> (i32, i32, i32) {
+  let upvotes = votes.values().filter(|up| **up).count() as i32;
+  let downvotes = votes.len() as i32 - upvotes;
+
+  (upvotes - downvotes, upvotes, downvotes)
+}
+
+/// Parses a page cursor created by [`paginate`].
+fn page_offset(cursor: Option<&impl Serialize>) -> usize {
+  cursor
+    .and_then(|cursor| serde_json::to_value(cursor).ok())
+    .and_then(|cursor| cursor.as_str()?.parse().ok())
+    .unwrap_or_default()
+}
+
+/// Renders one page of `items` as a `PagedResponse`, using offsets as cursors.
+fn paginate(items: Vec<Value>, cursor: Option<&impl Serialize>, limit: Option<i64>) -> Value {
+  let offset = page_offset(cursor);
+  let limit = limit
+    .and_then(|limit| usize::try_from(limit).ok())
+    .filter(|limit| *limit > 0)
+    .unwrap_or(DEFAULT_LIMIT);
+  let end = (offset + limit).min(items.len());
+  let next_page = (end < items.len()).then(|| end.to_string());
+  let prev_page = (offset > 0).then(|| offset.saturating_sub(limit).to_string());
+
+  json!({
+    "items": items.get(offset..end).unwrap_or_default(),
+    "next_page": next_page,
+    "prev_page": prev_page,
+  })
+}
+
+fn parse_query<T: DeserializeOwned>(query: &str) -> Result<T, LemmyErrorType> {
+  serde_urlencoded::from_str(query).map_err(|e| LemmyErrorType::Unknown(e.to_string()))
+}
+
+fn parse_json<T: DeserializeOwned>(body: &[u8]) -> Result<T, LemmyErrorType> {
+  serde_json::from_slice(body).map_err(|e| LemmyErrorType::Unknown(e.to_string()))
+}
+
+fn success() -> Value {
+  json!({ "success": true })
+}
+
+/// The state of a mock Lemmy instance.
+pub(crate) struct Instance {
+  domain: String,
+  version: String,
+  next_id: i32,
+  persons: Vec<Person>,
+  communities: Vec<Community>,
+  posts: Vec<Post>,
+  comments: Vec<Comment>,
+  notifications: Vec<Notification>,
+  sessions: HashMap<String, i32>,
+}
+
+impl Instance {
+  pub fn new(domain: String) -> Self {
+    Self {
+      domain,
+      version: format!("{}-mock", env!("CARGO_PKG_VERSION")),
+      next_id: 1,
+      persons: Vec::new(),
+      communities: Vec::new(),
+      posts: Vec::new(),
+      comments: Vec::new(),
+      notifications: Vec::new(),
+      sessions: HashMap::new(),
+    }
+  }
+
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn set_domain(&mut self, domain: String) {
+    self.domain = domain;
+  }
+
+  pub fn set_version(&mut self, version: String) {
+    self.version = version;
+  }
+
+  fn next_id(&mut self) -> i32 {
+    let id = self.next_id;
+    self.next_id += 1;
+
+    id
+  }
+
+  /// Handles a request to `/api/v4/{route}`, returning the status and JSON body of the
+  /// response.
+  pub fn handle(
+    &mut self,
+    method: &Method,
+    route: &str,
+    query: &str,
+    jwt: Option<&str>,
+    body: &[u8],
+  ) -> (StatusCode, Value) {
+    let viewer = jwt.and_then(|jwt| self.sessions.get(jwt).copied());
+
+    match self.route(method, route, query, jwt, viewer, body) {
+      Ok(value) => (StatusCode::OK, value),
+      Err(error) => {
+        let status = match error {
+          LemmyErrorType::NotFound => StatusCode::NOT_FOUND,
+          LemmyErrorType::NotLoggedIn | LemmyErrorType::IncorrectLogin => StatusCode::UNAUTHORIZED,
+          _ => StatusCode::BAD_REQUEST,
+        };
+
+        (status, serde_json::to_value(error).unwrap_or_default())
+      }
+    }
+  }
+
+  fn route(
+    &mut self,
+    method: &Method,
+    route: &str,
+    query: &str,
+    jwt: Option<&str>,
+    viewer: Option<i32>,
+    body: &[u8],
+  ) -> HandlerResult {
+    let logged_in = || viewer.ok_or(LemmyErrorType::NotLoggedIn);
+
+    match (method.as_str(), route) {
+      ("GET", "site") => Ok(self.site()),
+      ("POST", "account/auth/register") => {
+        let form: lemmy_api_common::account::auth::Register = parse_json(body)?;
+        self.register(form.username, form.password.into_inner())
+      }
+      ("POST", "account/auth/login") => {
+        let form: lemmy_api_common::account::auth::Login = parse_json(body)?;
+        self.login(
+          &form.username_or_email.into_inner(),
+          &form.password.into_inner(),
+        )
+      }
+      ("POST", "account/auth/logout") => {
+        logged_in()?;
+        if let Some(jwt) = jwt {
+          self.sessions.remove(jwt);
+        }
+        Ok(success())
+      }
+      ("GET", "account/validate_auth") => logged_in().map(|_| success()),
+      ("GET", "account") => Ok(self.my_user(logged_in()?)),
+      ("GET", "account/unread_counts") => {
+        let viewer = logged_in()?;
+        let unread = self
+          .notifications
+          .iter()
+          .filter(|n| n.recipient_id == viewer && !n.read)
+          .count();
+
+        Ok(json!({ "notification_count": unread }))
+      }
+      ("GET", "account/notification/list") => {
+        let viewer = logged_in()?;
+        let form: ListNotifications = parse_query(query)?;
+        let items = self
+          .notifications
+          .iter()
+          .rev()
+          .filter(|n| n.recipient_id == viewer && !(form.unread_only == Some(true) && n.read))
+          .filter_map(|n| self.notification_view(n, viewer))
+          .collect();
+
+        Ok(paginate(items, form.page_cursor.as_ref(), form.limit))
+      }
+      ("POST", "account/notification/mark_as_read/all") => {
+        let viewer = logged_in()?;
+        for notification in self
+          .notifications
+          .iter_mut()
+          .filter(|n| n.recipient_id == viewer)
+        {
+          notification.read = true;
+        }
+
+        Ok(success())
+      }
+      ("GET", "person") => {
+        let form: GetPersonDetails = parse_query(query)?;
+        let person = self
+          .persons
+          .iter()
+          .find(|p| {
+            form.person_id.is_some_and(|id| id.0 == p.id)
+              || form.username.as_deref() == Some(p.name.as_str())
+          })
+          .ok_or(LemmyErrorType::NotFound)?;
+
+        Ok(json!({
+          "person_view": self.person_view(person),
+          "moderates": [],
+          "multi_communities_created": [],
+        }))
+      }
+      ("POST", "community") => {
+        let viewer = logged_in()?;
+        let form: CreateCommunity = parse_json(body)?;
+        self.create_community(viewer, form)
+      }
+      ("GET", "community") => {
+        let form: GetCommunity = parse_query(query)?;
+        let community = self
+          .communities
+          .iter()
+          .find(|c| {
+            form.id.is_some_and(|id| id.0 == c.id) || form.name.as_deref() == Some(c.name.as_str())
+          })
+          .ok_or(LemmyErrorType::NotFound)?;
+
+        Ok(json!({
+          "community_view": self.community_view(community, viewer),
+          "moderators": [],
+          "discussion_languages": [],
+        }))
+      }
+      ("GET", "community/list") => {
+        let form: ListCommunities = parse_query(query)?;
+        let items = self
+          .communities
+          .iter()
+          .map(|c| self.community_view(c, viewer))
+          .collect();
+
+        Ok(paginate(items, form.page_cursor.as_ref(), form.limit))
+      }
+      ("POST", "community/follow") => {
+        let viewer = logged_in()?;
+        let form: FollowCommunity = parse_json(body)?;
+        let community = self
+          .communities
+          .iter_mut()
+          .find(|c| c.id == form.community_id.0)
+          .ok_or(LemmyErrorType::NotFound)?;
+
+        if form.follow {
+          community.followers.insert(viewer);
+        } else {
+          community.followers.remove(&viewer);
+        }
+
+        Ok(self.community_response(form.community_id.0, Some(viewer)))
+      }
+      ("POST", "post") => {
+        let viewer = logged_in()?;
+        let form: CreatePost = parse_json(body)?;
+        self.create_post(viewer, form)
+      }
+      ("GET", "post") => {
+        let form: GetPost = parse_query(query)?;
+        let post = self
+          .posts
+          .iter()
+          .find(|p| form.id.is_some_and(|id| id.0 == p.id))
+          .ok_or(LemmyErrorType::NotFound)?;
+        let community = self
+          .communities
+          .iter()
+          .find(|c| c.id == post.community_id)
+          .ok_or(LemmyErrorType::NotFound)?;
+
+        Ok(json!({
+          "post_view": self.post_view(post, viewer),
+          "community_view": self.community_view(community, viewer),
+          "cross_posts": [],
+        }))
+      }
+      ("PUT", "post") => {
+        let viewer = logged_in()?;
+        let form: EditPost = parse_json(body)?;
+        let post = self.own_post(form.post_id.0, viewer)?;
+
+        if let Some(name) = form.name {
+          post.name = name;
+        }
+        if form.body.is_some() {
+          post.body = form.body;
+        }
+        if form.url.is_some() {
+          post.url = form.url;
+        }
+
+        Ok(self.post_response(form.post_id.0, Some(viewer)))
+      }
+      ("POST", "post/delete") => {
+        let viewer = logged_in()?;
+        let form: DeletePost = parse_json(body)?;
+        self.own_post(form.post_id.0, viewer)?.deleted = form.deleted;
+
+        Ok(self.post_response(form.post_id.0, Some(viewer)))
+      }
+      ("GET", "post/list") => {
+        let form: GetPosts = parse_query(query)?;
+        let items = self
+          .posts
+          .iter()
+          .rev()
+          .filter(|p| !p.deleted)
+          .filter(|p| form.community_id.is_none_or(|id| id.0 == p.community_id))
+          .filter(|p| {
+            form.community_name.as_deref().is_none_or(|name| {
+              self
+                .communities
+                .iter()
+                .any(|c| c.id == p.community_id && c.name == name)
+            })
+          })
+          .map(|p| self.post_view(p, viewer))
+          .collect();
+
+        Ok(paginate(items, form.page_cursor.as_ref(), form.limit))
+      }
+      ("POST", "post/like") => {
+        let viewer = logged_in()?;
+        let form: CreatePostLike = parse_json(body)?;
+        let post = self
+          .posts
+          .iter_mut()
+          .find(|p| p.id == form.post_id.0)
+          .ok_or(LemmyErrorType::NotFound)?;
+
+        match form.is_upvote {
+          Some(up) => post.votes.insert(viewer, up),
+          None => post.votes.remove(&viewer),
+        };
+
+        Ok(self.post_response(form.post_id.0, Some(viewer)))
+      }
+      ("POST", "comment") => {
+        let viewer = logged_in()?;
+        let form: CreateComment = parse_json(body)?;
+        self.create_comment(viewer, form)
+      }
+      ("GET", "comment") => {
+        let form: GetComment = parse_query(query)?;
+        Ok(self.comment_response(form.id.0, viewer))
+      }
+      ("GET", "comment/list") => {
+        let form: GetComments = parse_query(query)?;
+        let items = self
+          .comments
+          .iter()
+          .filter(|c| form.post_id.is_none_or(|id| id.0 == c.post_id))
+          .filter_map(|c| self.comment_view(c, viewer))
+          .collect();
+
+        Ok(paginate(items, form.page_cursor.as_ref(), form.limit))
+      }
+      ("POST", "comment/like") => {
+        let viewer = logged_in()?;
+        let form: CreateCommentLike = parse_json(body)?;
+        let comment = self
+          .comments
+          .iter_mut()
+          .find(|c| c.id == form.comment_id.0)
+          .ok_or(LemmyErrorType::NotFound)?;
+
+        match form.is_upvote {
+          Some(up) => comment.votes.insert(viewer, up),
+          None => comment.votes.remove(&viewer),
+        };
+
+        Ok(self.comment_response(form.comment_id.0, Some(viewer)))
+      }
+      _ => Err(LemmyErrorType::NotFound),
+    }
+  }
+
+  fn register(&mut self, username: String, password: String) -> HandlerResult {
+    if self.persons.iter().any(|p| p.name == username) {
+      return Err(LemmyErrorType::UsernameAlreadyTaken);
+    }
+
+    let id = self.next_id();
+    // Like on a real instance, the first account becomes the admin.
+    let admin = self.persons.is_empty();
+    self.persons.push(Person {
+      id,
+      name: username,
+      password,
+      admin,
+    });
+
+    Ok(self.new_session(id))
+  }
+
+  fn login(&mut self, username: &str, password: &str) -> HandlerResult {
+    let id = self
+      .persons
+      .iter()
+      .find(|p| p.name == username && p.password == password)
+      .map(|p| p.id)
+      .ok_or(LemmyErrorType::IncorrectLogin)?;
+
+    Ok(self.new_session(id))
+  }
+
+  fn new_session(&mut self, person_id: i32) -> Value {
+    let jwt = format!("mock-jwt-{}-{}", person_id, self.next_id());
+    self.sessions.insert(jwt.clone(), person_id);
+
+    json!({
+      "jwt": jwt,
+      "registration_created": false,
+      "verify_email_sent": false,
+    })
+  }
+
+  fn create_community(&mut self, creator_id: i32, form: CreateCommunity) -> HandlerResult {
+    if self.communities.iter().any(|c| c.name == form.name) {
+      return Err(LemmyErrorType::AlreadyExists);
+    }
+
+    let id = self.next_id();
+    self.communities.push(Community {
+      id,
+      name: form.name,
+      title: form.title,
+      followers: HashSet::from([creator_id]),
+    });
+
+    Ok(self.community_response(id, Some(creator_id)))
+  }
+
+  fn create_post(&mut self, creator_id: i32, form: CreatePost) -> HandlerResult {
+    if !self.communities.iter().any(|c| c.id == form.community_id.0) {
+      return Err(LemmyErrorType::NotFound);
+    }
+
+    let id = self.next_id();
+    self.posts.push(Post {
+      id,
+      name: form.name,
+      body: form.body,
+      url: form.url,
+      creator_id,
+      community_id: form.community_id.0,
+      deleted: false,
+      // Lemmy upvotes your own content automatically.
+      votes: HashMap::from([(creator_id, true)]),
+    });
+
+    Ok(self.post_response(id, Some(creator_id)))
+  }
+
+  fn create_comment(&mut self, creator_id: i32, form: CreateComment) -> HandlerResult {
+    let post_creator = self
+      .posts
+      .iter()
+      .find(|p| p.id == form.post_id.0)
+      .map(|p| p.creator_id)
+      .ok_or(LemmyErrorType::NotFound)?;
+    let (parent_path, recipient_id) = match form.parent_id {
+      Some(parent_id) => self
+        .comments
+        .iter()
+        .find(|c| c.id == parent_id.0)
+        .map(|c| (c.path.clone(), c.creator_id))
+        .ok_or(LemmyErrorType::NotFound)?,
+      None => ("0".to_owned(), post_creator),
+    };
+
+    let id = self.next_id();
+    self.comments.push(Comment {
+      id,
+      content: form.content,
+      post_id: form.post_id.0,
+      path: format!("{parent_path}.{id}"),
+      creator_id,
+      votes: HashMap::from([(creator_id, true)]),
+    });
+
+    if recipient_id != creator_id {
+      let notification_id = self.next_id();
+      self.notifications.push(Notification {
+        id: notification_id,
+        recipient_id,
+        creator_id,
+        comment_id: id,
+        read: false,
+      });
+    }
+
+    Ok(self.comment_response(id, Some(creator_id)))
+  }
+
+  /// Looks up a post that `viewer` is allowed to change.
+  fn own_post(&mut self, post_id: i32, viewer: i32) -> Result<&mut Post, LemmyErrorType> {
+    let post = self
+      .posts
+      .iter_mut()
+      .find(|p| p.id == post_id)
+      .ok_or(LemmyErrorType::NotFound)?;
+
+    if post.creator_id == viewer {
+      Ok(post)
+    } else {
+      Err(LemmyErrorType::NoPostEditAllowed)
+    }
+  }
+
+  fn ap_id(&self, kind: &str, name: impl std::fmt::Display) -> String {
+    format!("http://{}/{kind}/{name}", self.domain)
+  }
+
+  fn site(&self) -> Value {
+    let limits = json!({
+      "local_site_id": 1,
+      "message_max_requests": 180,
+      "message_interval_seconds": 60,
+      "post_max_requests": 6,
+      "post_interval_seconds": 600,
+      "register_max_requests": 10,
+      "register_interval_seconds": 3600,
+      "image_max_requests": 6,
+      "image_interval_seconds": 3600,
+      "comment_max_requests": 6,
+      "comment_interval_seconds": 600,
+      "search_max_requests": 60,
+      "search_interval_seconds": 600,
+      "import_user_settings_max_requests": 1,
+      "import_user_settings_interval_seconds": 86400,
+      "published_at": TIMESTAMP,
+    });
+
+    json!({
+      "site_view": {
+        "site": {
+          "id": 1,
+          "name": "Mock Lemmy",
+          "published_at": TIMESTAMP,
+          "ap_id": format!("http://{}/", self.domain),
+          "last_refreshed_at": TIMESTAMP,
+          "inbox_url": format!("http://{}/inbox", self.domain),
+          "instance_id": 1,
+        },
+        "local_site": self.local_site(),
+        "local_site_rate_limit": limits,
+        "instance": self.instance(),
+      },
+      "admins": self
+        .persons
+        .iter()
+        .filter(|p| p.admin)
+        .map(|p| self.person_view(p))
+        .collect::<Vec<_>>(),
+      "version": self.version,
+      "all_languages": [],
+      "discussion_languages": [],
+      "oauth_providers": [],
+      "admin_oauth_providers": [],
+      "blocked_urls": [],
+      "active_plugins": [],
+      "captcha_enabled": false,
+    })
+  }
+
+  fn local_site(&self) -> Value {
+    let mut local_site = json!({
+      "id": 1,
+      "site_id": 1,
+      "site_setup": true,
+      "community_creation_admin_only": false,
+      "email_verification_required": false,
+      "application_email_admins": false,
+      "private_instance": false,
+      "default_theme": "browser",
+      "default_post_listing_type": "all",
+      "default_post_listing_mode": "list",
+      "default_post_sort_type": "active",
+      "default_comment_sort_type": "hot",
+      "default_items_per_page": DEFAULT_LIMIT,
+      "federation_enabled": false,
+      "federation_signed_fetch": false,
+      "reports_email_admins": false,
+      "registration_mode": "open",
+      "oauth_registration": false,
+      "email_notifications_disabled": false,
+      "nsfw_content_disallowed": false,
+      "post_upvotes": "all",
+      "post_downvotes": "all",
+      "comment_upvotes": "all",
+      "comment_downvotes": "all",
+      "published_at": TIMESTAMP,
+    });
+    let images = json!({
+      "image_mode": "none",
+      "image_upload_disabled": true,
+      "image_allow_video_uploads": false,
+      "image_max_avatar_size": 0,
+      "image_max_banner_size": 0,
+      "image_max_thumbnail_size": 0,
+      "image_max_upload_size": 0,
+      "image_upload_timeout_seconds": 0,
+      "max_invites_per_user_allowed": 0,
+    });
+    let stats = json!({
+      "users": self.persons.len(),
+      "posts": self.posts.len(),
+      "comments": self.comments.len(),
+      "communities": self.communities.len(),
+      "users_active_day": 0,
+      "users_active_week": 0,
+      "users_active_month": 0,
+      "users_active_half_year": 0,
+    });
+
+    // Split up to stay below `json!`'s recursion limit.
+    for part in [images, stats] {
+      if let (Value::Object(local_site), Value::Object(part)) = (&mut local_site, part) {
+        local_site.extend(part);
+      }
+    }
+
+    local_site
+  }
+
+  fn instance(&self) -> Value {
+    json!({
+      "id": 1,
+      "domain": self.domain,
+      "published_at": TIMESTAMP,
+    })
+  }
+
+  fn person(&self, id: i32) -> Value {
+    let Some(person) = self.persons.iter().find(|p| p.id == id) else {
+      return Value::Null;
+    };
+
+    json!({
+      "id": person.id,
+      "name": person.name,
+      "published_at": TIMESTAMP,
+      "ap_id": self.ap_id("u", &person.name),
+      "local": true,
+      "last_refreshed_at": TIMESTAMP,
+      "deleted": false,
+      "bot_account": false,
+      "instance_id": 1,
+      "post_count": self.posts.iter().filter(|p| p.creator_id == id).count(),
+      "comment_count": self.comments.iter().filter(|c| c.creator_id == id).count(),
+    })
+  }
+
+  fn person_view(&self, person: &Person) -> Value {
+    json!({
+      "person": self.person(person.id),
+      "is_admin": person.admin,
+      "banned": false,
+    })
+  }
+
+  fn my_user(&self, viewer: i32) -> Value {
+    json!({
+      "local_user_view": {
+        "local_user": {},
+        "person": self.person(viewer),
+        "banned": false,
+      },
+      "follows": [],
+      "moderates": [],
+      "community_blocks": [],
+      "instance_communities_blocks": [],
+      "instance_persons_blocks": [],
+      "person_blocks": [],
+      "keyword_blocks": [],
+      "discussion_languages": [],
+      "multi_community_follows": [],
+    })
+  }
+
+  fn community(&self, id: i32) -> Value {
+    let Some(community) = self.communities.iter().find(|c| c.id == id) else {
+      return Value::Null;
+    };
+    let posts = self.posts.iter().filter(|p| p.community_id == id);
+
+    json!({
+      "id": community.id,
+      "name": community.name,
+      "title": community.title,
+      "removed": false,
+      "published_at": TIMESTAMP,
+      "deleted": false,
+      "nsfw": false,
+      "ap_id": self.ap_id("c", &community.name),
+      "local": true,
+      "last_refreshed_at": TIMESTAMP,
+      "posting_restricted_to_mods": false,
+      "instance_id": 1,
+      "visibility": "public",
+      "subscribers": community.followers.len(),
+      "subscribers_local": community.followers.len(),
+      "posts": posts.clone().count(),
+      "comments": self
+        .comments
+        .iter()
+        .filter(|c| posts.clone().any(|p| p.id == c.post_id))
+        .count(),
+      "users_active_day": 0,
+      "users_active_week": 0,
+      "users_active_month": 0,
+      "users_active_half_year": 0,
+      "report_count": 0,
+      "unresolved_report_count": 0,
+      "local_removed": false,
+    })
+  }
+
+  fn community_actions(&self, community: &Community, viewer: Option<i32>) -> Value {
+    match viewer {
+      Some(viewer) if community.followers.contains(&viewer) => json!({
+        "person_id": viewer,
+        "community_id": community.id,
+        "followed_at": TIMESTAMP,
+        "follow_state": "accepted",
+      }),
+      _ => Value::Null,
+    }
+  }
+
+  fn community_view(&self, community: &Community, viewer: Option<i32>) -> Value {
+    json!({
+      "community": self.community(community.id),
+      "community_actions": self.community_actions(community, viewer),
+      "can_mod": false,
+      "tags": [],
+    })
+  }
+
+  fn community_response(&self, id: i32, viewer: Option<i32>) -> Value {
+    let community_view = self
+      .communities
+      .iter()
+      .find(|c| c.id == id)
+      .map(|c| self.community_view(c, viewer));
+
+    json!({
+      "community_view": community_view,
+      "discussion_languages": [],
+    })
+  }
+
+  fn post(&self, post: &Post) -> Value {
+    let (score, upvotes, downvotes) = tally(&post.votes);
+
+    json!({
+      "id": post.id,
+      "name": post.name,
+      "url": post.url,
+      "body": post.body,
+      "creator_id": post.creator_id,
+      "community_id": post.community_id,
+      "removed": false,
+      "locked": false,
+      "published_at": TIMESTAMP,
+      "deleted": post.deleted,
+      "nsfw": false,
+      "ap_id": self.ap_id("post", post.id),
+      "local": true,
+      "language_id": 0,
+      "featured_community": false,
+      "featured_local": false,
+      "comments": self.comments.iter().filter(|c| c.post_id == post.id).count(),
+      "score": score,
+      "upvotes": upvotes,
+      "downvotes": downvotes,
+      "report_count": 0,
+      "unresolved_report_count": 0,
+      "federation_pending": false,
+    })
+  }
+
+  fn post_view(&self, post: &Post, viewer: Option<i32>) -> Value {
+    let community = self.communities.iter().find(|c| c.id == post.community_id);
+    let post_actions = viewer
+      .and_then(|viewer| Some((viewer, post.votes.get(&viewer)?)))
+      .map(|(viewer, up)| {
+        json!({
+          "person_id": viewer,
+          "post_id": post.id,
+          "voted_at": TIMESTAMP,
+          "vote_is_upvote": up,
+        })
+      });
+
+    json!({
+      "post": self.post(post),
+      "creator": self.person(post.creator_id),
+      "community": self.community(post.community_id),
+      "community_actions": community.map(|c| self.community_actions(c, viewer)),
+      "post_actions": post_actions,
+      "creator_is_admin": self.persons.iter().any(|p| p.id == post.creator_id && p.admin),
+      "tags": [],
+      "can_mod": false,
+      "creator_banned": false,
+      "creator_banned_from_community": false,
+      "creator_is_moderator": false,
+    })
+  }
+
+  fn post_response(&self, id: i32, viewer: Option<i32>) -> Value {
+    let post_view = self
+      .posts
+      .iter()
+      .find(|p| p.id == id)
+      .map(|p| self.post_view(p, viewer));
+
+    json!({ "post_view": post_view })
+  }
+
+  fn comment_view(&self, comment: &Comment, viewer: Option<i32>) -> Option<Value> {
+    let post = self.posts.iter().find(|p| p.id == comment.post_id)?;
+    let (score, upvotes, downvotes) = tally(&comment.votes);
+    let comment_actions = viewer
+      .and_then(|viewer| Some((viewer, comment.votes.get(&viewer)?)))
+      .map(|(viewer, up)| {
+        json!({
+          "person_id": viewer,
+          "comment_id": comment.id,
+          "voted_at": TIMESTAMP,
+          "vote_is_upvote": up,
+        })
+      });
+    let child_count = self
+      .comments
+      .iter()
+      .filter(|c| c.path.starts_with(&format!("{}.", comment.path)))
+      .count();
+
+    Some(json!({
+      "comment": {
+        "id": comment.id,
+        "creator_id": comment.creator_id,
+        "post_id": comment.post_id,
+        "content": comment.content,
+        "removed": false,
+        "published_at": TIMESTAMP,
+        "deleted": false,
+        "ap_id": self.ap_id("comment", comment.id),
+        "local": true,
+        "path": comment.path,
+        "distinguished": false,
+        "language_id": 0,
+        "score": score,
+        "upvotes": upvotes,
+        "downvotes": downvotes,
+        "child_count": child_count,
+        "report_count": 0,
+        "unresolved_report_count": 0,
+        "federation_pending": false,
+        "locked": false,
+      },
+      "creator": self.person(comment.creator_id),
+      "post": self.post(post),
+      "community": self.community(post.community_id),
+      "comment_actions": comment_actions,
+      "creator_is_admin": self.persons.iter().any(|p| p.id == comment.creator_id && p.admin),
+      "tags": [],
+      "can_mod": false,
+      "creator_banned": false,
+      "creator_banned_from_community": false,
+      "creator_is_moderator": false,
+    }))
+  }
+
+  fn comment_response(&self, id: i32, viewer: Option<i32>) -> Value {
+    let comment_view = self
+      .comments
+      .iter()
+      .find(|c| c.id == id)
+      .and_then(|c| self.comment_view(c, viewer));
+
+    json!({ "comment_view": comment_view })
+  }
+
+  fn notification_view(&self, notification: &Notification, viewer: i32) -> Option<Value> {
+    let comment = self
+      .comments
+      .iter()
+      .find(|c| c.id == notification.comment_id)?;
+    let mut data = self.comment_view(comment, Some(viewer))?;
+    data["type_"] = json!("comment");
+
+    Some(json!({
+      "notification": {
+        "id": notification.id,
+        "recipient_id": notification.recipient_id,
+        "comment_id": notification.comment_id,
+        "read": notification.read,
+        "published_at": TIMESTAMP,
+        "kind": "reply",
+        "creator_id": notification.creator_id,
+      },
+      "data": data,
+    }))
+  }
+}
@@ -0,0 +1,274 @@
+//! A minimal HTTP/1.1 server exposing a [`MockServer`] on localhost.
+
+use super::MockServer;
+use crate::ClientOptions;
+use bytes::Bytes;
+use http::{HeaderMap, HeaderName, HeaderValue, Method, Request, Response, StatusCode, header};
+use std::{
+  io::{self, BufRead, BufReader, Write},
+  net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+  sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+  },
+  thread,
+  time::Duration,
+};
+
+/// Header holding a number of milliseconds to wait before handling a request.
+const DELAY_HEADER: &str = "x-mock-delay";
+
+/// A [`MockServer`] being served over HTTP on localhost, started with [`MockServer::start`].
+///
+/// The server shuts down when this is dropped.
+#[derive(Debug)]
+pub struct RunningMockServer {
+  server: MockServer,
+  addr: SocketAddr,
+  stopped: Arc<AtomicBool>,
+}
+
+impl MockServer {
+  /// Serves the instance over plain HTTP on a random port of `127.0.0.1`, so that tests can go
+  /// through a real HTTP client.
+  ///
+  /// Each connection is handled on its own thread, so the server works with any async runtime.
+  ///
+  /// The server answers CORS preflights and allows credentialed requests from any origin. This
+  /// lets it act as the stub instance for `wasm-bindgen-test` tests of the `wasm` feature: start
+  /// it from a native process with [`start_on`][Self::start_on] and point the browser tests' client
+  /// at its address.
+  ///
+  /// A request with an `x-mock-delay` header is handled after waiting that many milliseconds, and
+  /// not at all if the client closed the connection in the meantime, so that tests can tell
+  /// whether a request was aborted.
+  ///
+  /// ```
+  /// use lemmy_client::testing::MockServer;
+  ///
+  /// async fn get_site() {
+  ///   let server = MockServer::new().start().unwrap();
+  ///   let site = server.client().get_site().await.unwrap();
+  ///
+  ///   assert!(site.admins.is_empty());
+  /// }
+  /// ```
+  pub fn start(self) -> io::Result<RunningMockServer> {
+    self.start_on("127.0.0.1:0")
+  }
+
+  /// Serves the instance over plain HTTP on the given address, like [`start`][Self::start] does
+  /// on a random port.
+  pub fn start_on(self, addr: impl ToSocketAddrs) -> io::Result<RunningMockServer> {
+    let listener = TcpListener::bind(addr)?;
+    let addr = listener.local_addr()?;
+    let stopped = Arc::new(AtomicBool::new(false));
+
+    self
+      .instance
+      .lock()
+      .unwrap_or_else(|e| e.into_inner())
+      .set_domain(addr.to_string());
+
+    let server = self.clone();
+    let accept_stopped = stopped.clone();
+    thread::Builder::new()
+      .name(format!("lemmy-mock-server-{addr}"))
+      .spawn(move || {
+        for stream in listener.incoming() {
+          if accept_stopped.load(Ordering::Acquire) {
+            break;
+          }
+
+          if let Ok(stream) = stream {
+            let server = server.clone();
+            thread::spawn(move || serve_connection(&server, stream));
+          }
+        }
+      })?;
+
+    Ok(RunningMockServer {
+      server: self,
+      addr,
+      stopped,
+    })
+  }
+}
+
+impl RunningMockServer {
+  /// The address the server listens on.
+  pub fn addr(&self) -> SocketAddr {
+    self.addr
+  }
+
+  /// The instance being served. Requests made through it bypass HTTP but see the same data.
+  pub fn server(&self) -> &MockServer {
+    &self.server
+  }
+
+  /// Options for a client talking to the server over HTTP.
+  pub fn options(&self) -> ClientOptions<String> {
+    ClientOptions {
+      secure: false,
+      ..ClientOptions::new(self.addr.to_string())
+    }
+  }
+
+  /// Creates a client that talks to the server over HTTP with the default transport.
+  #[cfg(feature = "native")]
+  pub fn client(&self) -> crate::LemmyClient {
+    crate::LemmyClient::new(self.options())
+  }
+}
+
+impl Drop for RunningMockServer {
+  fn drop(&mut self) {
+    self.stopped.store(true, Ordering::Release);
+    // Wake the accept loop up so it notices it should stop.
+    let _ = TcpStream::connect(self.addr);
+  }
+}
+
+/// Serves requests on a connection until the client closes it or sends something unparseable.
+fn serve_connection(server: &MockServer, stream: TcpStream) {
+  let Ok(mut writer) = stream.try_clone() else {
+    return;
+  };
+  let mut reader = BufReader::new(stream);
+
+  while let Ok(Some(request)) = read_request(&mut reader) {
+    if let Some(delay) = request
+      .headers()
+      .get(DELAY_HEADER)
+      .and_then(|value| value.to_str().ok()?.parse().ok())
+    {
+      thread::sleep(Duration::from_millis(delay));
+      if is_closed(&writer) {
+        break;
+      }
+    }
+
+    let mut response = if request.method() == Method::OPTIONS {
+      let mut response = Response::new(Bytes::new());
+      *response.status_mut() = StatusCode::NO_CONTENT;
+      response
+    } else {
+      server.handle(&request)
+    };
+    allow_cors(&request, response.headers_mut());
+
+    let mut head = format!(
+      "HTTP/1.1 {}\r\ncontent-length: {}\r\n",
+      response.status(),
+      response.body().len()
+    );
+    for (name, value) in response.headers() {
+      if let Ok(value) = value.to_str() {
+        head.push_str(&format!("{name}: {value}\r\n"));
+      }
+    }
+    head.push_str("\r\n");
+
+    if writer
+      .write_all(head.as_bytes())
+      .and_then(|()| writer.write_all(response.body()))
+      .is_err()
+    {
+      break;
+    }
+  }
+
+  let _ = writer.shutdown(Shutdown::Both);
+}
+
+/// Whether the client closed the connection, without waiting for it to send anything.
+fn is_closed(stream: &TcpStream) -> bool {
+  if stream.set_nonblocking(true).is_err() {
+    return false;
+  }
+  let closed = matches!(stream.peek(&mut [0]), Ok(0));
+  let _ = stream.set_nonblocking(false);
+
+  closed
+}
+
+/// Lets the browser hand the response to a page of any origin, cookies included.
+fn allow_cors(request: &Request<Vec<u8>>, headers: &mut HeaderMap) {
+  // Credentialed requests don't accept a wildcard origin, so the request's origin is echoed.
+  let origin = request
+    .headers()
+    .get(header::ORIGIN)
+    .cloned()
+    .unwrap_or(HeaderValue::from_static("*"));
+  let allowed_headers = request
+    .headers()
+    .get(header::ACCESS_CONTROL_REQUEST_HEADERS)
+    .cloned()
+    .unwrap_or(HeaderValue::from_static("authorization, content-type"));
+
+  headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+  headers.insert(
+    header::ACCESS_CONTROL_ALLOW_CREDENTIALS,
+    HeaderValue::from_static("true"),
+  );
+  headers.insert(
+    header::ACCESS_CONTROL_ALLOW_METHODS,
+    HeaderValue::from_static("GET, POST, PUT, DELETE"),
+  );
+  headers.insert(header::ACCESS_CONTROL_ALLOW_HEADERS, allowed_headers);
+  headers.insert(header::VARY, HeaderValue::from_static("origin"));
+}
+
+fn invalid_data(message: &str) -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Reads one request, returning `None` if the connection was closed before it started.
+fn read_request(reader: &mut impl BufRead) -> io::Result<Option<Request<Vec<u8>>>> {
+  let mut line = String::new();
+  if reader.read_line(&mut line)? == 0 {
+    return Ok(None);
+  }
+
+  let mut parts = line.split_whitespace();
+  let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+    return Err(invalid_data("malformed request line"));
+  };
+  let mut request = Request::builder()
+    .method(Method::from_bytes(method.as_bytes()).map_err(|_| invalid_data("invalid method"))?)
+    .uri(target);
+
+  let mut content_length = 0;
+  loop {
+    line.clear();
+    reader.read_line(&mut line)?;
+    let field = line.trim_end();
+    if field.is_empty() {
+      break;
+    }
+
+    let Some((name, value)) = field.split_once(':') else {
+      return Err(invalid_data("malformed header"));
+    };
+    let name = HeaderName::from_bytes(name.trim().as_bytes())
+      .map_err(|_| invalid_data("invalid header name"))?;
+    let value = HeaderValue::from_str(value.trim()).map_err(|_| invalid_data("invalid header"))?;
+
+    if name == header::CONTENT_LENGTH {
+      content_length = value
+        .to_str()
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .ok_or_else(|| invalid_data("invalid content length"))?;
+    }
+    request = request.header(name, value);
+  }
+
+  let mut body = vec![0; content_length];
+  reader.read_exact(&mut body)?;
+
+  request
+    .body(body)
+    .map(Some)
+    .map_err(|_| invalid_data("invalid request"))
+}
@@ -0,0 +1,188 @@
+use crate::{ClientError, UploadBody};
+use bytes::Bytes;
+use http::{Request, Response};
+use std::{error::Error, future::Future, pin::Pin};
+
+/// The underlying cause of a [`ClientError::Transport`] error.
+pub type TransportError = Box<dyn Error + Send + Sync>;
+
+/// The future returned by [`Transport::send`].
+///
+/// On native targets the future must be [`Send`] so the client can be used from multithreaded
+/// runtimes. On WASM, where the browser's fetch futures are not [`Send`], this bound is dropped.
+#[cfg(not(target_arch = "wasm32"))]
+pub type TransportFuture<'a> =
+  Pin<Box<dyn Future<Output = Result<Response<Bytes>, ClientError>> + Send + 'a>>;
+
+/// The future returned by [`Transport::send`].
+///
+/// On native targets the future must be [`Send`] so the client can be used from multithreaded
+/// runtimes. On WASM, where the browser's fetch futures are not [`Send`], this bound is dropped.
+#[cfg(target_arch = "wasm32")]
+pub type TransportFuture<'a> =
+  Pin<Box<dyn Future<Output = Result<Response<Bytes>, ClientError>> + 'a>>;
+
+/// Requires [`Send`] and [`Sync`] on native targets and nothing on WASM, where the browser's
+/// types are neither.
+///
+/// This is the supertrait of [`Transport`], [`Middleware`][crate::Middleware],
+/// [`CacheStore`][crate::CacheStore], [`CredentialStore`][crate::CredentialStore] and
+/// [`MetricsRecorder`][crate::MetricsRecorder], so that their implementations can be shared
+/// between threads on native targets. It is implemented for every type that qualifies, so there
+/// is no need to implement it yourself.
+#[cfg(not(target_arch = "wasm32"))]
+pub trait MaybeSendSync: Send + Sync {}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<T: Send + Sync + ?Sized> MaybeSendSync for T {}
+
+/// Requires [`Send`] and [`Sync`] on native targets and nothing on WASM, where the browser's
+/// types are neither.
+///
+/// This is the supertrait of [`Transport`], [`Middleware`][crate::Middleware],
+/// [`CacheStore`][crate::CacheStore], [`CredentialStore`][crate::CredentialStore] and
+/// [`MetricsRecorder`][crate::MetricsRecorder], so that their implementations can be shared
+/// between threads on native targets. It is implemented for every type that qualifies, so there
+/// is no need to implement it yourself.
+#[cfg(target_arch = "wasm32")]
+pub trait MaybeSendSync {}
+
+#[cfg(target_arch = "wasm32")]
+impl<T: ?Sized> MaybeSendSync for T {}
+
+/// Requires [`Send`] on native targets and nothing on WASM, where the browser's types aren't.
+///
+/// Bounds the streams, readers and callbacks an [`UploadBody`][crate::UploadBody] is built from.
+/// Like [`MaybeSendSync`], it is implemented for every type that qualifies.
+#[cfg(not(target_arch = "wasm32"))]
+pub trait MaybeSend: Send {}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<T: Send + ?Sized> MaybeSend for T {}
+
+/// Requires [`Send`] on native targets and nothing on WASM, where the browser's types aren't.
+///
+/// Bounds the streams, readers and callbacks an [`UploadBody`][crate::UploadBody] is built from.
+/// Like [`MaybeSendSync`], it is implemented for every type that qualifies.
+#[cfg(target_arch = "wasm32")]
+pub trait MaybeSend {}
+
+#[cfg(target_arch = "wasm32")]
+impl<T: ?Sized> MaybeSend for T {}
+
+/// The HTTP layer used by [`LemmyClient`][client] to talk to a Lemmy instance.
+///
+/// Every request the client makes is turned into an [`http::Request`] with a fully built URL,
+/// headers and serialized body, and handed to the transport. Implement this trait to swap in a
+/// different HTTP library, add instrumentation, or return canned responses in tests.
+///
+/// Transports should only fail when no response was received, using
+/// [`ClientError::Transport`] or [`ClientError::Timeout`]. Error statuses are returned as regular
+/// responses so the client can decode Lemmy's error body.
+///
+/// ```
+/// use bytes::Bytes;
+/// use http::{Request, Response};
+/// use lemmy_client::{Transport, TransportFuture};
+///
+/// struct Offline;
+///
+/// impl Transport for Offline {
+///   fn send(&self, _request: Request<Vec<u8>>) -> TransportFuture<'_> {
+///     Box::pin(async { Ok(Response::new(Bytes::from_static(br#"{"success":true}"#))) })
+///   }
+/// }
+/// ```
+///
+/// [client]: crate::LemmyClient
+pub trait Transport: MaybeSendSync {
+  /// Sends a request and returns the full response.
+  fn send(&self, request: Request<Vec<u8>>) -> TransportFuture<'_>;
+
+  /// Sends a request whose body is read as it is sent, such as a file upload, and returns the full
+  /// response.
+  ///
+  /// By default, the body is read in full and the request is sent with
+  /// [`send`][Transport::send]. Transports that can stream request bodies should override this,
+  /// so that large bodies aren't held in memory and the body's progress follows the upload.
+  fn send_streaming(&self, request: Request<UploadBody>) -> TransportFuture<'_> {
+    Box::pin(async move {
+      let (parts, body) = request.into_parts();
+      let body = body
+        .read_to_end()
+        .await
+        .map_err(|e| ClientError::Transport(Box::new(e)))?;
+
+      self.send(Request::from_parts(parts, body)).await
+    })
+  }
+}
+
+impl<T: Transport + ?Sized> Transport for std::sync::Arc<T> {
+  fn send(&self, request: Request<Vec<u8>>) -> TransportFuture<'_> {
+    (**self).send(request)
+  }
+
+  fn send_streaming(&self, request: Request<UploadBody>) -> TransportFuture<'_> {
+    (**self).send_streaming(request)
+  }
+}
+
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+mod fetch_transport;
+#[cfg(feature = "native")]
+mod reqwest_transport;
+
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+pub use fetch_transport::FetchTransport;
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+pub(crate) use fetch_transport::JsError;
+#[cfg(feature = "native")]
+pub use reqwest_transport::ReqwestTransport;
+
+/// The transport used by [`LemmyClient::new`][new]: the browser's fetch API when targeting WASM
+/// with the `wasm` feature, and reqwest otherwise.
+///
+/// [new]: crate::LemmyClient::new
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+pub(crate) type DefaultTransport = FetchTransport;
+
+/// The transport used by [`LemmyClient::new`][new]: the browser's fetch API when targeting WASM
+/// with the `wasm` feature, and reqwest otherwise.
+///
+/// [new]: crate::LemmyClient::new
+#[cfg(all(feature = "native", not(all(feature = "wasm", target_arch = "wasm32"))))]
+pub(crate) type DefaultTransport = ReqwestTransport;
+
+/// Creates the transport used by [`LemmyClient::try_new`][try_new]. The [`HttpOptions`][options]
+/// only apply to native targets, since the browser manages connections itself.
+///
+/// [try_new]: crate::LemmyClient::try_new
+/// [options]: crate::HttpOptions
+#[cfg(any(feature = "native", all(feature = "wasm", target_arch = "wasm32")))]
+pub(crate) fn default_transport(
+  options: &crate::HttpOptions,
+) -> Result<DefaultTransport, ClientError> {
+  #[cfg(not(target_arch = "wasm32"))]
+  return ReqwestTransport::with_options(options);
+
+  #[cfg(target_arch = "wasm32")]
+  {
+    let _ = options;
+    Ok(DefaultTransport::default())
+  }
+}
+
+/// Stands in for a default transport that couldn't be created, failing every request with the
+/// error that kept it from being created.
+#[cfg(any(feature = "native", all(feature = "wasm", target_arch = "wasm32")))]
+pub(crate) struct Unavailable(pub ClientError);
+
+#[cfg(any(feature = "native", all(feature = "wasm", target_arch = "wasm32")))]
+impl Transport for Unavailable {
+  fn send(&self, _request: Request<Vec<u8>>) -> TransportFuture<'_> {
+    let error = self.0.duplicate();
+
+    Box::pin(async move { Err(error) })
+  }
+}
@@ -0,0 +1,299 @@
+use crate::{ClientError, Transport, TransportFuture, UploadBody};
+use bytes::Bytes;
+use http::{HeaderMap, HeaderName, HeaderValue, Request, Response, StatusCode, header};
+use js_sys::{Array, ArrayBuffer, Promise, Uint8Array};
+use std::fmt;
+use wasm_bindgen::{JsCast, JsValue, closure::Closure, prelude::wasm_bindgen};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+  AbortController,
+  Headers,
+  ProgressEvent,
+  RequestCredentials,
+  RequestInit,
+  RequestMode,
+  XmlHttpRequest,
+  XmlHttpRequestResponseType,
+  XmlHttpRequestUpload,
+};
+
+#[wasm_bindgen]
+extern "C" {
+  /// The global `fetch`, which exists both in windows and in workers.
+  #[wasm_bindgen(js_name = fetch)]
+  fn fetch_with_request(input: &web_sys::Request) -> Promise;
+}
+
+/// Headers the browser sets by itself. Setting them is either forbidden or makes every request
+/// need a CORS preflight.
+const BROWSER_HEADERS: [HeaderName; 5] = [
+  header::USER_AGENT,
+  header::HOST,
+  header::CONTENT_LENGTH,
+  header::CONNECTION,
+  header::ACCEPT_ENCODING,
+];
+
+/// An error thrown by a browser API.
+#[derive(Debug)]
+pub(crate) struct JsError(String);
+
+impl fmt::Display for JsError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(&self.0)
+  }
+}
+
+impl std::error::Error for JsError {}
+
+impl From<JsValue> for JsError {
+  fn from(value: JsValue) -> Self {
+    let message = value
+      .dyn_ref::<js_sys::Error>()
+      .map(|e| String::from(e.message()))
+      .or_else(|| value.as_string())
+      .unwrap_or_else(|| format!("{value:?}"));
+
+    Self(message)
+  }
+}
+
+fn transport_error(value: JsValue) -> ClientError {
+  ClientError::Transport(Box::new(JsError::from(value)))
+}
+
+fn invalid_request(value: JsValue) -> ClientError {
+  ClientError::InvalidRequest(Box::new(JsError::from(value)))
+}
+
+/// Aborts the fetch it guards when dropped, which happens when the request's future is dropped
+/// before it completes.
+struct AbortOnDrop(Option<AbortController>);
+
+impl Drop for AbortOnDrop {
+  fn drop(&mut self) {
+    if let Some(controller) = self.0.take() {
+      controller.abort();
+    }
+  }
+}
+
+/// Aborts the upload it guards when dropped before the upload completed, and detaches the upload's
+/// progress callback, which is dropped along with the request's future.
+struct UploadGuard {
+  xhr: XmlHttpRequest,
+  upload: XmlHttpRequestUpload,
+  done: bool,
+}
+
+impl Drop for UploadGuard {
+  fn drop(&mut self) {
+    self.upload.set_onprogress(None);
+    if !self.done {
+      let _ = self.xhr.abort();
+    }
+  }
+}
+
+/// Returns the headers of a request that the browser lets scripts set.
+fn forwarded_headers(
+  headers: &HeaderMap,
+) -> impl Iterator<Item = Result<(&str, &str), ClientError>> {
+  headers
+    .iter()
+    .filter(|(name, _)| !BROWSER_HEADERS.contains(name))
+    .map(|(name, value)| {
+      let value = value
+        .to_str()
+        .map_err(|e| ClientError::InvalidRequest(Box::new(e)))?;
+
+      Ok((name.as_str(), value))
+    })
+}
+
+/// A [`Transport`] that sends requests with the browser's fetch API. It is the default when
+/// targeting WASM with the `wasm` feature.
+///
+/// Dropping a request's future aborts the fetch through an `AbortSignal`. Requests are always
+/// made in CORS mode, and headers that the browser sets by itself, such as `User-Agent`, are
+/// left out to avoid CORS preflights.
+///
+/// Uploads, sent with [`send_streaming`][Transport::send_streaming], use an `XMLHttpRequest`
+/// instead, whose upload events report the progress of the body as the browser sends it. Its
+/// credentials mode can only send cookies along with cross-origin requests or not, so
+/// [`RequestCredentials::Omit`] still sends them to the page's own origin. Where there is no
+/// `XMLHttpRequest`, such as in service workers, the body is read in full and fetched, and its
+/// progress follows the reading.
+#[derive(Debug, Clone)]
+pub struct FetchTransport {
+  credentials: RequestCredentials,
+}
+
+impl Default for FetchTransport {
+  fn default() -> Self {
+    Self::new(RequestCredentials::SameOrigin)
+  }
+}
+
+impl FetchTransport {
+  /// Creates a transport that sends requests with the given credentials mode.
+  ///
+  /// Use [`RequestCredentials::Include`] to send the instance's `jwt` cookie along with
+  /// cross-origin requests, for front-ends that rely on the cookie instead of
+  /// [`set_jwt`][crate::LemmyClient::set_jwt].
+  pub fn new(credentials: RequestCredentials) -> Self {
+    Self { credentials }
+  }
+
+  async fn fetch(&self, request: Request<Vec<u8>>) -> Result<Response<Bytes>, ClientError> {
+    let (parts, body) = request.into_parts();
+
+    let headers = Headers::new().map_err(invalid_request)?;
+    for header in forwarded_headers(&parts.headers) {
+      let (name, value) = header?;
+      headers.append(name, value).map_err(invalid_request)?;
+    }
+
+    let controller = AbortController::new().map_err(invalid_request)?;
+    let init = RequestInit::new();
+    init.set_method(parts.method.as_str());
+    init.set_mode(RequestMode::Cors);
+    init.set_credentials(self.credentials);
+    init.set_headers(&headers);
+    init.set_signal(Some(&controller.signal()));
+    if !body.is_empty() {
+      init.set_body(&Uint8Array::from(body.as_slice()));
+    }
+
+    let request = web_sys::Request::new_with_str_and_init(&parts.uri.to_string(), &init)
+      .map_err(invalid_request)?;
+    let mut abort = AbortOnDrop(Some(controller));
+
+    let res: web_sys::Response = JsFuture::from(fetch_with_request(&request))
+      .await
+      .map_err(transport_error)?
+      .dyn_into()
+      .map_err(transport_error)?;
+    let buffer = JsFuture::from(res.array_buffer().map_err(transport_error)?)
+      .await
+      .map_err(transport_error)?;
+    // The response has been read in full, so there is nothing left to abort.
+    abort.0 = None;
+
+    let mut response = Response::new(Bytes::from(Uint8Array::new(&buffer).to_vec()));
+    *response.status_mut() =
+      StatusCode::from_u16(res.status()).map_err(|e| ClientError::Transport(Box::new(e)))?;
+
+    let entries = js_sys::try_iter(&res.headers())
+      .map_err(transport_error)?
+      .into_iter()
+      .flatten();
+    for entry in entries {
+      let entry = Array::from(&entry.map_err(transport_error)?);
+      let (Some(name), Some(value)) = (entry.get(0).as_string(), entry.get(1).as_string()) else {
+        continue;
+      };
+
+      if let (Ok(name), Ok(value)) = (HeaderName::try_from(name), HeaderValue::try_from(value)) {
+        response.headers_mut().append(name, value);
+      }
+    }
+
+    Ok(response)
+  }
+
+  async fn upload(&self, request: Request<UploadBody>) -> Result<Response<Bytes>, ClientError> {
+    let (parts, body) = request.into_parts();
+    let Ok(xhr) = XmlHttpRequest::new() else {
+      let body = body
+        .read_to_end()
+        .await
+        .map_err(|e| ClientError::Transport(Box::new(e)))?;
+
+      return self.fetch(Request::from_parts(parts, body)).await;
+    };
+
+    let (blob, progress) = body
+      .into_blob()
+      .await
+      .map_err(|e| ClientError::Transport(Box::new(e)))?;
+
+    xhr
+      .open_with_async(parts.method.as_str(), &parts.uri.to_string(), true)
+      .map_err(invalid_request)?;
+    for header in forwarded_headers(&parts.headers) {
+      let (name, value) = header?;
+      xhr
+        .set_request_header(name, value)
+        .map_err(invalid_request)?;
+    }
+    xhr.set_with_credentials(self.credentials == RequestCredentials::Include);
+    xhr.set_response_type(XmlHttpRequestResponseType::Arraybuffer);
+
+    let on_progress = Closure::<dyn FnMut(ProgressEvent)>::new(move |event: ProgressEvent| {
+      progress.report(event.loaded() as u64);
+    });
+    let upload = xhr.upload().map_err(invalid_request)?;
+    upload.set_onprogress(Some(on_progress.as_ref().unchecked_ref()));
+    let loaded = Promise::new(&mut |resolve, reject| {
+      xhr.set_onload(Some(&resolve));
+      xhr.set_onerror(Some(&reject));
+      xhr.set_ontimeout(Some(&reject));
+      xhr.set_onabort(Some(&reject));
+    });
+    // Dropped before `on_progress`, so the browser never calls the callback once it's gone.
+    let mut guard = UploadGuard {
+      xhr: xhr.clone(),
+      upload,
+      done: false,
+    };
+
+    xhr
+      .send_with_opt_blob(Some(&blob))
+      .map_err(transport_error)?;
+    JsFuture::from(loaded).await.map_err(|_| {
+      ClientError::Transport(Box::new(JsError(format!(
+        "the upload to {} failed",
+        parts.uri
+      ))))
+    })?;
+    guard.done = true;
+
+    let status = xhr.status().map_err(transport_error)?;
+    let buffer: ArrayBuffer = xhr
+      .response()
+      .map_err(transport_error)?
+      .dyn_into()
+      .map_err(transport_error)?;
+
+    let mut response = Response::new(Bytes::from(Uint8Array::new(&buffer).to_vec()));
+    *response.status_mut() =
+      StatusCode::from_u16(status).map_err(|e| ClientError::Transport(Box::new(e)))?;
+
+    let headers = xhr.get_all_response_headers().map_err(transport_error)?;
+    for line in headers.split("\r\n") {
+      let Some((name, value)) = line.split_once(':') else {
+        continue;
+      };
+
+      if let (Ok(name), Ok(value)) = (
+        HeaderName::try_from(name.trim()),
+        HeaderValue::try_from(value.trim()),
+      ) {
+        response.headers_mut().append(name, value);
+      }
+    }
+
+    Ok(response)
+  }
+}
+
+impl Transport for FetchTransport {
+  fn send(&self, request: Request<Vec<u8>>) -> TransportFuture<'_> {
+    Box::pin(self.fetch(request))
+  }
+
+  fn send_streaming(&self, request: Request<UploadBody>) -> TransportFuture<'_> {
+    Box::pin(self.upload(request))
+  }
+}
@@ -0,0 +1,115 @@
+#[cfg(not(target_arch = "wasm32"))]
+use crate::UploadBody;
+use crate::{ClientError, Transport, TransportFuture};
+use bytes::Bytes;
+use http::{Request, Response};
+use reqwest::{Body, Client};
+
+fn map_reqwest_error(e: reqwest::Error) -> ClientError {
+  if e.is_timeout() {
+    ClientError::Timeout
+  } else {
+    ClientError::Transport(Box::new(e))
+  }
+}
+
+/// The default [`Transport`], backed by [`reqwest`].
+#[derive(Debug, Clone, Default)]
+pub struct ReqwestTransport {
+  client: Client,
+}
+
+impl ReqwestTransport {
+  /// Creates a transport that sends requests with the given [`reqwest::Client`].
+  pub fn new(client: Client) -> Self {
+    Self { client }
+  }
+
+  /// Creates a transport with a [`reqwest::Client`] configured from `options`.
+  ///
+  /// Fails with [`ClientError::InvalidOptions`] if the proxy URL or a certificate is malformed.
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn with_options(options: &crate::HttpOptions) -> Result<Self, ClientError> {
+    use reqwest::{Certificate, Proxy};
+
+    let invalid_options = |e: reqwest::Error| ClientError::InvalidOptions(Box::new(e));
+
+    let mut builder = Client::builder()
+      .pool_idle_timeout(options.pool_idle_timeout)
+      .tls_danger_accept_invalid_certs(options.accept_invalid_certs)
+      .gzip(options.gzip)
+      .brotli(options.brotli);
+
+    if let Some(timeout) = options.connect_timeout {
+      builder = builder.connect_timeout(timeout);
+    }
+    if let Some(timeout) = options.read_timeout {
+      builder = builder.read_timeout(timeout);
+    }
+    if let Some(timeout) = options.timeout {
+      builder = builder.timeout(timeout);
+    }
+    if let Some(proxy) = &options.proxy {
+      builder = builder.proxy(Proxy::all(proxy).map_err(invalid_options)?);
+    }
+    for pem in &options.root_certificates {
+      let certificates = Certificate::from_pem_bundle(pem).map_err(invalid_options)?;
+      if certificates.is_empty() {
+        return Err(ClientError::InvalidOptions(
+          "root certificate contains no PEM-encoded certificates".into(),
+        ));
+      }
+
+      for certificate in certificates {
+        builder = builder.add_root_certificate(certificate);
+      }
+    }
+    if options.http2_prior_knowledge {
+      builder = builder.http2_prior_knowledge();
+    }
+
+    builder.build().map(Self::new).map_err(invalid_options)
+  }
+}
+
+impl From<Client> for ReqwestTransport {
+  fn from(client: Client) -> Self {
+    Self::new(client)
+  }
+}
+
+impl ReqwestTransport {
+  async fn execute(
+    &self,
+    request: Request<impl Into<Body>>,
+  ) -> Result<Response<Bytes>, ClientError> {
+    let request = request
+      .try_into()
+      .map_err(|e| ClientError::InvalidRequest(Box::new(e)))?;
+    let res = self
+      .client
+      .execute(request)
+      .await
+      .map_err(map_reqwest_error)?;
+
+    let mut response = Response::new(Bytes::new());
+    *response.status_mut() = res.status();
+    *response.headers_mut() = res.headers().clone();
+    *response.body_mut() = res.bytes().await.map_err(map_reqwest_error)?;
+
+    Ok(response)
+  }
+}
+
+impl Transport for ReqwestTransport {
+  fn send(&self, request: Request<Vec<u8>>) -> TransportFuture<'_> {
+    Box::pin(self.execute(request))
+  }
+
+  /// Streams the body as it is sent. On WASM, where reqwest can't stream request bodies, the
+  /// default of reading the body in full first is kept.
+  #[cfg(not(target_arch = "wasm32"))]
+  fn send_streaming(&self, request: Request<UploadBody>) -> TransportFuture<'_> {
+    Box::pin(self.execute(request.map(Body::wrap_stream)))
+  }
+}
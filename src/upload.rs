@@ -0,0 +1,458 @@
+use crate::transport::MaybeSend;
+use bytes::Bytes;
+use futures_util::{AsyncRead, AsyncReadExt, Stream, TryStreamExt, stream};
+use std::{
+  fmt,
+  io,
+  pin::Pin,
+  sync::{
+    Arc,
+    atomic::{AtomicU64, Ordering},
+  },
+  task::{Context, Poll},
+};
+
+/// How many bytes are read from a reader at a time.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+#[cfg(not(target_arch = "wasm32"))]
+type Chunks = Pin<Box<dyn Stream<Item = io::Result<Bytes>> + Send>>;
+
+#[cfg(target_arch = "wasm32")]
+type Chunks = Pin<Box<dyn Stream<Item = io::Result<Bytes>>>>;
+
+#[cfg(not(target_arch = "wasm32"))]
+type ProgressCallback = Box<dyn Fn(UploadProgress) + Send>;
+
+#[cfg(target_arch = "wasm32")]
+type ProgressCallback = Box<dyn Fn(UploadProgress)>;
+
+/// Where the bytes of an [`UploadBody`] come from.
+enum Contents {
+  /// Bytes held in memory, taken once they are read.
+  Buffered(Option<Bytes>),
+  /// Chunks read as the body is sent.
+  Streamed(Chunks),
+  /// A browser `Blob`, which `FetchTransport` hands to the browser as is.
+  #[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+  Blob(web_sys::Blob),
+}
+
+/// How much of an [`UploadBody`] was sent so far.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UploadProgress {
+  /// How many bytes were sent.
+  pub sent: u64,
+  /// The size of the body, if it is known.
+  pub total: Option<u64>,
+}
+
+/// The body of a file upload, such as an image for
+/// [`upload_image`][crate::LemmyClient::upload_image], which is read as it is sent instead of being
+/// held in memory in full.
+///
+/// The body is a [`Stream`] of chunks, and reports its progress to the callback set with
+/// [`on_progress`][UploadBody::on_progress] each time a chunk is read from it. The default
+/// transport on native targets reads chunks as they are sent, so the progress follows the upload.
+/// `FetchTransport` sends bodies with an `XMLHttpRequest`, since not every browser can stream
+/// request bodies with fetch, and reports the progress of the upload as the browser sends it.
+/// Bodies that aren't created with `from_blob` are read into a `Blob` first. Other transports read
+/// the body in full before sending it, see [`Transport::send_streaming`][send].
+/// ```
+/// # use lemmy_client::{ClientError, LemmyClient, UploadBody};
+/// # async fn upload(client: &LemmyClient) -> Result<(), ClientError> {
+/// let body = UploadBody::from_path("cat.png")
+///   .map_err(|e| ClientError::InvalidRequest(Box::new(e)))?
+///   .on_progress(|progress| {
+///     if let Some(total) = progress.total {
+///       println!("{}%", progress.sent * 100 / total.max(1));
+///     }
+///   });
+/// let image = client.upload_image(body).await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [send]: crate::Transport::send_streaming
+pub struct UploadBody {
+  contents: Contents,
+  progress: Progress,
+}
+
+impl fmt::Debug for UploadBody {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("UploadBody")
+      .field("size", &self.progress.size)
+      .field("sent", &self.progress.sent.load(Ordering::Relaxed))
+      .finish_non_exhaustive()
+  }
+}
+
+/// Keeps track of how much of an [`UploadBody`] was sent, and reports it to its callback.
+pub(crate) struct Progress {
+  size: Option<u64>,
+  sent: Arc<AtomicU64>,
+  on_progress: Option<ProgressCallback>,
+}
+
+impl Progress {
+  /// Records that `sent` bytes of the body were sent so far.
+  pub(crate) fn report(&self, sent: u64) {
+    self.sent.store(sent, Ordering::Relaxed);
+    if let Some(on_progress) = &self.on_progress {
+      on_progress(UploadProgress {
+        sent,
+        total: self.size,
+      });
+    }
+  }
+}
+
+impl UploadBody {
+  /// Creates a body from chunks of bytes. `size` is the total size of the chunks, if known, which
+  /// is sent as the request's `Content-Length` and reported as [`UploadProgress::total`].
+  pub fn from_stream<S>(chunks: S, size: Option<u64>) -> Self
+  where
+    S: Stream<Item = io::Result<Bytes>> + MaybeSend + 'static,
+  {
+    Self::new(Contents::Streamed(Box::pin(chunks)), size)
+  }
+
+  fn new(contents: Contents, size: Option<u64>) -> Self {
+    Self {
+      contents,
+      progress: Progress {
+        size,
+        sent: Arc::default(),
+        on_progress: None,
+      },
+    }
+  }
+
+  /// Creates a body that reads `reader` until its end. `size` is how many bytes it will read, if
+  /// known, which is sent as the request's `Content-Length` and reported as
+  /// [`UploadProgress::total`].
+  pub fn from_reader<R>(reader: R, size: Option<u64>) -> Self
+  where
+    R: AsyncRead + MaybeSend + 'static,
+  {
+    let chunks = stream::try_unfold(Box::pin(reader), |mut reader| async move {
+      let mut chunk = vec![0; CHUNK_SIZE];
+      let read = reader.read(&mut chunk).await?;
+      if read == 0 {
+        return Ok(None);
+      }
+      chunk.truncate(read);
+
+      Ok(Some((Bytes::from(chunk), reader)))
+    });
+
+    Self::from_stream(chunks, size)
+  }
+
+  /// Creates a body that reads the file at `path`. The file is opened right away, and read on a
+  /// thread of its own as the body is sent, a few chunks ahead, so that reading it doesn't block
+  /// the task that sends it.
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn from_path(path: impl AsRef<std::path::Path>) -> io::Result<Self> {
+    let file = std::fs::File::open(path)?;
+    let size = file.metadata()?.len();
+
+    Ok(Self::from_stream(FileChunks::read(file)?, Some(size)))
+  }
+
+  /// Calls `callback` with the progress of the upload each time a chunk of the body is read, e.g.
+  /// to update a progress bar or send it to a channel.
+  pub fn on_progress(mut self, callback: impl Fn(UploadProgress) + MaybeSend + 'static) -> Self {
+    self.progress.on_progress = Some(Box::new(callback));
+
+    self
+  }
+
+  /// The size of the body, if it is known.
+  pub fn size(&self) -> Option<u64> {
+    self.progress.size
+  }
+
+  /// How many bytes were read from the body, shared so it can be read after the body was sent.
+  pub(crate) fn sent(&self) -> Arc<AtomicU64> {
+    self.progress.sent.clone()
+  }
+
+  /// Returns the bytes of a body held in memory without a progress callback, which can be sent
+  /// like any other request, and retried.
+  pub(crate) fn into_buffered(self) -> Result<Bytes, Self> {
+    match self {
+      Self {
+        contents: Contents::Buffered(Some(bytes)),
+        progress: Progress {
+          on_progress: None, ..
+        },
+      } => Ok(bytes),
+      body => Err(body),
+    }
+  }
+
+  /// Reads the whole body.
+  pub(crate) async fn read_to_end(self) -> io::Result<Vec<u8>> {
+    let capacity = self.size().map_or(0, |size| size.min(1 << 30) as usize);
+
+    self
+      .try_fold(Vec::with_capacity(capacity), |mut body, chunk| async move {
+        body.extend_from_slice(&chunk);
+        Ok(body)
+      })
+      .await
+  }
+}
+
+impl Stream for UploadBody {
+  type Item = io::Result<Bytes>;
+
+  fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+    let poll = self.contents.poll_chunk(cx);
+
+    if let Poll::Ready(Some(Ok(chunk))) = &poll {
+      let sent = self.progress.sent.load(Ordering::Relaxed) + chunk.len() as u64;
+      self.progress.report(sent);
+    }
+
+    poll
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    match &self.contents {
+      Contents::Buffered(bytes) => {
+        let len = usize::from(bytes.is_some());
+        (len, Some(len))
+      }
+      Contents::Streamed(chunks) => chunks.size_hint(),
+      #[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+      Contents::Blob(_) => (0, None),
+    }
+  }
+}
+
+impl Contents {
+  /// Reads the next chunk, without reporting it to the body's progress.
+  fn poll_chunk(&mut self, cx: &mut Context<'_>) -> Poll<Option<io::Result<Bytes>>> {
+    match self {
+      Self::Buffered(bytes) => Poll::Ready(bytes.take().map(Ok)),
+      Self::Streamed(chunks) => chunks.as_mut().poll_next(cx),
+      #[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+      Self::Blob(blob) => match readable_stream_chunks(&blob.stream()) {
+        Ok(chunks) => {
+          *self = Self::Streamed(Box::pin(chunks));
+          self.poll_chunk(cx)
+        }
+        Err(e) => {
+          *self = Self::Buffered(None);
+          Poll::Ready(Some(Err(e)))
+        }
+      },
+    }
+  }
+}
+
+/// The chunks of a file, read on a thread of their own so that reading them doesn't block the
+/// task that sends the file. The thread stays a few chunks ahead, and stops once the chunks are
+/// dropped.
+#[cfg(not(target_arch = "wasm32"))]
+struct FileChunks {
+  chunks: std::sync::mpsc::Receiver<io::Result<Bytes>>,
+  waker: Arc<futures_util::task::AtomicWaker>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl FileChunks {
+  /// How many chunks the thread reads ahead of the ones taken from it.
+  const READ_AHEAD: usize = 2;
+
+  fn read(mut file: std::fs::File) -> io::Result<Self> {
+    use std::io::Read;
+
+    let (sender, chunks) = std::sync::mpsc::sync_channel(Self::READ_AHEAD);
+    let waker = Arc::new(futures_util::task::AtomicWaker::new());
+    let reader_waker = waker.clone();
+
+    std::thread::Builder::new()
+      .name("lemmy-client-upload".to_owned())
+      .spawn(move || {
+        loop {
+          let mut chunk = vec![0; CHUNK_SIZE];
+          let chunk = match file.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(read) => {
+              chunk.truncate(read);
+              Ok(Bytes::from(chunk))
+            }
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => Err(e),
+          };
+
+          let failed = chunk.is_err();
+          if sender.send(chunk).is_err() || failed {
+            break;
+          }
+          reader_waker.wake();
+        }
+
+        // Lets the stream see that the file ended.
+        drop(sender);
+        reader_waker.wake();
+      })?;
+
+    Ok(Self { chunks, waker })
+  }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Stream for FileChunks {
+  type Item = io::Result<Bytes>;
+
+  fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+    use std::sync::mpsc::TryRecvError;
+
+    // Registered before checking for a chunk, so that one sent in between wakes the task.
+    self.waker.register(cx.waker());
+    match self.chunks.try_recv() {
+      Ok(chunk) => Poll::Ready(Some(chunk)),
+      Err(TryRecvError::Empty) => Poll::Pending,
+      Err(TryRecvError::Disconnected) => Poll::Ready(None),
+    }
+  }
+}
+
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+impl UploadBody {
+  /// Creates a body from a `Blob`, such as a `File` picked with an `<input type="file">`.
+  /// `FetchTransport` hands it to the browser as is, without reading it into memory.
+  pub fn from_blob(blob: &web_sys::Blob) -> Self {
+    Self::new(Contents::Blob(blob.clone()), Some(blob.size() as u64))
+  }
+
+  /// Creates a body that reads a `ReadableStream` of `Uint8Array` chunks, e.g. the body of a
+  /// fetched response. `size` is how many bytes it will read, if known. Fails if the stream is
+  /// locked by another reader.
+  pub fn from_readable_stream(
+    stream: &web_sys::ReadableStream,
+    size: Option<u64>,
+  ) -> io::Result<Self> {
+    Ok(Self::from_stream(readable_stream_chunks(stream)?, size))
+  }
+
+  /// Turns the body into a `Blob` that the browser can send, reading it first unless it was
+  /// created with [`from_blob`][Self::from_blob], along with the body's progress, which the
+  /// transport reports as the browser sends the `Blob`.
+  pub(crate) async fn into_blob(self) -> io::Result<(web_sys::Blob, Progress)> {
+    let Self {
+      mut contents,
+      mut progress,
+    } = self;
+
+    let blob = match contents {
+      Contents::Blob(blob) => blob,
+      _ => {
+        let parts = js_sys::Array::new();
+        while let Some(chunk) = futures_util::future::poll_fn(|cx| contents.poll_chunk(cx)).await {
+          parts.push(&js_sys::Uint8Array::from(chunk?.as_ref()));
+        }
+
+        web_sys::Blob::new_with_u8_array_sequence(&parts).map_err(js_error)?
+      }
+    };
+    progress.size = progress.size.or(Some(blob.size() as u64));
+
+    Ok((blob, progress))
+  }
+}
+
+/// Reads the `Uint8Array` chunks of a `ReadableStream`, and cancels it if they are dropped before
+/// the end of the stream.
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+fn readable_stream_chunks(
+  stream: &web_sys::ReadableStream,
+) -> io::Result<impl Stream<Item = io::Result<Bytes>> + use<>> {
+  use wasm_bindgen::JsValue;
+
+  struct Reader(web_sys::ReadableStreamDefaultReader);
+
+  impl Drop for Reader {
+    fn drop(&mut self) {
+      let _ = self.0.cancel();
+    }
+  }
+
+  let reader = Reader(web_sys::ReadableStreamDefaultReader::new(stream).map_err(js_error)?);
+
+  Ok(stream::try_unfold(reader, |reader| async move {
+    let result = wasm_bindgen_futures::JsFuture::from(reader.0.read())
+      .await
+      .map_err(js_error)?;
+    let done = js_sys::Reflect::get(&result, &JsValue::from_str("done")).map_err(js_error)?;
+    if done.is_truthy() {
+      return Ok(None);
+    }
+
+    let value = js_sys::Reflect::get(&result, &JsValue::from_str("value")).map_err(js_error)?;
+    let chunk = Bytes::from(js_sys::Uint8Array::new(&value).to_vec());
+
+    Ok(Some((chunk, reader)))
+  }))
+}
+
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+fn js_error(value: wasm_bindgen::JsValue) -> io::Error {
+  io::Error::other(crate::transport::JsError::from(value))
+}
+
+impl From<Bytes> for UploadBody {
+  fn from(bytes: Bytes) -> Self {
+    let size = bytes.len() as u64;
+
+    Self::new(Contents::Buffered(Some(bytes)), Some(size))
+  }
+}
+
+impl From<Vec<u8>> for UploadBody {
+  fn from(bytes: Vec<u8>) -> Self {
+    Bytes::from(bytes).into()
+  }
+}
+
+impl From<&'static [u8]> for UploadBody {
+  fn from(bytes: &'static [u8]) -> Self {
+    Bytes::from_static(bytes).into()
+  }
+}
+
+impl From<String> for UploadBody {
+  fn from(text: String) -> Self {
+    Bytes::from(text).into()
+  }
+}
+
+impl From<&'static str> for UploadBody {
+  fn from(text: &'static str) -> Self {
+    Bytes::from_static(text.as_bytes()).into()
+  }
+}
+
+/// Bodies held in memory are copied, and others, such as one created from a `tokio::fs::File`, are
+/// streamed.
+#[cfg(all(feature = "native", not(target_arch = "wasm32")))]
+impl From<reqwest::Body> for UploadBody {
+  fn from(body: reqwest::Body) -> Self {
+    use futures_util::StreamExt;
+
+    if let Some(bytes) = body.as_bytes() {
+      return Bytes::copy_from_slice(bytes).into();
+    }
+
+    let size = http_body::Body::size_hint(&body).exact();
+    let chunks =
+      http_body_util::BodyDataStream::new(body).map(|chunk| chunk.map_err(io::Error::other));
+
+    Self::from_stream(chunks, size)
+  }
+}
@@ -0,0 +1,205 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+mod common;
+
+use bytes::Bytes;
+use http::{Method, Request, Response, StatusCode};
+use lemmy_client::{
+  ApiVersion,
+  ApiVersionMode,
+  ClientError,
+  ClientOptions,
+  Endpoint,
+  LemmyClient,
+  RetryPolicy,
+  Transport,
+  TransportFuture,
+  lemmy_api_common::{account::auth::ChangePassword, post::GetPosts},
+};
+use std::sync::{Arc, Mutex};
+
+/// Answers every request with a successful login, recording the method and path of each.
+#[derive(Clone, Default)]
+struct Requests(Arc<Mutex<Vec<(Method, String)>>>);
+
+impl Transport for Requests {
+  fn send(&self, request: Request<Vec<u8>>) -> TransportFuture<'_> {
+    self
+      .0
+      .lock()
+      .unwrap()
+      .push((request.method().clone(), request.uri().path().to_owned()));
+    let body = r#"{"jwt":null,"registration_created":false,"verify_email_sent":false}"#;
+
+    Box::pin(async move { Ok(Response::new(Bytes::from_static(body.as_bytes()))) })
+  }
+}
+
+fn client(version: ApiVersion, requests: &Requests) -> LemmyClient {
+  LemmyClient::with_transport(
+    ClientOptions {
+      api_version: ApiVersionMode::Fixed(version),
+      ..common::mock_options()
+    },
+    requests.clone(),
+  )
+}
+
+fn change_password() -> ChangePassword {
+  ChangePassword {
+    new_password: "new password".to_owned().into(),
+    new_password_verify: "new password".to_owned().into(),
+    old_password: common::PASSWORD.to_owned().into(),
+    stay_logged_in: None,
+  }
+}
+
+#[test]
+fn v3_routes_keep_their_own_methods() {
+  assert_eq!(Endpoint::ChangePassword.method(), Method::POST);
+  assert_eq!(
+    Endpoint::ChangePassword.method_for(ApiVersion::V3),
+    Method::PUT
+  );
+  assert_eq!(Endpoint::Login.method_for(ApiVersion::V3), Method::POST);
+  assert_eq!(
+    Endpoint::ValidateAuth.method_for(ApiVersion::V3),
+    Method::GET
+  );
+
+  let v3 = Endpoint::ALL
+    .iter()
+    .filter(|endpoint| endpoint.path_for(ApiVersion::V3).is_some())
+    .count();
+  assert_eq!(v3, 7);
+}
+
+#[tokio::test]
+async fn change_password_is_put_on_v3() {
+  let requests = Requests::default();
+  client(ApiVersion::V3, &requests)
+    .change_password(change_password())
+    .await
+    .unwrap();
+  client(ApiVersion::V4, &requests)
+    .change_password(change_password())
+    .await
+    .unwrap();
+
+  assert_eq!(
+    *requests.0.lock().unwrap(),
+    [
+      (Method::PUT, "/api/v3/user/change_password".to_owned()),
+      (
+        Method::POST,
+        "/api/v4/account/auth/change_password".to_owned()
+      ),
+    ]
+  );
+}
+
+#[tokio::test]
+async fn other_endpoints_are_unsupported_on_v3() {
+  let requests = Requests::default();
+  let error = client(ApiVersion::V3, &requests)
+    .list_posts(GetPosts::default())
+    .await
+    .unwrap_err();
+
+  assert!(matches!(
+    error,
+    ClientError::UnsupportedOnServerVersion {
+      endpoint: Endpoint::ListPosts,
+      version: ApiVersion::V3,
+    }
+  ));
+  assert!(requests.0.lock().unwrap().is_empty());
+}
+
+/// An instance whose `/api/v4/site` responds with the given status and body, and which serves a
+/// Lemmy 0.19 site on `/api/v3/site`.
+#[derive(Clone)]
+struct Site {
+  v4: (StatusCode, &'static str),
+  requests: Requests,
+}
+
+impl Site {
+  fn client(v4: (StatusCode, &'static str)) -> (LemmyClient, Requests) {
+    let requests = Requests::default();
+    let client = LemmyClient::with_transport(
+      ClientOptions {
+        api_version: ApiVersionMode::Detect,
+        retry_policy: RetryPolicy::never(),
+        ..common::mock_options()
+      },
+      Site {
+        v4,
+        requests: requests.clone(),
+      },
+    );
+
+    (client, requests)
+  }
+}
+
+impl Transport for Site {
+  fn send(&self, request: Request<Vec<u8>>) -> TransportFuture<'_> {
+    let path = request.uri().path().to_owned();
+    self
+      .requests
+      .0
+      .lock()
+      .unwrap()
+      .push((request.method().clone(), path.clone()));
+    let (status, body) = match path.as_str() {
+      "/api/v4/site" => self.v4,
+      _ => (
+        StatusCode::OK,
+        r#"{"version":"0.19.5","site_view":{"local_site":{"registration_mode":"Open","private_instance":false}}}"#,
+      ),
+    };
+
+    Box::pin(async move {
+      Ok(
+        Response::builder()
+          .status(status)
+          .body(Bytes::from_static(body.as_bytes()))
+          .unwrap(),
+      )
+    })
+  }
+}
+
+#[tokio::test]
+async fn instances_without_v4_are_detected_as_v3() {
+  for v4 in [
+    (StatusCode::NOT_FOUND, r#"{"error":"not_found"}"#),
+    (StatusCode::NOT_FOUND, "Not Found"),
+    (StatusCode::OK, "<!doctype html><title>Lemmy</title>"),
+  ] {
+    let (client, requests) = Site::client(v4);
+
+    assert_eq!(client.detect_api_version().await.unwrap(), ApiVersion::V3);
+    assert_eq!(requests.0.lock().unwrap().len(), 2);
+  }
+}
+
+#[tokio::test]
+async fn server_errors_are_not_mistaken_for_v3() {
+  for v4 in [
+    (StatusCode::INTERNAL_SERVER_ERROR, r#"{"error":"unknown"}"#),
+    (StatusCode::BAD_GATEWAY, "<html>502 Bad Gateway</html>"),
+    (StatusCode::TOO_MANY_REQUESTS, ""),
+  ] {
+    let (client, requests) = Site::client(v4);
+
+    let error = client.detect_api_version().await.unwrap_err();
+    assert_eq!(error.status(), Some(v4.0));
+    assert_eq!(
+      *requests.0.lock().unwrap(),
+      [(Method::GET, "/api/v4/site".to_owned())]
+    );
+    assert_eq!(client.api_version(), None);
+  }
+}
@@ -0,0 +1,108 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+mod common;
+
+use common::{PASSWORD, login};
+use lemmy_client::{
+  ClientError,
+  PaginationOptions,
+  blocking::LemmyClient,
+  lemmy_api_common::{
+    account::auth::Register,
+    community::actions::CreateCommunity,
+    error::LemmyErrorType,
+    post::{GetPosts, actions::CreatePost},
+  },
+  testing::MockServer,
+};
+
+fn client(server: &MockServer) -> LemmyClient {
+  LemmyClient::with_transport(common::mock_options(), server.clone())
+}
+
+/// Registers an account, returning its JWT.
+fn register(client: &LemmyClient, username: &str) -> String {
+  client
+    .register_account(Register {
+      username: username.to_owned(),
+      password: PASSWORD.to_owned().into(),
+      password_verify: PASSWORD.to_owned().into(),
+      ..Default::default()
+    })
+    .unwrap()
+    .jwt
+    .unwrap()
+    .into_inner()
+}
+
+#[test]
+fn blocking_clients_call_every_kind_of_endpoint() {
+  let server = MockServer::new();
+  let mut client = client(&server);
+
+  let jwt = register(&client, "alice");
+  client.client_mut().set_jwt(&jwt).unwrap();
+
+  let community_id = client
+    .create_community(CreateCommunity {
+      name: "rust".to_owned(),
+      title: "Rust".to_owned(),
+      ..Default::default()
+    })
+    .unwrap()
+    .community_view
+    .community
+    .id;
+  for name in ["first", "second", "third"] {
+    client
+      .create_post(CreatePost {
+        name: name.to_owned(),
+        community_id,
+        ..Default::default()
+      })
+      .unwrap();
+  }
+
+  let posts = client
+    .list_posts_stream(
+      GetPosts {
+        limit: Some(2),
+        ..Default::default()
+      },
+      PaginationOptions::default(),
+    )
+    .map(|post| post.unwrap().post.name)
+    .collect::<Vec<_>>();
+  assert_eq!(posts.len(), 3);
+
+  assert!(client.logout().unwrap().success);
+  let error = client.get_current_user().unwrap_err();
+  assert!(matches!(
+    error,
+    ClientError::Api {
+      error: LemmyErrorType::NotLoggedIn,
+      ..
+    }
+  ));
+}
+
+#[test]
+fn blocking_sessions_are_started_and_ended() {
+  let server = MockServer::new();
+  let client = client(&server);
+  register(&client, "alice");
+
+  client.start_session(login("alice")).unwrap();
+  assert_eq!(
+    client
+      .get_current_user()
+      .unwrap()
+      .local_user_view
+      .person
+      .name,
+    "alice"
+  );
+
+  client.end_session().unwrap();
+  assert!(!client.client().session().is_active());
+}
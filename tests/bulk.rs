@@ -0,0 +1,147 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+mod common;
+
+use common::{create_community, create_post, register};
+use http::{Method, Request};
+use lemmy_client::{
+  BulkOutcome,
+  LemmyClient,
+  Transport,
+  TransportFuture,
+  lemmy_api_common::post::{GetPost, PostId, actions::DeletePost},
+  testing::MockServer,
+};
+use std::{
+  sync::{Arc, Mutex},
+  time::Duration,
+};
+
+/// Answers from a [`MockServer`] after a short delay, counting the requests it was sent and how
+/// many were in flight at once.
+#[derive(Default)]
+struct Tracked {
+  server: MockServer,
+  state: Mutex<TrackedState>,
+}
+
+#[derive(Default)]
+struct TrackedState {
+  sent: usize,
+  in_flight: usize,
+  max_in_flight: usize,
+}
+
+impl Transport for Tracked {
+  fn send(&self, request: Request<Vec<u8>>) -> TransportFuture<'_> {
+    Box::pin(async move {
+      {
+        let mut state = self.state.lock().unwrap();
+        state.sent += 1;
+        state.in_flight += 1;
+        state.max_in_flight = state.max_in_flight.max(state.in_flight);
+      }
+      tokio::time::sleep(Duration::from_millis(10)).await;
+      self.state.lock().unwrap().in_flight -= 1;
+
+      self.server.send(request).await
+    })
+  }
+}
+
+/// A client logged in as the author of `count` posts.
+async fn author(count: usize) -> (LemmyClient, Arc<Tracked>, Vec<PostId>) {
+  let transport = Arc::new(Tracked::default());
+  let mut client = LemmyClient::with_transport(common::mock_options(), transport.clone());
+  register(&mut client, "alice").await;
+  let community_id = create_community(&client, "community").await;
+
+  let mut posts = Vec::new();
+  for i in 0..count {
+    posts.push(create_post(&client, community_id, &format!("post {i}")).await);
+  }
+  *transport.state.lock().unwrap() = TrackedState::default();
+
+  (client, transport, posts)
+}
+
+fn deletions(posts: impl IntoIterator<Item = PostId>) -> impl Iterator<Item = DeletePost> {
+  posts.into_iter().map(|post_id| DeletePost {
+    post_id,
+    deleted: true,
+  })
+}
+
+async fn is_deleted(client: &LemmyClient, id: PostId) -> bool {
+  client
+    .get_post(GetPost {
+      id: Some(id),
+      comment_id: None,
+    })
+    .await
+    .unwrap()
+    .post_view
+    .post
+    .deleted
+}
+
+#[tokio::test]
+async fn failures_do_not_stop_the_other_operations() {
+  let (client, _, posts) = author(2).await;
+
+  let missing = PostId(posts[1].0 + 100);
+  let report = client
+    .bulk()
+    .run(deletions([posts[0], missing, posts[1]]))
+    .await;
+
+  assert!(!report.is_success());
+  let indices = report
+    .items
+    .iter()
+    .map(|item| item.index)
+    .collect::<Vec<_>>();
+  assert_eq!(indices, [0, 1, 2]);
+  assert_eq!(report.succeeded().count(), 2);
+  let failed = report.failed().map(|item| item.index).collect::<Vec<_>>();
+  assert_eq!(failed, [1]);
+  for post in posts {
+    assert!(is_deleted(&client, post).await);
+  }
+}
+
+#[tokio::test]
+async fn calls_are_limited_to_the_concurrency() {
+  let (client, transport, posts) = author(6).await;
+
+  let report = client.bulk().concurrency(2).run(deletions(posts)).await;
+
+  assert!(report.is_success());
+  let state = transport.state.lock().unwrap();
+  assert_eq!(state.sent, 6);
+  assert_eq!(state.max_in_flight, 2);
+}
+
+#[tokio::test]
+async fn dry_runs_send_nothing() {
+  let (client, transport, posts) = author(2).await;
+
+  let report = client
+    .bulk()
+    .dry_run(true)
+    .run(deletions(posts.clone()))
+    .await;
+
+  assert!(report.is_success());
+  assert_eq!(transport.state.lock().unwrap().sent, 0);
+  for item in &report.items {
+    let BulkOutcome::Planned { method, uri } = &item.outcome else {
+      panic!("unexpected outcome: {:?}", item.outcome);
+    };
+    assert_eq!(*method, Method::POST);
+    assert_eq!(uri.path(), "/api/v4/post/delete");
+  }
+  for post in posts {
+    assert!(!is_deleted(&client, post).await);
+  }
+}
@@ -0,0 +1,135 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+mod common;
+
+use bytes::Bytes;
+use common::Flaky;
+use http::{HeaderValue, StatusCode, header::COOKIE};
+use lemmy_client::{
+  CacheStore,
+  CachedResponse,
+  Endpoint,
+  FileCache,
+  MemoryCache,
+  ResponseCache,
+  testing::MockServer,
+};
+use std::{
+  path::PathBuf,
+  sync::Arc,
+  thread,
+  time::{Duration, SystemTime},
+};
+
+/// An empty directory for a test, removed when dropped.
+struct TempDir(PathBuf);
+
+impl TempDir {
+  fn new(name: &str) -> Self {
+    let dir = std::env::temp_dir().join(format!("lemmy-client-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+
+    Self(dir)
+  }
+}
+
+impl Drop for TempDir {
+  fn drop(&mut self) {
+    let _ = std::fs::remove_dir_all(&self.0);
+  }
+}
+
+/// A response that expires in a minute, at a whole second since files only keep those.
+fn cached(body: Bytes) -> CachedResponse {
+  let now = SystemTime::now()
+    .duration_since(SystemTime::UNIX_EPOCH)
+    .unwrap();
+
+  let mut cached = CachedResponse::new(
+    StatusCode::OK,
+    body,
+    SystemTime::UNIX_EPOCH + Duration::from_secs(now.as_secs() + 60),
+  );
+  cached.etag = Some(HeaderValue::from_static("\"v1\""));
+
+  cached
+}
+
+#[tokio::test]
+async fn cached_responses_are_not_fetched_again() {
+  let transport = Flaky::new(MockServer::new());
+  let mut client = transport.client(common::mock_options());
+  client.add_middleware(ResponseCache::new(MemoryCache::new(10)));
+
+  let site = client.get_site().await.unwrap();
+  let requests = transport.requests();
+  let cached = client.get_site().await.unwrap();
+
+  assert_eq!(transport.requests(), requests);
+  assert_eq!(cached.version, site.version);
+}
+
+#[test]
+fn file_cache_survives_restarts() {
+  let dir = TempDir::new("restart");
+  let response = cached(Bytes::from_static(br#"{"version":"1.0.0"}"#));
+  FileCache::new(&dir.0).insert(Endpoint::GetSite, "key", response.clone());
+
+  let restarted = FileCache::new(&dir.0);
+  assert_eq!(restarted.get(Endpoint::GetSite, "key"), Some(response));
+  assert_eq!(restarted.get(Endpoint::GetSite, "other key"), None);
+
+  restarted.remove_endpoint(Endpoint::GetSite);
+  assert_eq!(restarted.get(Endpoint::GetSite, "key"), None);
+}
+
+#[test]
+fn file_cache_readers_never_see_partial_writes() {
+  let dir = TempDir::new("atomic");
+  let cache = Arc::new(FileCache::new(&dir.0));
+  let bodies = [b'a', b'b'].map(|byte| Bytes::from(vec![byte; 1 << 20]));
+  cache.insert(Endpoint::GetSite, "key", cached(bodies[0].clone()));
+
+  let writer = {
+    let cache = cache.clone();
+    let bodies = bodies.clone();
+    thread::spawn(move || {
+      for body in bodies.iter().cycle().take(50) {
+        cache.insert(Endpoint::GetSite, "key", cached(body.clone()));
+      }
+    })
+  };
+  while !writer.is_finished() {
+    let response = cache.get(Endpoint::GetSite, "key").unwrap();
+    assert!(bodies.contains(&response.body));
+  }
+  writer.join().unwrap();
+
+  // Only the response itself is left behind.
+  let files = std::fs::read_dir(dir.0.join(Endpoint::GetSite.name()))
+    .unwrap()
+    .count();
+  assert_eq!(files, 1);
+}
+
+#[tokio::test]
+async fn responses_are_cached_per_cookie() {
+  let transport = Flaky::new(MockServer::new());
+  let mut client = transport.client(common::mock_options());
+  client.add_middleware(ResponseCache::new(MemoryCache::new(10)));
+
+  let mut requests = Vec::new();
+  for cookie in ["jwt=alice", "jwt=bob", "jwt=alice"] {
+    client
+      .headers_mut()
+      .insert(COOKIE, HeaderValue::from_static(cookie));
+    client.get_site().await.unwrap();
+    requests.push(transport.requests());
+  }
+  client.headers_mut().remove(COOKIE);
+  client.get_site().await.unwrap();
+  requests.push(transport.requests());
+
+  // Bob doesn't get Alice's response, nor does a client without a cookie.
+  assert_eq!(requests, [1, 2, 2, 3]);
+}
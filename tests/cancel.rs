@@ -0,0 +1,103 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+mod common;
+
+use common::{Failure, Flaky, Slow};
+use futures_util::future::join;
+use http::StatusCode;
+use lemmy_client::{
+  Backoff,
+  CancellationToken,
+  ClientError,
+  ClientOptions,
+  LemmyClient,
+  RetryPolicy,
+  testing::MockServer,
+};
+use std::{
+  sync::{Arc, atomic::Ordering},
+  time::{Duration, Instant},
+};
+
+fn slow_client() -> (LemmyClient, Arc<Slow>) {
+  let transport = Arc::new(Slow::new(Duration::from_millis(200)));
+  let client = LemmyClient::with_transport(common::mock_options(), transport.clone());
+
+  (client, transport)
+}
+
+#[tokio::test]
+async fn calls_past_their_deadline_time_out() {
+  let (client, transport) = slow_client();
+
+  let start = Instant::now();
+  let error = client
+    .with_deadline(Duration::from_millis(20))
+    .get_site()
+    .await
+    .unwrap_err();
+
+  assert!(matches!(error, ClientError::Timeout));
+  assert!(start.elapsed() < Duration::from_millis(200));
+  assert_eq!(transport.abandoned.load(Ordering::SeqCst), 1);
+
+  // The client itself has no deadline.
+  client.get_site().await.unwrap();
+}
+
+#[tokio::test]
+async fn deadlines_include_retries() {
+  let transport = Flaky::new(MockServer::new());
+  transport.fail(5, Failure::Status(StatusCode::SERVICE_UNAVAILABLE, None));
+  let client = transport.client(ClientOptions {
+    retry_policy: RetryPolicy {
+      max_attempts: 5,
+      backoff: Backoff::Constant(Duration::from_millis(50)),
+      jitter: 0.0,
+      ..Default::default()
+    },
+    ..common::mock_options()
+  });
+
+  let error = client
+    .with_deadline(Duration::from_millis(120))
+    .get_site()
+    .await
+    .unwrap_err();
+
+  assert!(matches!(error, ClientError::Timeout));
+  assert!(transport.requests() < 5);
+}
+
+#[tokio::test]
+async fn cancelling_fails_calls_in_flight() {
+  let (client, transport) = slow_client();
+  let token = CancellationToken::new();
+  let cancellable = client.with_cancellation(&token);
+
+  let cancel = async {
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    token.cancel();
+  };
+  let (result, ()) = join(cancellable.get_site(), cancel).await;
+
+  assert!(matches!(result, Err(ClientError::Cancelled)));
+  assert_eq!(transport.abandoned.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn cancelled_tokens_send_nothing() {
+  let (client, transport) = slow_client();
+  let token = CancellationToken::new();
+  token.clone().cancel();
+
+  let error = client
+    .with_deadline(Duration::from_secs(5))
+    .with_cancellation(&token)
+    .get_site()
+    .await
+    .unwrap_err();
+
+  assert!(matches!(error, ClientError::Cancelled));
+  assert_eq!(transport.sent.load(Ordering::SeqCst), 0);
+}
@@ -0,0 +1,117 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+mod common;
+
+use common::{Flaky, register};
+use lemmy_client::{
+  ClientError,
+  ClientOptions,
+  Endpoint,
+  Feature,
+  lemmy_api_common::community::GetMultiCommunity,
+  testing::MockServer,
+};
+
+#[tokio::test]
+async fn features_are_read_from_the_site() {
+  let capabilities = MockServer::new().client().capabilities().await.unwrap();
+
+  assert!(capabilities.supports(Feature::Registration));
+  assert!(!capabilities.supports(Feature::Captcha));
+  assert!(!capabilities.supports(Feature::Oauth));
+  assert!(capabilities.supports_endpoint(Endpoint::ListPosts));
+}
+
+#[tokio::test]
+async fn unsupported_features_are_not_called() {
+  let transport = Flaky::new(MockServer::new());
+  let mut client = transport.client(ClientOptions {
+    check_capabilities: true,
+    ..common::mock_options()
+  });
+
+  register(&mut client, "alice").await;
+  let requests = transport.requests();
+
+  let error = client.get_captcha().await.unwrap_err();
+  assert!(matches!(
+    error,
+    ClientError::UnsupportedFeature {
+      endpoint: Endpoint::GetCaptcha,
+      feature: Feature::Captcha,
+    }
+  ));
+  assert_eq!(transport.requests(), requests);
+}
+
+#[tokio::test]
+async fn features_of_later_versions_are_unsupported_on_older_sites() {
+  let transport = Flaky::new(MockServer::with_version("0.19.5"));
+  let client = transport.client(ClientOptions {
+    check_capabilities: true,
+    ..common::mock_options()
+  });
+
+  let capabilities = client.capabilities().await.unwrap();
+  assert_eq!(capabilities.version, "0.19.5");
+  for feature in [
+    Feature::MultiCommunity,
+    Feature::PostWarnings,
+    Feature::ModEditPost,
+    Feature::PendingFollows,
+    Feature::PersonNotes,
+  ] {
+    assert!(!capabilities.supports(feature), "{feature}");
+  }
+  assert!(capabilities.supports(Feature::Registration));
+
+  let requests = transport.requests();
+  let error = client
+    .get_multi_community(GetMultiCommunity {
+      id: None,
+      name: Some("rust".to_owned()),
+    })
+    .await
+    .unwrap_err();
+  assert!(matches!(
+    error,
+    ClientError::UnsupportedFeature {
+      endpoint: Endpoint::GetMultiCommunity,
+      feature: Feature::MultiCommunity,
+    }
+  ));
+  assert_eq!(transport.requests(), requests);
+}
+
+#[tokio::test]
+async fn features_of_later_versions_are_supported_on_newer_sites() {
+  for version in ["1.0.0", "1.0.0-beta.2", "1.2.3"] {
+    let transport = Flaky::new(MockServer::with_version(version));
+    let client = transport.client(ClientOptions {
+      check_capabilities: true,
+      ..common::mock_options()
+    });
+
+    let capabilities = client.capabilities().await.unwrap();
+    assert!(capabilities.supports(Feature::MultiCommunity), "{version}");
+    assert!(capabilities.supports(Feature::PersonNotes), "{version}");
+    assert!(
+      capabilities.supports_endpoint(Endpoint::ModEditPost),
+      "{version}"
+    );
+
+    let requests = transport.requests();
+    let error = client
+      .get_multi_community(GetMultiCommunity {
+        id: None,
+        name: Some("rust".to_owned()),
+      })
+      .await
+      .unwrap_err();
+    assert!(
+      !matches!(error, ClientError::UnsupportedFeature { .. }),
+      "{version}"
+    );
+    assert_eq!(transport.requests(), requests + 1, "{version}");
+  }
+}
@@ -0,0 +1,137 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+mod common;
+
+use common::{PASSWORD, login, register};
+use http::{Request, header::AUTHORIZATION};
+use lemmy_client::{
+  ClientError,
+  LemmyClient,
+  Transport,
+  lemmy_api_common::post::GetPosts,
+  testing::{
+    Cassette,
+    Interaction,
+    MockServer,
+    REDACTED,
+    RecordedBody,
+    RecordedRequest,
+    RecordedResponse,
+    Recorder,
+    Replayer,
+    UnrecordedRequest,
+  },
+};
+use serde_json::{Value, to_value};
+use std::sync::Arc;
+
+fn recording_client(recorder: &Recorder<MockServer>) -> LemmyClient {
+  LemmyClient::with_transport(common::mock_options(), recorder.clone())
+}
+
+/// The cassette as it would be saved.
+fn saved(recorder: &Recorder<impl Transport>) -> String {
+  serde_json::to_string_pretty(&recorder.cassette()).unwrap()
+}
+
+#[tokio::test]
+async fn credentials_are_not_recorded() {
+  let recorder = Recorder::new(MockServer::new());
+  let mut client = recording_client(&recorder);
+
+  register(&mut client, "alice").await;
+  let jwt = client
+    .login(login("alice"))
+    .await
+    .unwrap()
+    .jwt
+    .unwrap()
+    .into_inner();
+  client.list_posts(GetPosts::default()).await.unwrap();
+
+  let cassette = saved(&recorder);
+  assert!(!cassette.contains(PASSWORD));
+  assert!(!cassette.contains(&jwt));
+  assert!(cassette.contains(REDACTED));
+
+  let interactions = recorder.cassette().interactions;
+  let list_posts = &interactions.last().unwrap().request;
+  assert_eq!(list_posts.headers[AUTHORIZATION.as_str()], REDACTED);
+}
+
+#[tokio::test]
+async fn other_secrets_are_not_recorded() {
+  let recorder = Recorder::new(MockServer::new());
+
+  // Neither route is implemented by the mock, which doesn't keep them from being recorded.
+  let oauth = Request::post("http://mock.lemmy/api/v4/oauth/authenticate")
+    .header("content-type", "application/json")
+    .body(
+      br#"{"code":"oauth-code","pkce_code_verifier":"pkce-verifier","oauth_provider_id":1}"#
+        .to_vec(),
+    )
+    .unwrap();
+  recorder.send(oauth).await.unwrap();
+  let reset = Request::get("http://mock.lemmy/api/v4/account/verify_email?token=email-token")
+    .body(Vec::new())
+    .unwrap();
+  recorder.send(reset).await.unwrap();
+
+  let cassette = saved(&recorder);
+  for secret in ["oauth-code", "pkce-verifier", "email-token"] {
+    assert!(!cassette.contains(secret), "{secret} was recorded");
+  }
+  assert!(cassette.contains(r#""oauth_provider_id": 1"#));
+}
+
+#[tokio::test]
+async fn secrets_in_responses_are_not_recorded() {
+  let totp = Cassette {
+    interactions: vec![Interaction {
+      request: RecordedRequest {
+        method: "POST".to_owned(),
+        uri: "http://mock.lemmy/api/v4/account/auth/totp/generate".to_owned(),
+        headers: Default::default(),
+        body: Some(RecordedBody::Json(Value::Null)),
+      },
+      response: RecordedResponse {
+        status: 200,
+        headers: Default::default(),
+        body: Some(RecordedBody::Text(
+          r#"{"totp_secret_url":"otpauth://totp/alice?secret=TOTPSECRET"}"#.to_owned(),
+        )),
+      },
+    }],
+  };
+  let recorder = Recorder::new(Replayer::new(totp));
+  let client = LemmyClient::with_transport(common::mock_options(), recorder.clone());
+
+  let response = client.generate_totp_secret().await.unwrap();
+  assert!(response.totp_secret_url.contains("TOTPSECRET"));
+  assert!(!saved(&recorder).contains("TOTPSECRET"));
+}
+
+#[tokio::test]
+async fn recorded_interactions_are_replayed() {
+  let recorder = Recorder::new(MockServer::new());
+  let mut client = recording_client(&recorder);
+  register(&mut client, "alice").await;
+  let site = client.get_site().await.unwrap();
+  let posts = client.list_posts(GetPosts::default()).await.unwrap();
+
+  let replayer = Arc::new(Replayer::new(recorder.cassette()));
+  let mut replay = LemmyClient::with_transport(common::mock_options(), replayer.clone());
+  register(&mut replay, "alice").await;
+  let replayed_site = replay.get_site().await.unwrap();
+  assert_eq!(to_value(replayed_site).unwrap(), to_value(site).unwrap());
+  let replayed_posts = replay.list_posts(GetPosts::default()).await.unwrap();
+  assert_eq!(to_value(replayed_posts).unwrap(), to_value(posts).unwrap());
+
+  // Every interaction was used up.
+  assert_eq!(replayer.remaining(), 0);
+  let error = replay.get_site().await.unwrap_err();
+  let ClientError::Transport(error) = error else {
+    panic!("unexpected error: {error}");
+  };
+  assert!(error.downcast_ref::<UnrecordedRequest>().is_some());
+}
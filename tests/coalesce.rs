@@ -0,0 +1,63 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+mod common;
+
+use common::Slow;
+use futures_util::future::join;
+use lemmy_client::{ClientError, ClientOptions, LemmyClient};
+use std::{
+  sync::{Arc, atomic::Ordering},
+  time::Duration,
+};
+
+fn client() -> (LemmyClient, Arc<Slow>) {
+  let transport = Arc::new(Slow::new(Duration::from_millis(100)));
+  let client = LemmyClient::with_transport(
+    ClientOptions {
+      coalesce_requests: true,
+      ..common::mock_options()
+    },
+    transport.clone(),
+  );
+
+  (client, transport)
+}
+
+#[tokio::test]
+async fn identical_requests_are_sent_once() {
+  let (client, transport) = client();
+
+  let (first, second) = join(client.get_site(), client.get_site()).await;
+
+  assert_eq!(first.unwrap().version, second.unwrap().version);
+  assert_eq!(transport.sent.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn requests_outlive_the_call_that_sent_them_while_others_wait() {
+  let (client, transport) = client();
+
+  let impatient = client.with_deadline(Duration::from_millis(20));
+  let (first, second) = join(impatient.get_site(), client.get_site()).await;
+
+  assert!(matches!(first, Err(ClientError::Timeout)));
+  second.unwrap();
+  assert_eq!(transport.sent.load(Ordering::SeqCst), 1);
+  assert_eq!(transport.abandoned.load(Ordering::SeqCst), 0);
+}
+
+#[tokio::test]
+async fn abandoned_requests_are_dropped_and_not_joined() {
+  let (client, transport) = client();
+
+  let error = client
+    .with_deadline(Duration::from_millis(20))
+    .get_site()
+    .await
+    .unwrap_err();
+  assert!(matches!(error, ClientError::Timeout));
+  assert_eq!(transport.abandoned.load(Ordering::SeqCst), 1);
+
+  client.get_site().await.unwrap();
+  assert_eq!(transport.sent.load(Ordering::SeqCst), 2);
+}
@@ -0,0 +1,204 @@
+//! Helpers shared by the integration tests.
+
+#![allow(dead_code)]
+
+use bytes::Bytes;
+use http::{Request, Response, StatusCode};
+use lemmy_client::{
+  ClientError,
+  ClientOptions,
+  LemmyClient,
+  Transport,
+  TransportFuture,
+  lemmy_api_common::{
+    account::auth::{Login, Register},
+    community::{CommunityId, actions::CreateCommunity},
+    post::{PostId, actions::CreatePost},
+  },
+  testing::MockServer,
+};
+use std::{
+  sync::{
+    Arc,
+    Mutex,
+    atomic::{AtomicUsize, Ordering},
+  },
+  time::Duration,
+};
+
+/// The password of every account registered by [`register`].
+pub const PASSWORD: &str = "hunter2hunter2";
+
+/// Registers an account with `client` and logs the client in as it.
+pub async fn register(client: &mut LemmyClient, username: &str) {
+  let jwt = client
+    .register_account(Register {
+      username: username.to_owned(),
+      password: PASSWORD.to_owned().into(),
+      password_verify: PASSWORD.to_owned().into(),
+      ..Default::default()
+    })
+    .await
+    .unwrap()
+    .jwt
+    .unwrap();
+
+  client.set_jwt(&jwt.into_inner()).unwrap();
+}
+
+/// The login form of an account registered by [`register`].
+pub fn login(username: &str) -> Login {
+  Login {
+    username_or_email: username.to_owned().into(),
+    password: PASSWORD.to_owned().into(),
+    totp_2fa_token: None,
+    stay_logged_in: None,
+  }
+}
+
+pub async fn create_community(client: &LemmyClient, name: &str) -> CommunityId {
+  client
+    .create_community(CreateCommunity {
+      name: name.to_owned(),
+      title: name.to_owned(),
+      ..Default::default()
+    })
+    .await
+    .unwrap()
+    .community_view
+    .community
+    .id
+}
+
+pub async fn create_post(client: &LemmyClient, community_id: CommunityId, name: &str) -> PostId {
+  client
+    .create_post(CreatePost {
+      name: name.to_owned(),
+      community_id,
+      ..Default::default()
+    })
+    .await
+    .unwrap()
+    .post_view
+    .post
+    .id
+}
+
+/// What a [`Flaky`] transport does instead of passing a request on.
+#[derive(Clone)]
+pub enum Failure {
+  /// Fail with a transport error.
+  Transport,
+  /// Respond with a status, along with a `Retry-After` header if given.
+  Status(StatusCode, Option<&'static str>),
+}
+
+/// Passes requests on to a [`MockServer`], but fails the ones it was told to, and counts every
+/// request it sees.
+#[derive(Clone, Default)]
+pub struct Flaky {
+  server: MockServer,
+  failures: Arc<Mutex<Vec<Failure>>>,
+  requests: Arc<AtomicUsize>,
+}
+
+impl Flaky {
+  pub fn new(server: MockServer) -> Self {
+    Self {
+      server,
+      ..Default::default()
+    }
+  }
+
+  /// Fails the next `count` requests with `failure`.
+  pub fn fail(&self, count: usize, failure: Failure) {
+    let mut failures = self.failures.lock().unwrap();
+    failures.extend(std::iter::repeat_n(failure, count));
+  }
+
+  /// How many requests were sent so far.
+  pub fn requests(&self) -> usize {
+    self.requests.load(Ordering::SeqCst)
+  }
+
+  /// A client for the mock instance that sends its requests through this transport.
+  pub fn client(&self, options: ClientOptions<&'static str>) -> LemmyClient {
+    LemmyClient::with_transport(options, self.clone())
+  }
+}
+
+impl Transport for Flaky {
+  fn send(&self, request: Request<Vec<u8>>) -> TransportFuture<'_> {
+    self.requests.fetch_add(1, Ordering::SeqCst);
+    let failure = {
+      let mut failures = self.failures.lock().unwrap();
+      (!failures.is_empty()).then(|| failures.remove(0))
+    };
+
+    Box::pin(async move {
+      match failure {
+        None => self.server.send(request).await,
+        Some(Failure::Transport) => Err(ClientError::Transport("connection reset".into())),
+        Some(Failure::Status(status, retry_after)) => {
+          let mut builder = Response::builder().status(status);
+          if let Some(retry_after) = retry_after {
+            builder = builder.header(http::header::RETRY_AFTER, retry_after);
+          }
+          Ok(builder.body(Bytes::new()).unwrap())
+        }
+      }
+    })
+  }
+}
+
+/// Answers from a [`MockServer`] after a delay, counting the requests it was sent and the ones
+/// that were dropped before they were answered.
+#[derive(Default)]
+pub struct Slow {
+  server: MockServer,
+  delay: Duration,
+  pub sent: AtomicUsize,
+  pub abandoned: Arc<AtomicUsize>,
+}
+
+impl Slow {
+  pub fn new(delay: Duration) -> Self {
+    Self {
+      delay,
+      ..Default::default()
+    }
+  }
+}
+
+/// Counts a request as abandoned unless it is defused once it is answered.
+struct Abandoned(Option<Arc<AtomicUsize>>);
+
+impl Drop for Abandoned {
+  fn drop(&mut self) {
+    if let Some(abandoned) = &self.0 {
+      abandoned.fetch_add(1, Ordering::SeqCst);
+    }
+  }
+}
+
+impl Transport for Slow {
+  fn send(&self, request: Request<Vec<u8>>) -> TransportFuture<'_> {
+    self.sent.fetch_add(1, Ordering::SeqCst);
+
+    Box::pin(async move {
+      let mut abandoned = Abandoned(Some(self.abandoned.clone()));
+      tokio::time::sleep(self.delay).await;
+      abandoned.0 = None;
+
+      self.server.send(request).await
+    })
+  }
+}
+
+/// Options for a client of a [`MockServer`] that isn't served over HTTP.
+pub fn mock_options() -> ClientOptions<&'static str> {
+  ClientOptions {
+    secure: false,
+    ..ClientOptions::new("mock.lemmy")
+  }
+}
@@ -0,0 +1,131 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+mod common;
+
+use bytes::Bytes;
+use http::{
+  HeaderValue,
+  Request,
+  Response,
+  StatusCode,
+  header::{ETAG, IF_NONE_MATCH},
+};
+use lemmy_client::{
+  CacheStore,
+  CachedResponse,
+  ClientError,
+  ConditionalRequests,
+  Endpoint,
+  LemmyClient,
+  MemoryCache,
+  NotModifiedMode,
+  Transport,
+  TransportFuture,
+  testing::MockServer,
+};
+use serde_json::to_value;
+use std::{collections::HashMap, sync::Mutex};
+
+/// Tags every response of a [`MockServer`] with the same `ETag`, and answers requests that send
+/// it back with `304 Not Modified`.
+#[derive(Default)]
+struct Unchanging(MockServer);
+
+impl Transport for Unchanging {
+  fn send(&self, request: Request<Vec<u8>>) -> TransportFuture<'_> {
+    Box::pin(async move {
+      if request.headers().contains_key(IF_NONE_MATCH) {
+        return Ok(
+          Response::builder()
+            .status(StatusCode::NOT_MODIFIED)
+            .body(Bytes::new())
+            .unwrap(),
+        );
+      }
+
+      let mut response = self.0.send(request).await?;
+      response
+        .headers_mut()
+        .insert(ETAG, HeaderValue::from_static("\"v1\""));
+
+      Ok(response)
+    })
+  }
+}
+
+/// Hands out the stored responses with their bodies garbled, so that decoding them again fails.
+struct Garbling(MemoryCache);
+
+impl CacheStore for Garbling {
+  fn get(&self, endpoint: Endpoint, key: &str) -> Option<CachedResponse> {
+    let mut cached = self.0.get(endpoint, key)?;
+    cached.body = Bytes::from_static(b"<garbled>");
+
+    Some(cached)
+  }
+
+  fn insert(&self, endpoint: Endpoint, key: &str, response: CachedResponse) {
+    self.0.insert(endpoint, key, response);
+  }
+
+  fn remove_endpoint(&self, endpoint: Endpoint) {
+    self.0.remove_endpoint(endpoint);
+  }
+}
+
+/// Drops each response once it was handed out, as a store that is full might in between sending a
+/// request and receiving the response.
+#[derive(Default)]
+struct Forgetful(Mutex<HashMap<String, CachedResponse>>);
+
+impl CacheStore for Forgetful {
+  fn get(&self, _endpoint: Endpoint, key: &str) -> Option<CachedResponse> {
+    self.0.lock().unwrap().remove(key)
+  }
+
+  fn insert(&self, _endpoint: Endpoint, key: &str, response: CachedResponse) {
+    self.0.lock().unwrap().insert(key.to_owned(), response);
+  }
+
+  fn remove_endpoint(&self, _endpoint: Endpoint) {
+    self.0.lock().unwrap().clear();
+  }
+}
+
+fn client(middleware: ConditionalRequests) -> LemmyClient {
+  let mut client = LemmyClient::with_transport(common::mock_options(), Unchanging::default());
+  client.add_middleware(middleware);
+
+  client
+}
+
+#[tokio::test]
+async fn unmodified_responses_are_returned_without_decoding_them_again() {
+  let client = client(ConditionalRequests::new(Garbling(MemoryCache::new(10))));
+
+  let site = client.get_site().await.unwrap();
+  let unmodified = client.get_site().await.unwrap();
+
+  assert_eq!(to_value(unmodified).unwrap(), to_value(site).unwrap());
+}
+
+#[tokio::test]
+async fn unmodified_responses_fail_in_error_mode() {
+  let client =
+    client(ConditionalRequests::new(MemoryCache::new(10)).not_modified(NotModifiedMode::Error));
+
+  client.get_site().await.unwrap();
+  let error = client.get_site().await.unwrap_err();
+
+  assert!(matches!(error, ClientError::NotModified));
+}
+
+#[tokio::test]
+async fn unmodified_responses_that_are_no_longer_stored_fail() {
+  let client = client(ConditionalRequests::new(Forgetful::default()));
+
+  client.get_site().await.unwrap();
+  let error = client.get_site().await.unwrap_err();
+
+  assert!(matches!(error, ClientError::NotModified));
+}
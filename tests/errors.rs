@@ -0,0 +1,123 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+mod common;
+
+use bytes::Bytes;
+use http::{Request, Response, StatusCode, header::CONTENT_TYPE};
+use lemmy_client::{
+  ClientError,
+  ClientOptions,
+  LemmyClient,
+  RetryPolicy,
+  Transport,
+  TransportFuture,
+  lemmy_api_common::error::LemmyErrorType,
+};
+
+/// Answers every request with the same response.
+#[derive(Clone)]
+struct Canned {
+  status: StatusCode,
+  content_type: &'static str,
+  body: Bytes,
+}
+
+impl Canned {
+  fn client(status: StatusCode, content_type: &'static str, body: impl Into<Bytes>) -> LemmyClient {
+    LemmyClient::with_transport(
+      ClientOptions {
+        retry_policy: RetryPolicy::never(),
+        ..common::mock_options()
+      },
+      Self {
+        status,
+        content_type,
+        body: body.into(),
+      },
+    )
+  }
+}
+
+impl Transport for Canned {
+  fn send(&self, _request: Request<Vec<u8>>) -> TransportFuture<'_> {
+    let response = Response::builder()
+      .status(self.status)
+      .header(CONTENT_TYPE, self.content_type)
+      .body(self.body.clone())
+      .unwrap();
+
+    Box::pin(async move { Ok(response) })
+  }
+}
+
+#[tokio::test]
+async fn html_error_pages_are_non_json() {
+  let page = format!(
+    "<html><head><title>502 Bad Gateway</title></head><body>{}</body></html>",
+    "nginx ".repeat(100)
+  );
+  let client = Canned::client(StatusCode::BAD_GATEWAY, "text/html", page.clone());
+
+  let error = client.get_site().await.unwrap_err();
+  let ClientError::NonJson {
+    status,
+    headers,
+    body,
+  } = &error
+  else {
+    panic!("expected a non-JSON error, got {error:?}");
+  };
+  assert_eq!(*status, StatusCode::BAD_GATEWAY);
+  assert_eq!(headers[CONTENT_TYPE], "text/html");
+  assert_eq!(body, page.as_bytes());
+
+  let message = error.to_string();
+  assert!(message.contains("502 Bad Gateway"), "{message}");
+  assert!(
+    message.contains("<title>502 Bad Gateway</title>"),
+    "{message}"
+  );
+  assert!(message.ends_with('…'), "{message}");
+  assert!(message.len() < page.len(), "{message}");
+}
+
+#[tokio::test]
+async fn wrongly_shaped_json_fails_to_decode_at_its_path() {
+  let client = Canned::client(
+    StatusCode::OK,
+    "application/json",
+    r#"{"site_view":{"site":{"id":"one"}}}"#,
+  );
+
+  let error = client.get_site().await.unwrap_err();
+  let ClientError::Decode {
+    status, path, body, ..
+  } = &error
+  else {
+    panic!("expected a decode error, got {error:?}");
+  };
+  assert_eq!(*status, StatusCode::OK);
+  assert_eq!(path, "site_view.site.id");
+  assert_eq!(body, r#"{"site_view":{"site":{"id":"one"}}}"#);
+  assert!(error.to_string().contains("`site_view.site.id`"));
+}
+
+#[tokio::test]
+async fn lemmy_errors_are_api_errors() {
+  let client = Canned::client(
+    StatusCode::BAD_REQUEST,
+    "application/json",
+    r#"{"error":"not_logged_in"}"#,
+  );
+
+  let error = client.get_site().await.unwrap_err();
+  assert!(matches!(
+    error,
+    ClientError::Api {
+      status: StatusCode::BAD_REQUEST,
+      error: LemmyErrorType::NotLoggedIn,
+    }
+  ));
+  assert_eq!(error.api_error(), Some(&LemmyErrorType::NotLoggedIn));
+  assert_eq!(error.body(), None);
+}
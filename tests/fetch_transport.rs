@@ -0,0 +1,201 @@
+//! Browser tests of [`FetchTransport`] against a mock instance served from a native process:
+//!
+//! ```sh
+//! cargo run --example mock_server -- 127.0.0.1:8536 &
+//! wasm-pack test --headless --firefox --no-default-features --features wasm -- --test fetch_transport
+//! ```
+//!
+//! Set `LEMMY_MOCK_SERVER` when building the tests to use a different address.
+
+#![cfg(all(target_arch = "wasm32", feature = "wasm"))]
+
+use http::{HeaderValue, Request, Response};
+use lemmy_client::{
+  ClientError,
+  ClientOptions,
+  FetchTransport,
+  LemmyClient,
+  LemmyResult,
+  Middleware,
+  RequestContext,
+  UploadBody,
+  lemmy_api_common::{
+    account::auth::{Login, Register},
+    community::actions::CreateCommunity,
+    error::LemmyErrorType,
+    post::{GetPosts, actions::CreatePost},
+  },
+};
+use std::{
+  ops::ControlFlow,
+  sync::{Arc, Mutex},
+  time::Duration,
+};
+use wasm_bindgen_test::{wasm_bindgen_test, wasm_bindgen_test_configure};
+use web_sys::RequestCredentials;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+const PASSWORD: &str = "hunter2hunter2";
+
+/// Address of the mock instance, set when the tests are built.
+fn server() -> &'static str {
+  option_env!("LEMMY_MOCK_SERVER").unwrap_or("127.0.0.1:8536")
+}
+
+fn fetch_client(credentials: RequestCredentials) -> LemmyClient {
+  LemmyClient::with_transport(
+    ClientOptions {
+      secure: false,
+      ..ClientOptions::new(server())
+    },
+    FetchTransport::new(credentials),
+  )
+}
+
+/// Usernames are unique per test run, since the mock instance outlives the browser session.
+fn username(name: &str) -> String {
+  format!("{name}{}", js_sys::Date::now() as u64)
+}
+
+async fn register(client: &LemmyClient, username: &str) -> String {
+  client
+    .register_account(Register {
+      username: username.to_owned(),
+      password: PASSWORD.to_owned().into(),
+      password_verify: PASSWORD.to_owned().into(),
+      ..Default::default()
+    })
+    .await
+    .unwrap()
+    .jwt
+    .unwrap()
+    .into_inner()
+}
+
+/// Asks the mock instance to wait before handling each request.
+struct Delay(u64);
+
+impl Middleware for Delay {
+  fn before_request(
+    &self,
+    _context: &RequestContext,
+    request: &mut Request<Vec<u8>>,
+  ) -> ControlFlow<LemmyResult<Response<bytes::Bytes>>> {
+    request
+      .headers_mut()
+      .insert("x-mock-delay", HeaderValue::from(self.0));
+
+    ControlFlow::Continue(())
+  }
+}
+
+#[wasm_bindgen_test]
+async fn authenticated_requests_pass_cors_preflights() {
+  let mut client = fetch_client(RequestCredentials::SameOrigin);
+  let jwt = register(&client, &username("cors")).await;
+  client.set_jwt(&jwt).unwrap();
+
+  // The `Authorization` and `Content-Type` headers make the browser send a preflight first.
+  client.validate_auth().await.unwrap();
+  let community = client
+    .create_community(CreateCommunity {
+      name: username("cors"),
+      title: "CORS".to_owned(),
+      ..Default::default()
+    })
+    .await
+    .unwrap();
+  assert!(community.community_view.community.local);
+}
+
+#[wasm_bindgen_test]
+async fn cookies_are_only_sent_with_included_credentials() {
+  let name = username("cookie");
+  let with_cookies = fetch_client(RequestCredentials::Include);
+  register(&with_cookies, &name).await;
+  with_cookies
+    .login(Login {
+      username_or_email: name.into(),
+      password: PASSWORD.to_owned().into(),
+      totp_2fa_token: None,
+      stay_logged_in: None,
+    })
+    .await
+    .unwrap();
+
+  // Neither client has a JWT set, so only the cookie can log them in.
+  with_cookies.validate_auth().await.unwrap();
+  let error = fetch_client(RequestCredentials::SameOrigin)
+    .validate_auth()
+    .await
+    .unwrap_err();
+  assert_eq!(error.api_error(), Some(&LemmyErrorType::NotLoggedIn));
+}
+
+#[wasm_bindgen_test]
+async fn dropping_a_request_aborts_the_fetch() {
+  let mut client = fetch_client(RequestCredentials::SameOrigin);
+  let jwt = register(&client, &username("abort")).await;
+  client.set_jwt(&jwt).unwrap();
+  let community_id = client
+    .create_community(CreateCommunity {
+      name: username("abort"),
+      title: "Abort".to_owned(),
+      ..Default::default()
+    })
+    .await
+    .unwrap()
+    .community_view
+    .community
+    .id;
+
+  let mut slow = fetch_client(RequestCredentials::SameOrigin);
+  slow.set_jwt(&jwt).unwrap();
+  slow.add_middleware(Delay(500));
+  let error = slow
+    .with_deadline(Duration::from_millis(100))
+    .create_post(CreatePost {
+      name: "Aborted".to_owned(),
+      community_id,
+      ..Default::default()
+    })
+    .await
+    .unwrap_err();
+  assert!(matches!(error, ClientError::Timeout));
+
+  // Outlast the delay, after which the mock instance skips requests whose connection was closed.
+  let mut slower = fetch_client(RequestCredentials::SameOrigin);
+  slower.add_middleware(Delay(1000));
+  slower.get_site().await.unwrap();
+
+  let posts = client
+    .list_posts(GetPosts {
+      community_id: Some(community_id),
+      ..Default::default()
+    })
+    .await
+    .unwrap();
+  assert!(posts.items.is_empty());
+}
+
+#[wasm_bindgen_test]
+async fn uploads_report_progress_as_the_browser_sends_them() {
+  let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(vec![7; 256 * 1024].as_slice()));
+  let blob = web_sys::Blob::new_with_u8_array_sequence(&parts).unwrap();
+  let progress = Arc::new(Mutex::new(Vec::new()));
+  let reported = progress.clone();
+  let body = UploadBody::from_blob(&blob).on_progress(move |progress| {
+    reported.lock().unwrap().push(progress);
+  });
+
+  // The mock instance has no image routes, so only the upload itself matters.
+  let client = fetch_client(RequestCredentials::SameOrigin);
+  let _ = client.upload_image(body).await;
+
+  let progress = progress.lock().unwrap();
+  let last = progress.last().unwrap();
+  assert_eq!(last.sent, 256 * 1024);
+  assert_eq!(last.total, Some(256 * 1024));
+  assert!(progress.windows(2).all(|pair| pair[0].sent <= pair[1].sent));
+}
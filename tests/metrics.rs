@@ -0,0 +1,199 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+mod common;
+
+use bytes::Bytes;
+use common::register;
+use http::{Request, Response, StatusCode};
+use lemmy_client::{
+  ApiVersion,
+  ApiVersionMode,
+  CancellationToken,
+  ClientOptions,
+  Endpoint,
+  LemmyClient,
+  MetricsRecorder,
+  RequestMetrics,
+  RetryPolicy,
+  Transport,
+  TransportFuture,
+  lemmy_api_common::post::GetPosts,
+  testing::MockServer,
+};
+use std::{
+  future,
+  sync::{
+    Arc,
+    Mutex,
+    atomic::{AtomicUsize, Ordering},
+  },
+  time::Duration,
+};
+
+/// What was recorded about a request.
+#[derive(Debug, PartialEq)]
+struct Recorded {
+  endpoint: Endpoint,
+  outcome: String,
+  retries: u32,
+  bytes_sent: usize,
+  bytes_received: usize,
+  status: Option<StatusCode>,
+}
+
+#[derive(Clone, Default)]
+struct Recorder(Arc<Mutex<Vec<Recorded>>>);
+
+impl Recorder {
+  fn last(&self) -> Recorded {
+    self.0.lock().unwrap().pop().unwrap()
+  }
+}
+
+impl MetricsRecorder for Recorder {
+  fn record(&self, metrics: &RequestMetrics) {
+    self.0.lock().unwrap().push(Recorded {
+      endpoint: metrics.endpoint,
+      outcome: metrics.outcome().into_owned(),
+      retries: metrics.retries,
+      bytes_sent: metrics.bytes_sent,
+      bytes_received: metrics.bytes_received,
+      status: metrics.status,
+    });
+  }
+}
+
+/// Responds to the first few requests with an error body, passes the rest on to a
+/// [`MockServer`], and counts the bytes of every body.
+#[derive(Default)]
+struct Overloaded {
+  server: MockServer,
+  failures: AtomicUsize,
+  sent: AtomicUsize,
+  received: AtomicUsize,
+}
+
+impl Transport for Overloaded {
+  fn send(&self, request: Request<Vec<u8>>) -> TransportFuture<'_> {
+    Box::pin(async move {
+      self.sent.fetch_add(request.body().len(), Ordering::SeqCst);
+      let overloaded = self
+        .failures
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+        .is_ok();
+      let response = if overloaded {
+        Response::builder()
+          .status(StatusCode::SERVICE_UNAVAILABLE)
+          .body(Bytes::from_static(b"<h1>Overloaded, try again</h1>"))
+          .unwrap()
+      } else {
+        self.server.send(request).await?
+      };
+      self
+        .received
+        .fetch_add(response.body().len(), Ordering::SeqCst);
+
+      Ok(response)
+    })
+  }
+}
+
+/// Never responds.
+struct Hanging;
+
+impl Transport for Hanging {
+  fn send(&self, _request: Request<Vec<u8>>) -> TransportFuture<'_> {
+    Box::pin(future::pending())
+  }
+}
+
+fn options() -> ClientOptions<&'static str> {
+  ClientOptions {
+    retry_policy: RetryPolicy {
+      max_delay: Duration::ZERO,
+      ..Default::default()
+    },
+    ..common::mock_options()
+  }
+}
+
+#[tokio::test]
+async fn bytes_are_counted_over_every_attempt() {
+  let transport = Arc::new(Overloaded::default());
+  let mut client = LemmyClient::with_transport(options(), transport.clone());
+  register(&mut client, "alice").await;
+  let recorder = Recorder::default();
+  client.set_metrics_recorder(recorder.clone());
+
+  transport.sent.store(0, Ordering::SeqCst);
+  transport.received.store(0, Ordering::SeqCst);
+  transport.failures.store(2, Ordering::SeqCst);
+  client.list_posts(GetPosts::default()).await.unwrap();
+
+  assert_eq!(
+    recorder.last(),
+    Recorded {
+      endpoint: Endpoint::ListPosts,
+      outcome: "success".to_owned(),
+      retries: 2,
+      bytes_sent: transport.sent.load(Ordering::SeqCst),
+      bytes_received: transport.received.load(Ordering::SeqCst),
+      status: Some(StatusCode::OK),
+    }
+  );
+}
+
+#[tokio::test]
+async fn calls_that_are_never_sent_are_recorded() {
+  let mut client = LemmyClient::with_transport(
+    ClientOptions {
+      api_version: ApiVersionMode::Fixed(ApiVersion::V3),
+      ..options()
+    },
+    Arc::new(Overloaded::default()),
+  );
+  let recorder = Recorder::default();
+  client.set_metrics_recorder(recorder.clone());
+
+  client.list_posts(GetPosts::default()).await.unwrap_err();
+  assert_eq!(
+    recorder.last(),
+    Recorded {
+      endpoint: Endpoint::ListPosts,
+      outcome: "unsupported_on_server_version".to_owned(),
+      retries: 0,
+      bytes_sent: 0,
+      bytes_received: 0,
+      status: None,
+    }
+  );
+
+  let token = CancellationToken::new();
+  token.cancel();
+  client
+    .with_cancellation(&token)
+    .get_site()
+    .await
+    .unwrap_err();
+  let recorded = recorder.last();
+  assert_eq!(recorded.endpoint, Endpoint::GetSite);
+  assert_eq!(recorded.outcome, "cancelled");
+}
+
+#[tokio::test]
+async fn calls_that_time_out_are_recorded() {
+  let mut client = LemmyClient::with_transport(options(), Hanging);
+  let recorder = Recorder::default();
+  client.set_metrics_recorder(recorder.clone());
+
+  client
+    .with_deadline(Duration::from_millis(50))
+    .get_site()
+    .await
+    .unwrap_err();
+
+  let recorded = recorder.last();
+  assert_eq!(recorded.endpoint, Endpoint::GetSite);
+  assert_eq!(recorded.outcome, "timeout");
+  assert_eq!(recorded.status, None);
+}
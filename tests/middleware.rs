@@ -0,0 +1,63 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+mod common;
+
+use bytes::Bytes;
+use common::{PASSWORD, login, register};
+use http::{Request, Response};
+use lemmy_client::{
+  Endpoint,
+  LemmyResult,
+  Middleware,
+  RequestContext,
+  testing::{MockServer, REDACTED},
+};
+use serde_json::Value;
+use std::{
+  ops::ControlFlow,
+  sync::{Arc, Mutex},
+};
+
+/// Keeps the data of every call it sees.
+#[derive(Clone, Default)]
+struct Spy(Arc<Mutex<Vec<(Endpoint, Value)>>>);
+
+impl Middleware for Spy {
+  fn before_request(
+    &self,
+    context: &RequestContext,
+    _request: &mut Request<Vec<u8>>,
+  ) -> ControlFlow<LemmyResult<Response<Bytes>>> {
+    self
+      .0
+      .lock()
+      .unwrap()
+      .push((context.endpoint, context.data.clone()));
+
+    ControlFlow::Continue(())
+  }
+}
+
+#[tokio::test]
+async fn middleware_do_not_see_secrets() {
+  let mut client = MockServer::new().client();
+  let spy = Spy::default();
+  client.add_middleware(spy.clone());
+
+  register(&mut client, "alice").await;
+  // Only the data is redacted, not the request.
+  client.login(login("alice")).await.unwrap();
+
+  let calls = spy.0.lock().unwrap();
+  let (endpoint, register) = &calls[0];
+  assert_eq!(*endpoint, Endpoint::RegisterAccount);
+  assert_eq!(register["username"], "alice");
+  assert_eq!(register["password"], REDACTED);
+  assert_eq!(register["password_verify"], REDACTED);
+
+  let (endpoint, login) = &calls[1];
+  assert_eq!(*endpoint, Endpoint::Login);
+  assert_eq!(login["username_or_email"], "alice");
+  assert_eq!(login["password"], REDACTED);
+  assert!(!format!("{calls:?}").contains(PASSWORD));
+}
@@ -0,0 +1,96 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+mod common;
+
+use common::{create_community, create_post, login, register};
+use lemmy_client::{
+  LemmyClient,
+  lemmy_api_common::{error::LemmyErrorType, post::GetPosts},
+  testing::MockServer,
+};
+
+/// Logs in with the right and the wrong password.
+async fn check_login(mut client: LemmyClient) {
+  register(&mut client, "alice").await;
+  client.clear_jwt();
+
+  let response = client.login(login("alice")).await.unwrap();
+  let jwt = response.jwt.unwrap().into_inner();
+  client.set_jwt(&jwt).unwrap();
+  client.validate_auth().await.unwrap();
+
+  let mut wrong = login("alice");
+  wrong.password = "wrong password".to_owned().into();
+  let error = client.login(wrong).await.unwrap_err();
+  assert_eq!(error.api_error(), Some(&LemmyErrorType::IncorrectLogin));
+}
+
+/// Creates posts in two communities and lists them.
+async fn check_posts(mut client: LemmyClient) {
+  register(&mut client, "alice").await;
+  let rust = create_community(&client, "rust").await;
+  let lemmy = create_community(&client, "lemmy").await;
+  let first = create_post(&client, rust, "First").await;
+  let second = create_post(&client, lemmy, "Second").await;
+
+  let posts = client.list_posts(GetPosts::default()).await.unwrap();
+  let ids = posts
+    .items
+    .iter()
+    .map(|view| view.post.id)
+    .collect::<Vec<_>>();
+  assert_eq!(ids, [second, first]);
+
+  let posts = client
+    .list_posts(GetPosts {
+      community_id: Some(rust),
+      ..Default::default()
+    })
+    .await
+    .unwrap();
+  assert_eq!(posts.items.len(), 1);
+  assert_eq!(posts.items[0].post.name, "First");
+  assert_eq!(posts.items[0].community.id, rust);
+}
+
+#[tokio::test]
+async fn login_in_process() {
+  check_login(MockServer::new().client()).await;
+}
+
+#[tokio::test]
+async fn login_over_http() {
+  let server = MockServer::new().start().unwrap();
+  check_login(server.client()).await;
+}
+
+#[tokio::test]
+async fn posts_in_process() {
+  check_posts(MockServer::new().client()).await;
+}
+
+#[tokio::test]
+async fn posts_over_http() {
+  let server = MockServer::new().start().unwrap();
+  check_posts(server.client()).await;
+}
+
+#[tokio::test]
+async fn create_post_requires_login() {
+  let server = MockServer::new();
+  let mut alice = server.client();
+  register(&mut alice, "alice").await;
+  let community_id = create_community(&alice, "rust").await;
+
+  let error = server
+    .client()
+    .create_post(lemmy_client::lemmy_api_common::post::actions::CreatePost {
+      name: "Anonymous".to_owned(),
+      community_id,
+      ..Default::default()
+    })
+    .await
+    .unwrap_err();
+  assert_eq!(error.api_error(), Some(&LemmyErrorType::NotLoggedIn));
+  assert_eq!(error.status(), Some(http::StatusCode::UNAUTHORIZED));
+}
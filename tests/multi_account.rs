@@ -0,0 +1,145 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+mod common;
+
+use bytes::Bytes;
+use common::{login, register};
+use http::{Request, header::AUTHORIZATION};
+use lemmy_client::{
+  ClientError,
+  LemmyClient,
+  MultiAccountClient,
+  Transport,
+  TransportFuture,
+  lemmy_api_common::error::LemmyErrorType,
+  testing::MockServer,
+};
+use std::sync::{Arc, Mutex};
+
+/// Passes requests on to a [`MockServer`], recording the `Authorization` header of each.
+#[derive(Clone, Default)]
+struct Authorizations {
+  server: MockServer,
+  seen: Arc<Mutex<Vec<Option<String>>>>,
+}
+
+impl Authorizations {
+  fn seen(&self) -> Vec<Option<String>> {
+    std::mem::take(&mut self.seen.lock().unwrap())
+  }
+}
+
+impl Transport for Authorizations {
+  fn send(&self, request: Request<Vec<u8>>) -> TransportFuture<'_> {
+    self.seen.lock().unwrap().push(
+      request
+        .headers()
+        .get(AUTHORIZATION)
+        .map(|value| value.to_str().unwrap().to_owned()),
+    );
+    let response = self.server.handle(&request).map(Bytes::from);
+
+    Box::pin(async move { Ok(response) })
+  }
+}
+
+/// Registers alice and bob, and logs both of them in with a [`MultiAccountClient`].
+async fn alice_and_bob() -> (MultiAccountClient, Authorizations, [String; 2]) {
+  let transport = Authorizations::default();
+  let client = LemmyClient::with_transport(common::mock_options(), transport.clone());
+  register(&mut transport.server.client(), "alice").await;
+  register(&mut transport.server.client(), "bob").await;
+
+  let accounts = MultiAccountClient::new(client);
+  let mut jwts = Vec::new();
+  for name in ["alice", "bob"] {
+    let response = accounts.login(name, login(name)).await.unwrap();
+    jwts.push(format!("Bearer {}", &*response.jwt.unwrap()));
+  }
+  transport.seen();
+
+  (accounts, transport, jwts.try_into().unwrap())
+}
+
+async fn username(client: &LemmyClient) -> String {
+  client
+    .get_current_user()
+    .await
+    .unwrap()
+    .local_user_view
+    .person
+    .name
+}
+
+#[tokio::test]
+async fn each_account_sends_its_own_jwt() {
+  let (accounts, transport, [alice, bob]) = alice_and_bob().await;
+  assert_eq!(accounts.accounts(), ["alice", "bob"]);
+
+  assert_eq!(username(&accounts.as_user("bob").unwrap()).await, "bob");
+  assert_eq!(username(&accounts.as_user("alice").unwrap()).await, "alice");
+  accounts.client().get_site().await.unwrap();
+
+  assert_eq!(transport.seen(), [Some(bob), Some(alice), None]);
+}
+
+#[tokio::test]
+async fn operations_run_as_every_account() {
+  let (accounts, transport, [alice, bob]) = alice_and_bob().await;
+
+  let names = accounts
+    .for_each_account(|_, client| async move { username(&client).await })
+    .await;
+
+  assert_eq!(names.len(), 2);
+  assert_eq!(names["alice"], "alice");
+  assert_eq!(names["bob"], "bob");
+  let mut seen = transport.seen();
+  seen.sort();
+  assert_eq!(seen, [Some(alice), Some(bob)]);
+}
+
+#[tokio::test]
+async fn removed_accounts_are_no_longer_used() {
+  let (accounts, transport, [_, bob]) = alice_and_bob().await;
+
+  let alice = accounts.remove_account("alice").unwrap();
+  assert!(accounts.as_user("alice").is_none());
+  assert!(accounts.remove_account("alice").is_none());
+  assert_eq!(accounts.accounts(), ["bob"]);
+
+  let names = accounts
+    .for_each_account(|_, client| async move { username(&client).await })
+    .await;
+  assert_eq!(names.into_keys().collect::<Vec<_>>(), ["bob"]);
+  assert_eq!(transport.seen(), [Some(bob)]);
+
+  // Removing an account doesn't log it out.
+  assert_eq!(username(&alice).await, "alice");
+}
+
+#[tokio::test]
+async fn failed_logins_register_nothing() {
+  let (accounts, _, _) = alice_and_bob().await;
+
+  let mut wrong = login("carol");
+  wrong.password = "wrong password".to_owned().into();
+  let error = accounts.login("carol", wrong).await.unwrap_err();
+  assert!(matches!(
+    error,
+    ClientError::Api {
+      error: LemmyErrorType::IncorrectLogin,
+      ..
+    }
+  ));
+
+  let mut wrong = login("alice");
+  wrong.password = "wrong password".to_owned().into();
+  accounts.login("alice", wrong).await.unwrap_err();
+
+  assert_eq!(accounts.accounts(), ["alice", "bob"]);
+  assert!(accounts.as_user("carol").is_none());
+  // The account that was already registered keeps its client.
+  let alice = accounts.as_user("alice").unwrap();
+  assert_eq!(username(&alice).await, "alice");
+}
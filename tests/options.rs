@@ -0,0 +1,132 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use lemmy_client::{ClientError, ClientOptions, HttpOptions, LemmyClient, RetryPolicy};
+use std::{
+  io::{Read, Write},
+  net::TcpListener,
+  sync::mpsc::{self, Receiver},
+  thread,
+};
+
+fn invalid_proxy() -> ClientOptions<&'static str> {
+  ClientOptions {
+    http: HttpOptions {
+      proxy: Some("not a url".to_owned()),
+      ..HttpOptions::default()
+    },
+    ..ClientOptions::new("lemmy.ml")
+  }
+}
+
+#[test]
+fn try_new_rejects_invalid_options() {
+  let result = LemmyClient::try_new(invalid_proxy());
+  assert!(matches!(result, Err(ClientError::InvalidOptions(_))));
+
+  let result = LemmyClient::try_new(ClientOptions::new("lemmy.ml/path"));
+  assert!(matches!(result, Err(ClientError::InvalidOptions(_))));
+}
+
+#[tokio::test]
+async fn new_fails_requests_with_invalid_options() {
+  let client = LemmyClient::new(invalid_proxy());
+  for _ in 0..2 {
+    let error = client.get_site().await.unwrap_err();
+    assert!(matches!(error, ClientError::InvalidOptions(_)));
+  }
+
+  let client = LemmyClient::new(ClientOptions::new("lemmy.ml/path"));
+  let error = client.get_site().await.unwrap_err();
+  assert!(matches!(error, ClientError::InvalidOptions(_)));
+}
+
+/// What a [`listen`] server received on a connection.
+#[derive(Debug, PartialEq)]
+enum Received {
+  /// The request line of a plain HTTP request.
+  Http(String),
+  /// A TLS handshake.
+  Tls,
+}
+
+/// Serves a port on localhost that answers every HTTP request with a `not_found` error, reporting
+/// what it received on each connection.
+fn listen() -> (u16, Receiver<Received>) {
+  let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+  let port = listener.local_addr().unwrap().port();
+  let (sender, receiver) = mpsc::channel();
+
+  thread::spawn(move || {
+    for stream in listener.incoming() {
+      let mut stream = stream.unwrap();
+      let mut head = Vec::new();
+      let mut byte = [0];
+      while !head.ends_with(b"\r\n\r\n") && stream.read(&mut byte).unwrap_or(0) == 1 {
+        head.push(byte[0]);
+        // TLS handshake records start with 0x16.
+        if head == [0x16] {
+          break;
+        }
+      }
+
+      if head == [0x16] {
+        let _ = sender.send(Received::Tls);
+        continue;
+      }
+      let request_line = String::from_utf8_lossy(&head)
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .to_owned();
+      let _ = sender.send(Received::Http(request_line));
+      let body = r#"{"error":"not_found"}"#;
+      let _ = write!(
+        stream,
+        "HTTP/1.1 404 Not Found\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+        body.len()
+      );
+    }
+  });
+
+  (port, receiver)
+}
+
+fn client(url: &str) -> LemmyClient {
+  LemmyClient::try_new(ClientOptions {
+    retry_policy: RetryPolicy::never(),
+    ..ClientOptions::from_url(url).unwrap()
+  })
+  .unwrap()
+}
+
+#[tokio::test]
+async fn base_urls_with_a_path_prefix_are_requested_under_it() {
+  for base_url in ["/lemmy", "/lemmy/"] {
+    let (port, received) = listen();
+    let url = format!("http://127.0.0.1:{port}{base_url}");
+    let options = ClientOptions::from_url(url.as_str()).unwrap();
+    assert_eq!(options.domain, format!("127.0.0.1:{port}"));
+    assert!(!options.secure);
+
+    let error = client(&url).get_site().await.unwrap_err();
+    assert_eq!(error.status(), Some(http::StatusCode::NOT_FOUND), "{url}");
+    assert_eq!(
+      received.recv().unwrap(),
+      Received::Http("GET /lemmy/api/v4/site HTTP/1.1".to_owned()),
+      "{url}"
+    );
+  }
+}
+
+#[tokio::test]
+async fn https_base_urls_are_requested_over_tls() {
+  let (port, received) = listen();
+  let url = format!("https://127.0.0.1:{port}/lemmy");
+  let options = ClientOptions::from_url(url.as_str()).unwrap();
+  assert_eq!(options.domain, format!("127.0.0.1:{port}"));
+  assert!(options.secure);
+
+  let error = client(&url).get_site().await.unwrap_err();
+  assert!(matches!(error, ClientError::Transport(_)), "{error:?}");
+  assert_eq!(received.recv().unwrap(), Received::Tls);
+}
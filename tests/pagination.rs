@@ -0,0 +1,98 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+mod common;
+
+use common::{create_community, create_post, register};
+use futures_util::{TryStreamExt, pin_mut};
+use lemmy_client::{
+  LemmyClient,
+  PaginationOptions,
+  lemmy_api_common::post::{GetPosts, PostId},
+  paginate_pages,
+  testing::MockServer,
+};
+
+/// Registers an account and creates `count` posts with it, returning their ids newest first.
+async fn posts(client: &mut LemmyClient, count: usize) -> Vec<PostId> {
+  register(client, "alice").await;
+  let community = create_community(client, "rust").await;
+
+  let mut ids = Vec::new();
+  for i in 0..count {
+    ids.push(create_post(client, community, &format!("Post {i}")).await);
+  }
+  ids.reverse();
+
+  ids
+}
+
+fn page_size(page_size: i64) -> PaginationOptions {
+  PaginationOptions {
+    page_size: Some(page_size),
+    ..Default::default()
+  }
+}
+
+#[tokio::test]
+async fn every_item_is_fetched_across_pages() {
+  let mut client = MockServer::new().client();
+  let expected = posts(&mut client, 5).await;
+
+  let ids: Vec<_> = client
+    .list_posts_stream(GetPosts::default(), page_size(2))
+    .map_ok(|view| view.post.id)
+    .try_collect()
+    .await
+    .unwrap();
+  assert_eq!(ids, expected);
+}
+
+#[tokio::test]
+async fn max_items_stops_early() {
+  let mut client = MockServer::new().client();
+  let expected = posts(&mut client, 5).await;
+
+  let options = PaginationOptions {
+    max_items: Some(3),
+    ..page_size(2)
+  };
+  let ids: Vec<_> = client
+    .list_posts_stream(GetPosts::default(), options)
+    .map_ok(|view| view.post.id)
+    .try_collect()
+    .await
+    .unwrap();
+  assert_eq!(ids, expected[..3]);
+}
+
+#[tokio::test]
+async fn pages_can_be_resumed_from_a_cursor() {
+  let mut client = MockServer::new().client();
+  let expected = posts(&mut client, 5).await;
+
+  let pages = paginate_pages(GetPosts::default(), page_size(2), |request| {
+    client.list_posts(request)
+  });
+  pin_mut!(pages);
+  let first = pages.try_next().await.unwrap().unwrap();
+  assert_eq!(first.items.len(), 2);
+
+  let options = PaginationOptions {
+    start_cursor: first.next_page,
+    ..page_size(2)
+  };
+  let ids: Vec<_> = paginate_pages(GetPosts::default(), options, |request| {
+    client.list_posts(request)
+  })
+  .map_ok(|page| {
+    page
+      .items
+      .iter()
+      .map(|view| view.post.id)
+      .collect::<Vec<_>>()
+  })
+  .try_collect()
+  .await
+  .unwrap();
+  assert_eq!(ids, [expected[2..4].to_vec(), expected[4..].to_vec()]);
+}
@@ -0,0 +1,150 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use futures_util::future::join_all;
+use http::Request;
+use lemmy_client::{
+  ApiVersion,
+  ApiVersionMode,
+  ClientError,
+  Endpoint,
+  HttpOptions,
+  LemmyClientPool,
+  PoolOptions,
+  Transport,
+  TransportFuture,
+  lemmy_api_common::post::GetPosts,
+  testing::MockServer,
+};
+use std::{
+  sync::{Arc, Mutex},
+  time::Duration,
+};
+
+/// Answers from a [`MockServer`] after a short delay, keeping track of how many requests are in
+/// flight at once and of the order they arrived in.
+#[derive(Clone, Default)]
+struct Slow {
+  server: MockServer,
+  state: Arc<Mutex<SlowState>>,
+}
+
+#[derive(Default)]
+struct SlowState {
+  in_flight: usize,
+  max_in_flight: usize,
+  queries: Vec<String>,
+}
+
+impl Transport for Slow {
+  fn send(&self, request: Request<Vec<u8>>) -> TransportFuture<'_> {
+    Box::pin(async move {
+      {
+        let mut state = self.state.lock().unwrap();
+        state.in_flight += 1;
+        state.max_in_flight = state.max_in_flight.max(state.in_flight);
+        let query = request.uri().query().unwrap_or_default().to_owned();
+        state.queries.push(query);
+      }
+      tokio::time::sleep(Duration::from_millis(20)).await;
+      self.state.lock().unwrap().in_flight -= 1;
+
+      self.server.send(request).await
+    })
+  }
+}
+
+fn pool(transport: &Slow, permits: usize) -> LemmyClientPool {
+  LemmyClientPool::with_transport(
+    PoolOptions {
+      max_concurrent_requests_per_host: Some(permits),
+      ..Default::default()
+    },
+    transport.clone(),
+  )
+}
+
+fn posts(limit: i64) -> GetPosts {
+  GetPosts {
+    limit: Some(limit),
+    ..Default::default()
+  }
+}
+
+#[tokio::test]
+async fn requests_to_one_host_wait_their_turn() {
+  let transport = Slow::default();
+  let pool = pool(&transport, 2);
+  let client = pool.client("mock.lemmy");
+
+  let results = join_all((1..=6).map(|limit| client.list_posts(posts(limit)))).await;
+  assert!(results.iter().all(Result::is_ok));
+
+  let state = transport.state.lock().unwrap();
+  assert_eq!(state.max_in_flight, 2);
+  let expected = (1..=6)
+    .map(|limit| format!("limit={limit}"))
+    .collect::<Vec<_>>();
+  assert_eq!(state.queries, expected);
+}
+
+#[tokio::test]
+async fn giving_up_while_waiting_keeps_the_queue_going() {
+  let transport = Slow::default();
+  let pool = pool(&transport, 1);
+  let client = pool.client("mock.lemmy");
+
+  let first = client.list_posts(posts(1));
+  let abandoned = tokio::time::timeout(Duration::from_millis(5), client.list_posts(posts(2)));
+  let third = client.list_posts(posts(3));
+  let (first, abandoned, third) = tokio::join!(first, abandoned, third);
+  assert!(first.is_ok());
+  assert!(abandoned.is_err());
+  assert!(third.is_ok());
+
+  // The permit handed to the abandoned request was passed on.
+  client.get_site().await.unwrap();
+
+  let state = transport.state.lock().unwrap();
+  assert_eq!(state.max_in_flight, 1);
+  assert_eq!(state.queries, ["limit=1", "limit=3", ""]);
+}
+
+#[tokio::test]
+async fn clients_talk_to_their_instance_with_the_pool_api_version() {
+  let transport = Slow::default();
+  let pool = LemmyClientPool::with_transport(
+    PoolOptions {
+      api_version: ApiVersionMode::Fixed(ApiVersion::V3),
+      ..Default::default()
+    },
+    transport.clone(),
+  );
+  let client = pool.client("mock.lemmy");
+
+  assert_eq!(client.api_version(), Some(ApiVersion::V3));
+  let error = client.list_posts(posts(1)).await.unwrap_err();
+  assert!(matches!(
+    error,
+    ClientError::UnsupportedOnServerVersion {
+      endpoint: Endpoint::ListPosts,
+      version: ApiVersion::V3,
+    }
+  ));
+  assert!(transport.state.lock().unwrap().queries.is_empty());
+}
+
+#[tokio::test]
+async fn pools_connect_with_their_http_options() {
+  let pool = LemmyClientPool::new(PoolOptions {
+    http: HttpOptions {
+      proxy: Some("not a url".to_owned()),
+      ..HttpOptions::default()
+    },
+    ..Default::default()
+  });
+
+  for domain in ["lemmy.ml", "lemmy.world"] {
+    let error = pool.client(domain).get_site().await.unwrap_err();
+    assert!(matches!(error, ClientError::InvalidOptions(_)), "{error:?}");
+  }
+}
@@ -0,0 +1,98 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+mod common;
+
+use common::{create_community, create_post, register};
+use lemmy_client::{
+  ClientOptions,
+  LemmyClient,
+  Limit,
+  RateLimitMode,
+  RateLimits,
+  testing::MockServer,
+};
+use std::time::{Duration, Instant};
+
+fn client(server: &MockServer, rate_limit: RateLimitMode) -> LemmyClient {
+  LemmyClient::with_transport(
+    ClientOptions {
+      rate_limit,
+      ..common::mock_options()
+    },
+    server.clone(),
+  )
+}
+
+fn post_limit(max_requests: u32, interval: Duration) -> RateLimitMode {
+  RateLimitMode::Fixed(RateLimits {
+    post: Some(Limit {
+      max_requests,
+      interval,
+    }),
+    ..Default::default()
+  })
+}
+
+#[tokio::test]
+async fn fixed_limits_throttle_their_category() {
+  let server = MockServer::new();
+  let mut client = client(&server, post_limit(2, Duration::from_millis(400)));
+  register(&mut client, "alice").await;
+  let community = create_community(&client, "rust").await;
+
+  // The third post waits for a token, which refills every 200ms.
+  let start = Instant::now();
+  for name in ["First", "Second", "Third"] {
+    create_post(&client, community, name).await;
+  }
+  assert!(start.elapsed() >= Duration::from_millis(150));
+}
+
+#[tokio::test]
+async fn fixed_limits_leave_other_categories_alone() {
+  let server = MockServer::new();
+  let mut client = client(&server, post_limit(1, Duration::from_secs(3600)));
+  register(&mut client, "alice").await;
+  let community = create_community(&client, "rust").await;
+  create_post(&client, community, "First").await;
+
+  // Posting again waits for an hour, while the site is fetched right away.
+  let throttled = tokio::time::timeout(
+    Duration::from_millis(100),
+    create_post(&client, community, "Second"),
+  )
+  .await;
+  assert!(throttled.is_err());
+  let sites = tokio::time::timeout(Duration::from_secs(30), async {
+    for _ in 0..10 {
+      client.get_site().await.unwrap();
+    }
+  })
+  .await;
+  assert!(sites.is_ok());
+}
+
+#[tokio::test]
+async fn site_limits_apply_once_the_site_was_fetched() {
+  let server = MockServer::new();
+  let mut client = client(&server, RateLimitMode::FromSite);
+  register(&mut client, "alice").await;
+  let community = create_community(&client, "rust").await;
+
+  // Not throttled before the limits are known.
+  for i in 0..8 {
+    create_post(&client, community, &format!("Post {i}")).await;
+  }
+
+  // The mock instance allows 6 posts every 10 minutes.
+  client.get_site().await.unwrap();
+  for i in 0..6 {
+    create_post(&client, community, &format!("Limited {i}")).await;
+  }
+  let throttled = tokio::time::timeout(
+    Duration::from_millis(200),
+    create_post(&client, community, "Throttled"),
+  )
+  .await;
+  assert!(throttled.is_err());
+}
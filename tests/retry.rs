@@ -0,0 +1,107 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+mod common;
+
+use common::{Failure, Flaky, login, mock_options, register};
+use http::StatusCode;
+use lemmy_client::{Backoff, ClientError, ClientOptions, RetryPolicy, testing::MockServer};
+use std::time::{Duration, Instant};
+
+/// A policy that retries right away, to keep the tests fast.
+fn policy(max_attempts: u32) -> RetryPolicy {
+  RetryPolicy {
+    max_attempts,
+    backoff: Backoff::Constant(Duration::ZERO),
+    jitter: 0.0,
+    ..RetryPolicy::default()
+  }
+}
+
+fn options(retry_policy: RetryPolicy) -> ClientOptions<&'static str> {
+  ClientOptions {
+    retry_policy,
+    ..mock_options()
+  }
+}
+
+#[tokio::test]
+async fn transport_errors_are_retried() {
+  let transport = Flaky::new(MockServer::new());
+  let client = transport.client(options(policy(3)));
+
+  transport.fail(2, Failure::Transport);
+  client.get_site().await.unwrap();
+  assert_eq!(transport.requests(), 3);
+}
+
+#[tokio::test]
+async fn retries_stop_after_max_attempts() {
+  let transport = Flaky::new(MockServer::new());
+  let client = transport.client(options(policy(3)));
+
+  transport.fail(5, Failure::Status(StatusCode::SERVICE_UNAVAILABLE, None));
+  let error = client.get_site().await.unwrap_err();
+  assert_eq!(error.status(), Some(StatusCode::SERVICE_UNAVAILABLE));
+  assert_eq!(transport.requests(), 3);
+}
+
+#[tokio::test]
+async fn never_policy_sends_once() {
+  let transport = Flaky::new(MockServer::new());
+  let client = transport.client(options(RetryPolicy::never()));
+
+  transport.fail(1, Failure::Transport);
+  let error = client.get_site().await.unwrap_err();
+  assert!(matches!(error, ClientError::Transport(_)));
+  assert_eq!(transport.requests(), 1);
+}
+
+#[tokio::test]
+async fn non_idempotent_requests_are_only_retried_when_rate_limited() {
+  let server = MockServer::new();
+  register(&mut server.client(), "alice").await;
+  let transport = Flaky::new(server);
+  let client = transport.client(options(policy(3)));
+
+  transport.fail(1, Failure::Status(StatusCode::BAD_GATEWAY, None));
+  let error = client.login(login("alice")).await.unwrap_err();
+  assert_eq!(error.status(), Some(StatusCode::BAD_GATEWAY));
+  assert_eq!(transport.requests(), 1);
+
+  transport.fail(1, Failure::Status(StatusCode::TOO_MANY_REQUESTS, None));
+  client.login(login("alice")).await.unwrap();
+  assert_eq!(transport.requests(), 3);
+}
+
+#[tokio::test]
+async fn retry_after_is_respected() {
+  let transport = Flaky::new(MockServer::new());
+  let client = transport.client(options(policy(2)));
+
+  transport.fail(
+    1,
+    Failure::Status(StatusCode::SERVICE_UNAVAILABLE, Some("1")),
+  );
+  let start = Instant::now();
+  client.get_site().await.unwrap();
+  assert!(start.elapsed() >= Duration::from_secs(1));
+  assert_eq!(transport.requests(), 2);
+}
+
+#[tokio::test]
+async fn retry_after_is_capped_by_max_delay() {
+  let transport = Flaky::new(MockServer::new());
+  let client = transport.client(options(RetryPolicy {
+    max_delay: Duration::from_millis(10),
+    ..policy(2)
+  }));
+
+  transport.fail(
+    1,
+    Failure::Status(StatusCode::SERVICE_UNAVAILABLE, Some("3600")),
+  );
+  let start = Instant::now();
+  client.get_site().await.unwrap();
+  assert!(start.elapsed() < Duration::from_secs(60));
+  assert_eq!(transport.requests(), 2);
+}
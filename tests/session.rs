@@ -0,0 +1,224 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+mod common;
+
+use common::{login, register};
+use lemmy_client::{
+  ClientError,
+  CredentialStore,
+  FileStore,
+  LemmyClient,
+  MemoryStore,
+  Session,
+  SessionData,
+  testing::MockServer,
+};
+use std::{io, path::PathBuf};
+
+/// A path for a session file that is removed once the test is done with it.
+struct TempPath(PathBuf);
+
+impl TempPath {
+  fn new(name: &str) -> Self {
+    Self(std::env::temp_dir().join(format!(
+      "lemmy-client-session-{name}-{}",
+      std::process::id()
+    )))
+  }
+}
+
+impl Drop for TempPath {
+  fn drop(&mut self) {
+    let _ = std::fs::remove_file(&self.0);
+  }
+}
+
+/// Keeps the session in memory, but fails to remove it.
+#[derive(Default)]
+struct Unclearable(MemoryStore);
+
+impl CredentialStore for Unclearable {
+  fn load(&self) -> io::Result<Option<SessionData>> {
+    self.0.load()
+  }
+
+  fn save(&self, session: &SessionData) -> io::Result<()> {
+    self.0.save(session)
+  }
+
+  fn clear(&self) -> io::Result<()> {
+    Err(io::Error::from(io::ErrorKind::PermissionDenied))
+  }
+}
+
+#[tokio::test]
+async fn session_is_persisted_and_restored() {
+  let server = MockServer::new();
+  register(&mut server.client(), "alice").await;
+
+  let path = TempPath::new("persisted");
+  let mut client = server.client();
+  client.set_session(Session::new(FileStore::new(&path.0)));
+  client.start_session(login("alice")).await.unwrap();
+  assert!(client.session().is_active());
+  assert_eq!(
+    client.session().user().unwrap().local_user_view.person.name,
+    "alice"
+  );
+
+  // A new client picks the session up from the store.
+  let mut restored = server.client();
+  restored.set_session(Session::new(FileStore::new(&path.0)));
+  let data = restored.restore_session().await.unwrap().unwrap();
+  assert_eq!(data.login.jwt, client.session().jwt());
+  restored.get_current_user().await.unwrap();
+
+  restored.end_session().await.unwrap();
+  assert!(!restored.session().is_active());
+  assert!(FileStore::new(&path.0).load().unwrap().is_none());
+  // Logging out invalidated the JWT for the first client too.
+  assert!(client.get_current_user().await.is_err());
+}
+
+/// Logs the session's JWT out from another client, so that the instance no longer accepts it.
+async fn revoke(client: &LemmyClient) {
+  let jwt = client.session().jwt().unwrap();
+  client.as_user(&jwt).unwrap().logout().await.unwrap();
+}
+
+#[tokio::test]
+async fn session_logs_in_again_when_jwt_is_revoked() {
+  let server = MockServer::new();
+  register(&mut server.client(), "alice").await;
+
+  let mut client = server.client();
+  client.set_session(Session::new(MemoryStore::default()).relogin(true));
+  client.start_session(login("alice")).await.unwrap();
+  let old_jwt = client.session().jwt();
+
+  revoke(&client).await;
+  client.get_current_user().await.unwrap();
+  assert_ne!(client.session().jwt(), old_jwt);
+  assert!(!client.session().is_expired());
+}
+
+#[tokio::test]
+async fn session_expires_without_relogin() {
+  let server = MockServer::new();
+  register(&mut server.client(), "alice").await;
+
+  let mut client = server.client();
+  client.set_session(Session::new(MemoryStore::default()));
+  client.start_session(login("alice")).await.unwrap();
+
+  revoke(&client).await;
+  let error = client.get_current_user().await.unwrap_err();
+  assert!(matches!(error, ClientError::Api { .. }));
+  assert!(client.session().is_expired());
+}
+
+#[tokio::test]
+async fn relogin_persists_the_new_jwt() {
+  let server = MockServer::new();
+  register(&mut server.client(), "alice").await;
+
+  let path = TempPath::new("relogin");
+  let mut client = server.client();
+  client.set_session(Session::new(FileStore::new(&path.0)).relogin(true));
+  client.start_session(login("alice")).await.unwrap();
+  let stored = FileStore::new(&path.0).load().unwrap().unwrap();
+  assert!(stored.credentials.is_some());
+
+  revoke(&client).await;
+  client.get_current_user().await.unwrap();
+  let stored = FileStore::new(&path.0).load().unwrap().unwrap();
+  assert_eq!(stored.login.jwt, client.session().jwt());
+  assert_ne!(stored.login.jwt, None);
+
+  // A restarted client picks up the new JWT rather than the revoked one.
+  let mut restored = server.client();
+  restored.set_session(Session::new(FileStore::new(&path.0)));
+  restored.restore_session().await.unwrap().unwrap();
+  assert!(!restored.session().is_expired());
+}
+
+#[tokio::test]
+async fn restoring_a_revoked_session_expires_it() {
+  let server = MockServer::new();
+  register(&mut server.client(), "alice").await;
+
+  let mut client = server.client();
+  client.set_session(Session::new(MemoryStore::default()));
+  client.start_session(login("alice")).await.unwrap();
+  let data = client.session().data().unwrap();
+  revoke(&client).await;
+
+  let store = MemoryStore::default();
+  store.save(&data).unwrap();
+  let mut restored = server.client();
+  restored.set_session(Session::new(store));
+  let error = restored.restore_session().await.unwrap_err();
+  assert!(matches!(error, ClientError::Api { .. }));
+  assert!(restored.session().is_expired());
+
+  // Ending an expired session doesn't ask the instance to log out a JWT it already rejected.
+  restored.end_session().await.unwrap();
+  assert!(!restored.session().is_active());
+  assert!(!restored.session().is_expired());
+}
+
+#[tokio::test]
+async fn corrupt_stores_fail_to_load() {
+  let path = TempPath::new("corrupt");
+  std::fs::write(&path.0, b"{\"login\":").unwrap();
+
+  let error = FileStore::new(&path.0).load().unwrap_err();
+  assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+
+  let mut client = MockServer::new().client();
+  client.set_session(Session::new(FileStore::new(&path.0)));
+  let error = client.restore_session().await.unwrap_err();
+  assert!(matches!(error, ClientError::CredentialStore(_)));
+  assert!(!client.session().is_active());
+}
+
+#[tokio::test]
+async fn saving_replaces_the_whole_file() {
+  let server = MockServer::new();
+  register(&mut server.client(), "alice").await;
+
+  let path = TempPath::new("replaced");
+  std::fs::write(&path.0, vec![b' '; 64 * 1024]).unwrap();
+  let mut client = server.client();
+  client.set_session(Session::new(FileStore::new(&path.0)));
+  client.start_session(login("alice")).await.unwrap();
+
+  assert!(FileStore::new(&path.0).load().unwrap().is_some());
+  #[cfg(unix)]
+  {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mode = std::fs::metadata(&path.0).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+  }
+  let prefix = path.0.file_name().unwrap().to_str().unwrap().to_owned();
+  let temps = std::fs::read_dir(std::env::temp_dir())
+    .unwrap()
+    .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+    .filter(|name| name.starts_with(&prefix) && name.ends_with(".tmp"))
+    .count();
+  assert_eq!(temps, 0);
+}
+
+#[tokio::test]
+async fn logout_succeeds_when_the_store_fails() {
+  let server = MockServer::new();
+  register(&mut server.client(), "alice").await;
+
+  let mut client = server.client();
+  client.set_session(Session::new(Unclearable::default()));
+  client.start_session(login("alice")).await.unwrap();
+
+  assert!(client.logout().await.unwrap().success);
+  assert!(!client.session().is_active());
+}
@@ -0,0 +1,93 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+mod common;
+
+use common::{PASSWORD, create_community, create_post, login, register};
+use lemmy_client::{
+  lemmy_api_common::post::actions::moderation::RemovePost,
+  testing::{MockServer, REDACTED},
+};
+use std::{
+  io,
+  sync::{Arc, Mutex},
+};
+use tracing_subscriber::{filter::LevelFilter, util::SubscriberInitExt};
+
+/// Collects everything logged on the current thread while it's alive.
+#[derive(Clone, Default)]
+struct Logs(Arc<Mutex<Vec<u8>>>);
+
+impl Logs {
+  fn capture(&self) -> impl Drop {
+    let logs = self.clone();
+
+    tracing_subscriber::fmt()
+      .with_max_level(LevelFilter::TRACE)
+      .with_writer(move || logs.clone())
+      .finish()
+      .set_default()
+  }
+
+  fn text(&self) -> String {
+    String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+  }
+}
+
+impl io::Write for Logs {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    self.0.lock().unwrap().extend_from_slice(buf);
+
+    Ok(buf.len())
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    Ok(())
+  }
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn credentials_are_not_logged() {
+  let logs = Logs::default();
+  let _guard = logs.capture();
+  let mut client = MockServer::new().client();
+
+  register(&mut client, "alice").await;
+  let jwt = client
+    .login(login("alice"))
+    .await
+    .unwrap()
+    .jwt
+    .unwrap()
+    .into_inner();
+  client.set_jwt(&jwt).unwrap();
+  client.validate_auth().await.unwrap();
+
+  let logs = logs.text();
+  assert!(logs.contains("sending request"));
+  assert!(!logs.contains(PASSWORD));
+  assert!(!logs.contains(&jwt));
+  assert!(logs.contains(REDACTED));
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn dry_runs_are_logged() {
+  let mut client = MockServer::new().client();
+  register(&mut client, "alice").await;
+  let community_id = create_community(&client, "community").await;
+  let post_id = create_post(&client, community_id, "post").await;
+
+  let logs = Logs::default();
+  let _guard = logs.capture();
+  let removal = RemovePost {
+    post_id,
+    removed: true,
+    reason: "Spam".to_owned(),
+    remove_children: None,
+  };
+  let report = client.bulk().dry_run(true).run([removal]).await;
+  assert!(report.is_success());
+
+  let logs = logs.text();
+  assert!(logs.contains("dry run, not sending request"));
+  assert!(logs.contains("Spam"));
+}
@@ -0,0 +1,128 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+mod common;
+
+use bytes::Bytes;
+use futures_util::TryStreamExt;
+use http::{Request, Response};
+use lemmy_client::{ClientError, LemmyClient, Transport, TransportFuture, UploadBody};
+use std::{
+  path::PathBuf,
+  sync::{Arc, Mutex},
+};
+
+const UPLOADED: &[u8] =
+  br#"{"image_url":"https://lemmy.test/pictrs/image/cat.png","filename":"cat.png"}"#;
+
+/// What an image host received.
+#[derive(Default)]
+struct Received {
+  buffered: usize,
+  chunks: Vec<usize>,
+  body: Vec<u8>,
+}
+
+/// Accepts every upload, reading streamed bodies a chunk at a time.
+#[derive(Clone, Default)]
+struct ImageHost(Arc<Mutex<Received>>);
+
+impl ImageHost {
+  fn client(&self) -> LemmyClient {
+    LemmyClient::with_transport(common::mock_options(), self.clone())
+  }
+
+  fn received(&self) -> std::sync::MutexGuard<'_, Received> {
+    self.0.lock().unwrap()
+  }
+}
+
+impl Transport for ImageHost {
+  fn send(&self, request: Request<Vec<u8>>) -> TransportFuture<'_> {
+    let mut received = self.received();
+    received.buffered += 1;
+    received.body = request.into_body();
+
+    Box::pin(async { Ok(Response::new(Bytes::from_static(UPLOADED))) })
+  }
+
+  fn send_streaming(&self, request: Request<UploadBody>) -> TransportFuture<'_> {
+    Box::pin(async move {
+      let mut chunks = request.into_body();
+      while let Some(chunk) = chunks
+        .try_next()
+        .await
+        .map_err(|e| ClientError::Transport(Box::new(e)))?
+      {
+        let mut received = self.received();
+        received.chunks.push(chunk.len());
+        received.body.extend_from_slice(&chunk);
+      }
+
+      Ok(Response::new(Bytes::from_static(UPLOADED)))
+    })
+  }
+}
+
+/// A file that is removed once the test is done with it.
+struct TempFile(PathBuf);
+
+impl TempFile {
+  fn new(name: &str, contents: &[u8]) -> Self {
+    let path = std::env::temp_dir().join(format!("lemmy-client-{name}-{}", std::process::id()));
+    std::fs::write(&path, contents).unwrap();
+
+    Self(path)
+  }
+}
+
+impl Drop for TempFile {
+  fn drop(&mut self) {
+    let _ = std::fs::remove_file(&self.0);
+  }
+}
+
+#[tokio::test]
+async fn files_are_streamed_in_chunks() {
+  let contents: Vec<u8> = (0..200_000).map(|i| i as u8).collect();
+  let file = TempFile::new("upload", &contents);
+  let progress = Arc::new(Mutex::new(Vec::new()));
+  let reported = progress.clone();
+  let body = UploadBody::from_path(&file.0)
+    .unwrap()
+    .on_progress(move |progress| reported.lock().unwrap().push(progress));
+
+  let host = ImageHost::default();
+  let image = host.client().upload_image(body).await.unwrap();
+
+  assert_eq!(image.filename, "cat.png");
+  let received = host.received();
+  assert_eq!(received.buffered, 0);
+  assert_eq!(received.chunks, [65_536, 65_536, 65_536, 3_392]);
+  assert_eq!(received.body, contents);
+  let progress = progress.lock().unwrap();
+  let sent: Vec<_> = progress.iter().map(|p| p.sent).collect();
+  assert_eq!(sent, [65_536, 131_072, 196_608, 200_000]);
+  assert!(progress.iter().all(|p| p.total == Some(200_000)));
+}
+
+#[test]
+fn missing_files_fail_before_uploading() {
+  let error = UploadBody::from_path(std::env::temp_dir().join("lemmy-client-missing.png"));
+
+  assert_eq!(error.unwrap_err().kind(), std::io::ErrorKind::NotFound);
+}
+
+#[tokio::test]
+async fn bodies_in_memory_are_sent_whole() {
+  let host = ImageHost::default();
+  host
+    .client()
+    .upload_image(UploadBody::from(&b"cat"[..]))
+    .await
+    .unwrap();
+
+  let received = host.received();
+  assert_eq!(received.buffered, 1);
+  assert_eq!(received.body, b"cat");
+  assert!(received.chunks.is_empty());
+}